@@ -0,0 +1,497 @@
+//! Generate Rust types from Avro schemas, so that the schemas stay the source of truth for the
+//! types of a program.
+//!
+//! Records become structs, enums become enums, fixed become newtype structs and unions become
+//! `Option<T>` when they are made of `null` and of one other type, or untagged enums otherwise.
+//! All of them derive `Serialize` and `Deserialize`, and the default values of record fields are
+//! used by `Deserialize` when a field is missing. Values are meant to be encoded and decoded
+//! straight under the guidance of their schema, with
+//! [`Writer::append_ser_direct`](https://docs.rs/avro-rs/*/avro_rs/struct.Writer.html#method.append_ser_direct)
+//! and [`Reader::into_deserialize_iter`](https://docs.rs/avro-rs/*/avro_rs/struct.Reader.html#method.into_deserialize_iter),
+//! or [`to_avro_datum_ser`](https://docs.rs/avro-rs/*/avro_rs/fn.to_avro_datum_ser.html) and
+//! [`from_avro_datum_de`](https://docs.rs/avro-rs/*/avro_rs/fn.from_avro_datum_de.html): unions,
+//! fixed and logical types do not go through `to_value` and `from_value`.
+//!
+//! The generated code depends on the `serde` crate, with its `derive` feature, and on the
+//! `serde_bytes` crate, which `bytes`, `fixed`, `decimal` and `duration` values are represented
+//! with.
+//!
+//! The generator is meant to be called from a build script:
+//!
+//! ```no_run
+//! // build.rs
+//! use std::{env, fs, path::Path};
+//!
+//! let code = avro_rs_codegen::generate_from_files(&["schemas/user.avsc"]).unwrap();
+//! let out_dir = env::var("OUT_DIR").unwrap();
+//! fs::write(Path::new(&out_dir).join("schemas.rs"), code).unwrap();
+//! println!("cargo:rerun-if-changed=schemas/user.avsc");
+//! ```
+//!
+//! and the generated types to be included in a module of the crate:
+//!
+//! ```ignore
+//! mod schemas {
+//!     include!(concat!(env!("OUT_DIR"), "/schemas.rs"));
+//! }
+//! ```
+//!
+//! The `avro-rs-codegen` binary does the same from the command line.
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+use avro_rs::schema::{Name, Schema, SchemaKind, UnionSchema};
+use heck::{CamelCase, SnakeCase};
+use serde_json::Value;
+use thiserror::Error;
+
+/// Errors which can happen while generating code.
+#[derive(Error, Debug)]
+pub enum Error {
+    /// A schema could not be parsed.
+    #[error(transparent)]
+    Schema(#[from] avro_rs::Error),
+
+    /// A schema file could not be read.
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
+    /// A schema has no Rust counterpart.
+    #[error("cannot generate code: {0}")]
+    Unsupported(String),
+}
+
+/// Generate the Rust types of the named types of `schemas` and of the unions they hold.
+///
+/// The schemas can refer to the named types defined by one another, as those returned by
+/// [`Schema::parse_list`](https://docs.rs/avro-rs/*/avro_rs/schema/enum.Schema.html#method.parse_list).
+pub fn generate(schemas: &[Schema]) -> Result<String, Error> {
+    let mut generator = Generator::default();
+    for schema in schemas {
+        generator.collect_names(schema)?;
+    }
+    for schema in schemas {
+        generator.rust_type(schema, "Root")?;
+    }
+    Ok(generator.finish())
+}
+
+/// Read and parse the `.avsc` files at `paths`, and generate the Rust types of their named types.
+pub fn generate_from_files<P: AsRef<Path>>(paths: &[P]) -> Result<String, Error> {
+    let inputs = paths
+        .iter()
+        .map(fs::read_to_string)
+        .collect::<Result<Vec<_>, _>>()?;
+    let inputs = inputs.iter().map(String::as_str).collect::<Vec<_>>();
+    generate(&Schema::parse_list(&inputs)?)
+}
+
+#[derive(Default)]
+struct Generator<'s> {
+    /// The named types, by fullname.
+    named: HashMap<String, &'s Schema>,
+    /// The Rust names of the named types, by fullname.
+    names: HashMap<String, String>,
+    /// The fullnames of the named types whose Rust type was already generated.
+    generated: HashSet<String>,
+    /// The fullnames of the records being generated, whose references are boxed.
+    stack: Vec<String>,
+    /// The generated items.
+    items: Vec<String>,
+}
+
+impl<'s> Generator<'s> {
+    /// Find the named types defined in `schema` and give them a Rust name.
+    fn collect_names(&mut self, schema: &'s Schema) -> Result<(), Error> {
+        match *schema {
+            Schema::Record {
+                ref name,
+                ref fields,
+                ..
+            } => {
+                self.add_name(name, schema)?;
+                for field in fields {
+                    self.collect_names(&field.schema)?;
+                }
+            }
+            Schema::Enum { ref name, .. } | Schema::Fixed { ref name, .. } => {
+                self.add_name(name, schema)?
+            }
+            Schema::Array(ref inner) | Schema::Map(ref inner) => self.collect_names(inner)?,
+            Schema::Decimal { ref inner, .. } => self.collect_names(inner)?,
+            Schema::Union(ref union) => {
+                for variant in union.variants() {
+                    self.collect_names(variant)?;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn add_name(&mut self, name: &Name, schema: &'s Schema) -> Result<(), Error> {
+        let fullname = name.fullname(None);
+        let rust_name = type_name(&name.name);
+        if self.names.values().any(|other| *other == rust_name) {
+            return Err(Error::Unsupported(format!(
+                "{} and another named type have the same Rust name {}",
+                fullname, rust_name
+            )));
+        }
+        self.named.insert(fullname.clone(), schema);
+        self.names.insert(fullname, rust_name);
+        Ok(())
+    }
+
+    fn finish(self) -> String {
+        let mut output = String::from("// This file is generated by avro-rs-codegen.\n");
+        for item in self.items {
+            output.push('\n');
+            output.push_str(&item);
+        }
+        output
+    }
+
+    /// Get the Rust type of values of `schema`, generating it if it is a named type or a union.
+    ///
+    /// `context` names the generated unions, which have no name in Avro.
+    fn rust_type(&mut self, schema: &'s Schema, context: &str) -> Result<String, Error> {
+        Ok(match *schema {
+            Schema::Null => "()".to_string(),
+            Schema::Boolean => "bool".to_string(),
+            Schema::Int | Schema::Date | Schema::TimeMillis => "i32".to_string(),
+            Schema::Long
+            | Schema::TimeMicros
+            | Schema::TimestampMillis
+            | Schema::TimestampMicros => "i64".to_string(),
+            Schema::Float => "f32".to_string(),
+            Schema::Double => "f64".to_string(),
+            Schema::String | Schema::Uuid => "String".to_string(),
+            Schema::Bytes | Schema::Decimal { .. } | Schema::Duration => {
+                "serde_bytes::ByteBuf".to_string()
+            }
+            Schema::Array(ref inner) => format!("Vec<{}>", self.rust_type(inner, context)?),
+            Schema::Map(ref inner) => format!(
+                "::std::collections::HashMap<String, {}>",
+                self.rust_type(inner, context)?
+            ),
+            Schema::Union(ref union) => {
+                let variants = union.variants();
+                if is_option(union) {
+                    let inner = variants.iter().find(|variant| **variant != Schema::Null);
+                    format!("Option<{}>", self.rust_type(inner.unwrap(), context)?)
+                } else {
+                    self.generate_union(variants, context)?
+                }
+            }
+            Schema::Record { ref name, .. }
+            | Schema::Enum { ref name, .. }
+            | Schema::Fixed { ref name, .. } => {
+                let fullname = name.fullname(None);
+                if self.generated.insert(fullname.clone()) {
+                    self.generate_named(schema, &fullname)?;
+                }
+                self.names[&fullname].clone()
+            }
+            Schema::Ref { ref name } => {
+                let fullname = name.fullname(None);
+                let rust_name = self
+                    .names
+                    .get(&fullname)
+                    .cloned()
+                    .ok_or_else(|| Error::Unsupported(format!("unknown type {}", fullname)))?;
+                if self.stack.contains(&fullname) {
+                    format!("Box<{}>", rust_name)
+                } else {
+                    rust_name
+                }
+            }
+            Schema::Annotated { ref inner, .. } => self.rust_type(inner, context)?,
+        })
+    }
+
+    fn generate_named(&mut self, schema: &'s Schema, fullname: &str) -> Result<(), Error> {
+        let rust_name = self.names[fullname].clone();
+        let mut item = String::new();
+        match *schema {
+            Schema::Record {
+                ref doc,
+                ref fields,
+                ..
+            } => {
+                self.stack.push(fullname.to_string());
+                let mut defaults = Vec::new();
+                write_doc(&mut item, doc.as_deref(), "");
+                item.push_str("#[derive(Clone, Debug, PartialEq, ::serde::Deserialize, ::serde::Serialize)]\n");
+                writeln!(item, "pub struct {} {{", rust_name).unwrap();
+                for field in fields {
+                    let ident = field_ident(&field.name);
+                    let context = format!("{}{}", rust_name, type_name(&field.name));
+                    let ty = self.rust_type(&field.schema, &context)?;
+                    write_doc(&mut item, field.doc.as_deref(), "    ");
+                    if ident.trim_start_matches("r#") != field.name {
+                        writeln!(item, "    #[serde(rename = {:?})]", field.name).unwrap();
+                    }
+                    if let Some(ref default) = field.default {
+                        let function = format!(
+                            "default_{}_{}",
+                            rust_name.to_snake_case(),
+                            ident.trim_start_matches("r#")
+                        );
+                        writeln!(item, "    #[serde(default = {:?})]", function).unwrap();
+                        let value = match field.schema {
+                            Schema::Union(ref union) if !is_option(union) => {
+                                self.union_default(&ty, union, default)?
+                            }
+                            _ => self.default_value(&field.schema, default)?,
+                        };
+                        defaults.push(format!(
+                            "fn {}() -> {} {{\n    {}\n}}\n",
+                            function, ty, value
+                        ));
+                    }
+                    writeln!(item, "    pub {}: {},", ident, ty).unwrap();
+                }
+                item.push_str("}\n");
+                for default in defaults {
+                    item.push('\n');
+                    item.push_str(&default);
+                }
+                self.stack.pop();
+            }
+            Schema::Enum {
+                ref doc,
+                ref symbols,
+                ..
+            } => {
+                write_doc(&mut item, doc.as_deref(), "");
+                item.push_str("#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, ::serde::Deserialize, ::serde::Serialize)]\n");
+                writeln!(item, "pub enum {} {{", rust_name).unwrap();
+                let mut variants = HashSet::new();
+                for symbol in symbols {
+                    let variant = type_name(symbol);
+                    if !variants.insert(variant.clone()) {
+                        return Err(Error::Unsupported(format!(
+                            "several symbols of {} are named {} in Rust",
+                            fullname, variant
+                        )));
+                    }
+                    if variant != *symbol {
+                        writeln!(item, "    #[serde(rename = {:?})]", symbol).unwrap();
+                    }
+                    writeln!(item, "    {},", variant).unwrap();
+                }
+                item.push_str("}\n");
+            }
+            Schema::Fixed { size, .. } => {
+                writeln!(item, "/// A fixed of {} bytes.", size).unwrap();
+                item.push_str("#[derive(Clone, Debug, Eq, Hash, PartialEq, ::serde::Deserialize, ::serde::Serialize)]\n");
+                writeln!(item, "pub struct {}(pub serde_bytes::ByteBuf);", rust_name).unwrap();
+            }
+            _ => unreachable!(),
+        }
+        self.items.push(item);
+        Ok(())
+    }
+
+    /// Generate an untagged enum for a union, with a variant per branch.
+    fn generate_union(&mut self, variants: &'s [Schema], context: &str) -> Result<String, Error> {
+        let rust_name = format!("{}Union", context);
+        let mut item = String::new();
+        item.push_str(
+            "#[derive(Clone, Debug, PartialEq, ::serde::Deserialize, ::serde::Serialize)]\n",
+        );
+        item.push_str("#[serde(untagged)]\n");
+        writeln!(item, "pub enum {} {{", rust_name).unwrap();
+        for variant in variants {
+            match *variant {
+                Schema::Null => item.push_str("    Null,\n"),
+                _ => {
+                    let ty = self.rust_type(variant, &rust_name)?;
+                    writeln!(item, "    {}({}),", self.variant_name(variant), ty).unwrap();
+                }
+            }
+        }
+        item.push_str("}\n");
+        self.items.push(item);
+        Ok(rust_name)
+    }
+
+    /// Get the Rust expression of the default value `value` of a union generated as the enum
+    /// `rust_name`, which is for its first branch.
+    fn union_default(
+        &self,
+        rust_name: &str,
+        union: &'s UnionSchema,
+        value: &Value,
+    ) -> Result<String, Error> {
+        Ok(match union.variants()[0] {
+            Schema::Null => format!("{}::Null", rust_name),
+            ref first => format!(
+                "{}::{}({})",
+                rust_name,
+                self.variant_name(first),
+                self.default_value(first, value)?
+            ),
+        })
+    }
+
+    /// Name the variant of a union enum holding values of `schema`.
+    fn variant_name(&self, schema: &Schema) -> String {
+        match *schema {
+            Schema::Record { ref name, .. }
+            | Schema::Enum { ref name, .. }
+            | Schema::Fixed { ref name, .. }
+            | Schema::Ref { ref name } => self.names[&name.fullname(None)].clone(),
+            Schema::Annotated { ref inner, .. } => self.variant_name(inner),
+            _ => format!("{:?}", SchemaKind::from(schema)),
+        }
+    }
+
+    /// Get the Rust expression of the default value `value` of a field of type `schema`.
+    fn default_value(&self, schema: &'s Schema, value: &Value) -> Result<String, Error> {
+        let unsupported = || {
+            Error::Unsupported(format!(
+                "default value {} does not match schema {:?}",
+                value, schema
+            ))
+        };
+        Ok(match (schema, value) {
+            (Schema::Null, Value::Null) => "()".to_string(),
+            (Schema::Boolean, Value::Bool(b)) => b.to_string(),
+            (
+                Schema::Int
+                | Schema::Date
+                | Schema::TimeMillis
+                | Schema::Long
+                | Schema::TimeMicros
+                | Schema::TimestampMillis
+                | Schema::TimestampMicros,
+                Value::Number(n),
+            ) if n.is_i64() => n.to_string(),
+            (Schema::Float | Schema::Double, Value::Number(n)) => {
+                format!("{:?}", n.as_f64().ok_or_else(unsupported)?)
+            }
+            (Schema::String | Schema::Uuid, Value::String(s)) => format!("{:?}.to_string()", s),
+            (Schema::Bytes | Schema::Decimal { .. } | Schema::Duration, Value::String(s)) => {
+                byte_buf(s)
+            }
+            (Schema::Fixed { ref name, .. }, Value::String(s)) => {
+                format!("{}({})", self.names[&name.fullname(None)], byte_buf(s))
+            }
+            (Schema::Array(inner), Value::Array(items)) => format!(
+                "vec![{}]",
+                items
+                    .iter()
+                    .map(|item| self.default_value(inner, item))
+                    .collect::<Result<Vec<_>, _>>()?
+                    .join(", ")
+            ),
+            (Schema::Map(inner), Value::Object(items)) => format!(
+                "vec![{}].into_iter().collect()",
+                items
+                    .iter()
+                    .map(|(key, item)| Ok(format!(
+                        "({:?}.to_string(), {})",
+                        key,
+                        self.default_value(inner, item)?
+                    )))
+                    .collect::<Result<Vec<_>, Error>>()?
+                    .join(", ")
+            ),
+            // The default value of a union is for its first branch.
+            (Schema::Union(union), _) if is_option(union) => match union.variants()[0] {
+                Schema::Null if *value == Value::Null => "None".to_string(),
+                Schema::Null => return Err(unsupported()),
+                ref first => format!("Some({})", self.default_value(first, value)?),
+            },
+            (Schema::Enum { name, symbols, .. }, Value::String(s)) if symbols.contains(s) => {
+                format!("{}::{}", self.names[&name.fullname(None)], type_name(s))
+            }
+            (Schema::Record { name, fields, .. }, Value::Object(object)) => {
+                let values = fields
+                    .iter()
+                    .map(|field| {
+                        let value = object
+                            .get(&field.name)
+                            .or(field.default.as_ref())
+                            .ok_or_else(unsupported)?;
+                        Ok(format!(
+                            "{}: {}",
+                            field_ident(&field.name),
+                            self.default_value(&field.schema, value)?
+                        ))
+                    })
+                    .collect::<Result<Vec<_>, Error>>()?;
+                format!(
+                    "{} {{ {} }}",
+                    self.names[&name.fullname(None)],
+                    values.join(", ")
+                )
+            }
+            (Schema::Ref { name }, _) => {
+                let fullname = name.fullname(None);
+                let expression = self.default_value(self.named[&fullname], value)?;
+                if self.stack.contains(&fullname) {
+                    format!("Box::new({})", expression)
+                } else {
+                    expression
+                }
+            }
+            (Schema::Annotated { inner, .. }, _) => self.default_value(inner, value)?,
+            _ => return Err(unsupported()),
+        })
+    }
+}
+
+/// Whether a union is generated as an `Option`, being made of `null` and of another type.
+fn is_option(union: &UnionSchema) -> bool {
+    union.variants().len() == 2 && union.variants().contains(&Schema::Null)
+}
+
+/// The Rust name of a named type or of an enum symbol.
+fn type_name(name: &str) -> String {
+    let name = name.rsplit('.').next().unwrap_or(name);
+    let camel = name.to_camel_case();
+    if camel.is_empty() {
+        name.to_string()
+    } else {
+        camel
+    }
+}
+
+/// The Rust identifier of a record field.
+fn field_ident(name: &str) -> String {
+    let snake = name.to_snake_case();
+    if KEYWORDS.contains(&snake.as_str()) {
+        format!("r#{}", snake)
+    } else {
+        snake
+    }
+}
+
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub",
+    "ref", "return", "static", "struct", "super", "trait", "true", "type", "unsafe", "use",
+    "where", "while",
+];
+
+fn write_doc(item: &mut String, doc: Option<&str>, indent: &str) {
+    for line in doc.into_iter().flat_map(str::lines) {
+        writeln!(item, "{}/// {}", indent, line).unwrap();
+    }
+}
+
+/// A `ByteBuf` holding the bytes of a default value, which are encoded as ISO-8859-1 in JSON.
+fn byte_buf(s: &str) -> String {
+    format!(
+        "serde_bytes::ByteBuf::from(vec![{}])",
+        s.chars()
+            .map(|c| (c as u32).to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    )
+}
@@ -0,0 +1,47 @@
+//! Generate Rust types from Avro schemas from the command line.
+//!
+//! ```text
+//! avro-rs-codegen [--output <file.rs>] <schema.avsc>...
+//! ```
+//!
+//! The generated code is written to the standard output, unless an output file is given.
+use std::env;
+use std::fs;
+use std::process;
+
+fn main() {
+    let mut output = None;
+    let mut paths = Vec::new();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-o" | "--output" => output = args.next(),
+            "-h" | "--help" => {
+                println!("usage: avro-rs-codegen [--output <file.rs>] <schema.avsc>...");
+                return;
+            }
+            _ => paths.push(arg),
+        }
+    }
+    if paths.is_empty() {
+        eprintln!("usage: avro-rs-codegen [--output <file.rs>] <schema.avsc>...");
+        process::exit(2);
+    }
+
+    let code = match avro_rs_codegen::generate_from_files(&paths) {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            process::exit(1);
+        }
+    };
+    match output {
+        Some(path) => {
+            if let Err(e) = fs::write(&path, code) {
+                eprintln!("error: cannot write {}: {}", path, e);
+                process::exit(1);
+            }
+        }
+        None => print!("{}", code),
+    }
+}
@@ -0,0 +1,120 @@
+use avro_rs::schema::Schema;
+use avro_rs::types::Value;
+use avro_rs::{from_avro_datum_de, to_avro_datum, Reader, Writer};
+use std::collections::HashMap;
+use std::fs;
+
+#[allow(dead_code)]
+mod generated {
+    include!("generated/user.rs");
+}
+
+use generated::{Address, Kind, Md5, User, UserScoreUnion};
+
+const SCHEMA: &str = "tests/data/user.avsc";
+
+fn user() -> User {
+    let mut counters = HashMap::new();
+    counters.insert("visits".to_string(), 3);
+    User {
+        id: 27,
+        user_name: "foo".to_string(),
+        r#type: Kind::Admin,
+        email: Some("foo@example.com".to_string()),
+        score: UserScoreUnion::String("high".to_string()),
+        tags: vec!["a".to_string()],
+        counters,
+        checksum: Md5(serde_bytes::ByteBuf::from(vec![1, 2, 3, 4])),
+        address: Address {
+            city: "Lyon".to_string(),
+            zip: None,
+        },
+        birthday: 18_000,
+        created_at: 1_600_000_000_000,
+        token: "550e8400-e29b-41d4-a716-446655440000".to_string(),
+        balance: serde_bytes::ByteBuf::from(vec![0, 100]),
+        referrer: None,
+    }
+}
+
+#[test]
+fn test_generate_from_files() {
+    let code = avro_rs_codegen::generate_from_files(&[SCHEMA]).unwrap();
+    assert_eq!(code, fs::read_to_string("tests/generated/user.rs").unwrap());
+}
+
+#[test]
+fn test_round_trip() {
+    let schema = Schema::parse_str(&fs::read_to_string(SCHEMA).unwrap()).unwrap();
+    let mut referred = user();
+    referred.referrer = Some(Box::new(user()));
+
+    let mut writer = Writer::new(&schema, Vec::new());
+    writer.append_ser_direct(&referred).unwrap();
+    let input = writer.into_inner().unwrap();
+
+    let mut read = Reader::new(&input[..])
+        .unwrap()
+        .into_deserialize_iter::<User>();
+    assert_eq!(read.next().unwrap().unwrap(), referred);
+    assert!(read.next().is_none());
+}
+
+#[test]
+fn test_defaults() {
+    let writer_schema = Schema::parse_str(
+        r#"
+        {
+            "type": "record",
+            "name": "User",
+            "namespace": "com.example",
+            "fields": [
+                {"name": "id", "type": "long"},
+                {"name": "userName", "type": "string"},
+                {"name": "checksum", "type": {"type": "fixed", "name": "Md5", "size": 4}},
+                {"name": "birthday", "type": {"type": "int", "logicalType": "date"}},
+                {"name": "createdAt", "type": {"type": "long", "logicalType": "timestamp-millis"}},
+                {"name": "token", "type": {"type": "string", "logicalType": "uuid"}},
+                {
+                    "name": "balance",
+                    "type": {"type": "bytes", "logicalType": "decimal", "precision": 10, "scale": 2}
+                }
+            ]
+        }
+        "#,
+    )
+    .unwrap();
+    let expected = user();
+    let value = Value::Record(vec![
+        ("id".to_string(), Value::Long(expected.id)),
+        ("userName".to_string(), Value::String(expected.user_name)),
+        ("checksum".to_string(), Value::Fixed(4, vec![1, 2, 3, 4])),
+        ("birthday".to_string(), Value::Date(expected.birthday)),
+        (
+            "createdAt".to_string(),
+            Value::TimestampMillis(expected.created_at),
+        ),
+        ("token".to_string(), Value::String(expected.token)),
+        ("balance".to_string(), Value::Bytes(vec![0, 100])),
+    ]);
+
+    let reader_schema = Schema::parse_str(&fs::read_to_string(SCHEMA).unwrap()).unwrap();
+    let value = value.resolve(&writer_schema).unwrap();
+    let encoded = to_avro_datum(&writer_schema, value).unwrap();
+
+    // Fields missing from the written value get their default values.
+    let user: User =
+        from_avro_datum_de(&writer_schema, &mut &encoded[..], Some(&reader_schema)).unwrap();
+    assert_eq!(user.r#type, Kind::Guest);
+    assert_eq!(user.email, None);
+    assert_eq!(user.score, UserScoreUnion::Int(0));
+    assert_eq!(user.tags, vec!["new".to_string()]);
+    assert_eq!(user.counters["visits"], 1);
+    assert_eq!(
+        user.address,
+        Address {
+            city: "Paris".to_string(),
+            zip: Some("75001".to_string()),
+        }
+    );
+}
@@ -0,0 +1,84 @@
+// This file is generated by avro-rs-codegen.
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, ::serde::Deserialize, ::serde::Serialize)]
+pub enum Kind {
+    #[serde(rename = "ADMIN")]
+    Admin,
+    #[serde(rename = "GUEST")]
+    Guest,
+}
+
+#[derive(Clone, Debug, PartialEq, ::serde::Deserialize, ::serde::Serialize)]
+#[serde(untagged)]
+pub enum UserScoreUnion {
+    Int(i32),
+    String(String),
+    Null,
+}
+
+/// A fixed of 4 bytes.
+#[derive(Clone, Debug, Eq, Hash, PartialEq, ::serde::Deserialize, ::serde::Serialize)]
+pub struct Md5(pub serde_bytes::ByteBuf);
+
+#[derive(Clone, Debug, PartialEq, ::serde::Deserialize, ::serde::Serialize)]
+pub struct Address {
+    pub city: String,
+    pub zip: Option<String>,
+}
+
+/// A user of the service.
+#[derive(Clone, Debug, PartialEq, ::serde::Deserialize, ::serde::Serialize)]
+pub struct User {
+    /// The unique identifier.
+    pub id: i64,
+    #[serde(rename = "userName")]
+    pub user_name: String,
+    #[serde(default = "default_user_type")]
+    pub r#type: Kind,
+    #[serde(default = "default_user_email")]
+    pub email: Option<String>,
+    #[serde(default = "default_user_score")]
+    pub score: UserScoreUnion,
+    #[serde(default = "default_user_tags")]
+    pub tags: Vec<String>,
+    #[serde(default = "default_user_counters")]
+    pub counters: ::std::collections::HashMap<String, i64>,
+    pub checksum: Md5,
+    #[serde(default = "default_user_address")]
+    pub address: Address,
+    pub birthday: i32,
+    #[serde(rename = "createdAt")]
+    pub created_at: i64,
+    pub token: String,
+    pub balance: serde_bytes::ByteBuf,
+    #[serde(default = "default_user_referrer")]
+    pub referrer: Option<Box<User>>,
+}
+
+fn default_user_type() -> Kind {
+    Kind::Guest
+}
+
+fn default_user_email() -> Option<String> {
+    None
+}
+
+fn default_user_score() -> UserScoreUnion {
+    UserScoreUnion::Int(0)
+}
+
+fn default_user_tags() -> Vec<String> {
+    vec!["new".to_string()]
+}
+
+fn default_user_counters() -> ::std::collections::HashMap<String, i64> {
+    vec![("visits".to_string(), 1)].into_iter().collect()
+}
+
+fn default_user_address() -> Address {
+    Address { city: "Paris".to_string(), zip: Some("75001".to_string()) }
+}
+
+fn default_user_referrer() -> Option<Box<User>> {
+    None
+}
@@ -0,0 +1,415 @@
+//! Derive the Avro [`Schema`] of Rust structs and enums, through the `AvroSchema` trait of
+//! [avro-rs](https://docs.rs/avro-rs).
+//!
+//! This crate is meant to be used through the `derive` feature of avro-rs, which re-exports the
+//! macro:
+//!
+//! ```toml
+//! [dependencies.avro-rs]
+//! version = "x.y"
+//! features = ["derive"]
+//! ```
+//!
+//! Structs with named fields become records and enums with unit variants only become enums. Each
+//! of their fields must itself implement `AvroSchemaComponent`, as primitive types, `String`,
+//! `Option<T>`, `Vec<T>`, `HashMap<String, T>` and the types deriving `AvroSchema` do. Newtype
+//! structs get the `Schema` of the type they wrap.
+//!
+//! The following attributes are taken into account:
+//! * `#[serde(rename = "...")]`, on containers, fields and variants;
+//! * `#[serde(rename_all = "...")]`, on containers;
+//! * `#[serde(skip)]` and `#[serde(skip_serializing)]`, on fields and variants;
+//! * `#[serde(default)]` and `#[serde(default = "path")]`, on fields, which become the default
+//!   value of the field;
+//! * doc comments, on containers and fields, which become their `doc`;
+//! * `#[avro(namespace = "...")]`, on containers.
+//!
+//! `#[serde(flatten)]` and `#[serde(skip_serializing_if = "...")]` change the fields a value is
+//! serialized with, which a record cannot follow, so they fail to compile.
+//!
+//! Unlike in a schema parsed from JSON, the namespace of a container is not inherited by the named
+//! types of its fields, which need their own `#[avro(namespace = "...")]`. A generic type has the
+//! same name whatever its type parameters, so a `Schema` can only hold one of its instantiations:
+//! any other one would be a reference to the first.
+//!
+//! [`Schema`]: https://docs.rs/avro-rs/*/avro_rs/schema/enum.Schema.html
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{
+    parse_macro_input, parse_quote, spanned::Spanned, Attribute, Data, DataEnum, DataStruct,
+    DeriveInput, Error, ExprPath, Fields, Lit, Meta, NestedMeta,
+};
+
+#[proc_macro_derive(AvroSchema, attributes(avro))]
+pub fn derive_avro_schema(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(|error| error.to_compile_error())
+        .into()
+}
+
+/// Where the default value of a field comes from.
+enum FieldDefault {
+    /// `#[serde(default)]`: the `Default` implementation of the type of the field.
+    Trait,
+    /// `#[serde(default = "path")]`: the function at `path`.
+    Function(ExprPath),
+}
+
+/// How `#[serde(rename_all = "...")]` renames the fields or variants of a container.
+#[derive(Clone, Copy)]
+enum RenameRule {
+    Lower,
+    Upper,
+    Pascal,
+    Camel,
+    Snake,
+    ScreamingSnake,
+    Kebab,
+    ScreamingKebab,
+}
+
+impl RenameRule {
+    fn parse(lit: &Lit) -> Result<RenameRule, Error> {
+        Ok(match string_value(lit)?.as_str() {
+            "lowercase" => RenameRule::Lower,
+            "UPPERCASE" => RenameRule::Upper,
+            "PascalCase" => RenameRule::Pascal,
+            "camelCase" => RenameRule::Camel,
+            "snake_case" => RenameRule::Snake,
+            "SCREAMING_SNAKE_CASE" => RenameRule::ScreamingSnake,
+            "kebab-case" => RenameRule::Kebab,
+            "SCREAMING-KEBAB-CASE" => RenameRule::ScreamingKebab,
+            _ => return Err(Error::new(lit.span(), "unknown rename rule")),
+        })
+    }
+
+    /// Rename a field, written in snake_case.
+    fn apply_to_field(self, field: &str) -> String {
+        match self {
+            RenameRule::Lower | RenameRule::Snake => field.to_string(),
+            RenameRule::Upper | RenameRule::ScreamingSnake => field.to_ascii_uppercase(),
+            RenameRule::Pascal => field
+                .split('_')
+                .map(|word| {
+                    let mut chars = word.chars();
+                    chars
+                        .next()
+                        .map(|first| first.to_ascii_uppercase().to_string() + chars.as_str())
+                        .unwrap_or_default()
+                })
+                .collect(),
+            RenameRule::Camel => {
+                let pascal = RenameRule::Pascal.apply_to_field(field);
+                pascal[..1].to_ascii_lowercase() + &pascal[1..]
+            }
+            RenameRule::Kebab => field.replace('_', "-"),
+            RenameRule::ScreamingKebab => field.replace('_', "-").to_ascii_uppercase(),
+        }
+    }
+
+    /// Rename a variant, written in PascalCase.
+    fn apply_to_variant(self, variant: &str) -> String {
+        match self {
+            RenameRule::Lower => variant.to_ascii_lowercase(),
+            RenameRule::Upper => variant.to_ascii_uppercase(),
+            RenameRule::Pascal => variant.to_string(),
+            RenameRule::Camel => variant[..1].to_ascii_lowercase() + &variant[1..],
+            _ => {
+                let mut snake = String::new();
+                for (i, c) in variant.char_indices() {
+                    if i > 0 && c.is_uppercase() {
+                        snake.push('_');
+                    }
+                    snake.push(c.to_ascii_lowercase());
+                }
+                self.apply_to_field(&snake)
+            }
+        }
+    }
+}
+
+/// The attributes of a container, a field or a variant which affect its `Schema`.
+#[derive(Default)]
+struct Attrs {
+    rename: Option<String>,
+    rename_all: Option<RenameRule>,
+    namespace: Option<String>,
+    doc: Option<String>,
+    default: Option<FieldDefault>,
+    skip: bool,
+}
+
+impl Attrs {
+    fn parse(attrs: &[Attribute]) -> Result<Attrs, Error> {
+        let mut result = Attrs::default();
+        let mut doc = Vec::new();
+
+        for attr in attrs {
+            if attr.path.is_ident("doc") {
+                if let Ok(Meta::NameValue(meta)) = attr.parse_meta() {
+                    if let Lit::Str(line) = meta.lit {
+                        let line = line.value();
+                        doc.push(
+                            line.strip_prefix(' ')
+                                .unwrap_or(&line)
+                                .trim_end()
+                                .to_string(),
+                        );
+                    }
+                }
+                continue;
+            }
+
+            let is_avro = attr.path.is_ident("avro");
+            if !is_avro && !attr.path.is_ident("serde") {
+                continue;
+            }
+            let nested = match attr.parse_meta()? {
+                Meta::List(list) => list.nested,
+                meta => return Err(Error::new(meta.span(), "expected a list of attributes")),
+            };
+            for meta in nested {
+                match meta {
+                    NestedMeta::Meta(Meta::NameValue(ref meta)) if meta.path.is_ident("rename") => {
+                        result.rename = Some(string_value(&meta.lit)?);
+                    }
+                    // `#[serde(rename(serialize = "...", deserialize = "..."))]`
+                    NestedMeta::Meta(Meta::List(ref list)) if list.path.is_ident("rename") => {
+                        for meta in &list.nested {
+                            if let NestedMeta::Meta(Meta::NameValue(ref meta)) = *meta {
+                                if meta.path.is_ident("serialize") {
+                                    result.rename = Some(string_value(&meta.lit)?);
+                                }
+                            }
+                        }
+                    }
+                    NestedMeta::Meta(Meta::NameValue(ref meta))
+                        if meta.path.is_ident("rename_all") =>
+                    {
+                        result.rename_all = Some(RenameRule::parse(&meta.lit)?);
+                    }
+                    NestedMeta::Meta(Meta::List(ref list)) if list.path.is_ident("rename_all") => {
+                        for meta in &list.nested {
+                            if let NestedMeta::Meta(Meta::NameValue(ref meta)) = *meta {
+                                if meta.path.is_ident("serialize") {
+                                    result.rename_all = Some(RenameRule::parse(&meta.lit)?);
+                                }
+                            }
+                        }
+                    }
+                    NestedMeta::Meta(Meta::Path(ref path)) if path.is_ident("flatten") => {
+                        return Err(Error::new(
+                            path.span(),
+                            "AvroSchema does not support `#[serde(flatten)]`",
+                        ));
+                    }
+                    NestedMeta::Meta(Meta::NameValue(ref meta))
+                        if meta.path.is_ident("skip_serializing_if") =>
+                    {
+                        return Err(Error::new(
+                            meta.path.span(),
+                            "AvroSchema does not support `#[serde(skip_serializing_if)]`, as records always hold all their fields",
+                        ));
+                    }
+                    NestedMeta::Meta(Meta::NameValue(ref meta))
+                        if is_avro && meta.path.is_ident("namespace") =>
+                    {
+                        result.namespace = Some(string_value(&meta.lit)?);
+                    }
+                    NestedMeta::Meta(Meta::Path(ref path)) if path.is_ident("default") => {
+                        result.default = Some(FieldDefault::Trait);
+                    }
+                    NestedMeta::Meta(Meta::NameValue(ref meta))
+                        if meta.path.is_ident("default") =>
+                    {
+                        let path = syn::parse_str(&string_value(&meta.lit)?)
+                            .map_err(|error| Error::new(meta.lit.span(), error))?;
+                        result.default = Some(FieldDefault::Function(path));
+                    }
+                    NestedMeta::Meta(Meta::Path(ref path))
+                        if path.is_ident("skip") || path.is_ident("skip_serializing") =>
+                    {
+                        result.skip = true;
+                    }
+                    ref meta if is_avro => {
+                        return Err(Error::new(meta.span(), "unknown avro attribute"));
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        if !doc.is_empty() {
+            result.doc = Some(doc.join("\n").trim().to_string());
+        }
+        Ok(result)
+    }
+}
+
+fn string_value(lit: &Lit) -> Result<String, Error> {
+    match *lit {
+        Lit::Str(ref lit) => Ok(lit.value()),
+        _ => Err(Error::new(lit.span(), "expected a string")),
+    }
+}
+
+fn quote_option(value: Option<String>) -> TokenStream2 {
+    match value {
+        Some(value) => quote!(::std::option::Option::Some(#value.to_string())),
+        None => quote!(::std::option::Option::None),
+    }
+}
+
+fn expand(mut input: DeriveInput) -> Result<TokenStream2, Error> {
+    let attrs = Attrs::parse(&input.attrs)?;
+    let name = attrs.rename.unwrap_or_else(|| input.ident.to_string());
+    let namespace = quote_option(attrs.namespace);
+    let doc = quote_option(attrs.doc);
+    let rename_all = attrs.rename_all;
+
+    let schema = match input.data {
+        Data::Struct(DataStruct {
+            fields: Fields::Named(ref fields),
+            ..
+        }) => {
+            let mut record_fields = Vec::new();
+            for field in &fields.named {
+                let attrs = Attrs::parse(&field.attrs)?;
+                if attrs.skip {
+                    continue;
+                }
+                let position = record_fields.len();
+                let field_name = attrs.rename.unwrap_or_else(|| {
+                    let ident = field.ident.as_ref().unwrap().to_string();
+                    match rename_all {
+                        Some(rule) => rule.apply_to_field(&ident),
+                        None => ident,
+                    }
+                });
+                let field_doc = quote_option(attrs.doc);
+                let ty = &field.ty;
+                let default = match attrs.default {
+                    Some(FieldDefault::Trait) => quote! {
+                        ::std::option::Option::Some(::avro_rs::schema::derive_default(
+                            <#ty as ::std::default::Default>::default(),
+                        ))
+                    },
+                    Some(FieldDefault::Function(path)) => quote! {
+                        ::std::option::Option::Some(::avro_rs::schema::derive_default(#path()))
+                    },
+                    None => quote!(::std::option::Option::None),
+                };
+                record_fields.push(quote! {
+                    ::avro_rs::schema::RecordField {
+                        name: #field_name.to_string(),
+                        doc: #field_doc,
+                        default: #default,
+                        aliases: ::std::option::Option::None,
+                        schema: <#ty as ::avro_rs::schema::AvroSchemaComponent>::get_schema_in_ctxt(named),
+                        order: ::avro_rs::schema::RecordFieldOrder::Ascending,
+                        position: #position,
+                        custom_attributes: ::std::default::Default::default(),
+                    }
+                });
+            }
+            quote! {
+                let fields = vec![#(#record_fields),*];
+                let lookup = fields
+                    .iter()
+                    .map(|field| (field.name.clone(), field.position))
+                    .collect();
+                ::avro_rs::Schema::Record {
+                    name,
+                    doc: #doc,
+                    fields,
+                    lookup,
+                    custom_attributes: ::std::default::Default::default(),
+                }
+            }
+        }
+        Data::Struct(DataStruct {
+            fields: Fields::Unnamed(ref fields),
+            ..
+        }) if fields.unnamed.len() == 1 => {
+            let ty = &fields.unnamed[0].ty;
+            let body =
+                quote!(<#ty as ::avro_rs::schema::AvroSchemaComponent>::get_schema_in_ctxt(named));
+            return Ok(implement(&mut input, body));
+        }
+        Data::Enum(DataEnum { ref variants, .. }) => {
+            let mut symbols = Vec::new();
+            for variant in variants {
+                if !variant.fields.is_empty() {
+                    return Err(Error::new(
+                        variant.span(),
+                        "AvroSchema can only be derived for enums with unit variants",
+                    ));
+                }
+                let attrs = Attrs::parse(&variant.attrs)?;
+                if !attrs.skip {
+                    symbols.push(attrs.rename.unwrap_or_else(|| {
+                        let ident = variant.ident.to_string();
+                        match rename_all {
+                            Some(rule) => rule.apply_to_variant(&ident),
+                            None => ident,
+                        }
+                    }));
+                }
+            }
+            quote! {
+                ::avro_rs::Schema::Enum {
+                    name,
+                    doc: #doc,
+                    symbols: vec![#(#symbols.to_string()),*],
+                    default: ::std::option::Option::None,
+                    custom_attributes: ::std::default::Default::default(),
+                }
+            }
+        }
+        _ => {
+            return Err(Error::new(
+                input.ident.span(),
+                "AvroSchema can only be derived for structs with named fields, newtype structs and enums",
+            ))
+        }
+    };
+
+    Ok(implement(
+        &mut input,
+        quote! {
+            let name = ::avro_rs::schema::Name {
+                name: #name.to_string(),
+                namespace: #namespace,
+                aliases: ::std::option::Option::None,
+            };
+            if !named.insert(name.fullname(::std::option::Option::None)) {
+                return ::avro_rs::Schema::Ref { name };
+            }
+            #schema
+        },
+    ))
+}
+
+/// Implement `AvroSchemaComponent` for `input`, with `body` building its `Schema`.
+fn implement(input: &mut DeriveInput, body: TokenStream2) -> TokenStream2 {
+    for param in input.generics.type_params_mut() {
+        param
+            .bounds
+            .push(parse_quote!(::avro_rs::schema::AvroSchemaComponent));
+    }
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    quote! {
+        impl #impl_generics ::avro_rs::schema::AvroSchemaComponent for #ident #ty_generics #where_clause {
+            fn get_schema_in_ctxt(
+                named: &mut ::std::collections::HashSet<::std::string::String>,
+            ) -> ::avro_rs::Schema {
+                #body
+            }
+        }
+    }
+}
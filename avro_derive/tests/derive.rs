@@ -0,0 +1,217 @@
+use avro_rs::schema::RecordField;
+use avro_rs::{from_value, AvroSchema, Reader, Schema, Writer};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// A color.
+#[derive(AvroSchema, Debug, Deserialize, PartialEq, Serialize)]
+#[avro(namespace = "com.example")]
+enum Color {
+    Red,
+    #[serde(rename = "GREEN")]
+    Green,
+}
+
+#[derive(AvroSchema, Debug, Deserialize, PartialEq, Serialize)]
+#[avro(namespace = "com.example")]
+struct Point {
+    x: i32,
+    y: f64,
+}
+
+/// A test record,
+/// over two lines.
+#[derive(AvroSchema, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename = "TestRecord")]
+#[avro(namespace = "com.example")]
+struct Test {
+    /// The identifier.
+    id: i64,
+    #[serde(rename = "label")]
+    name: String,
+    tags: Vec<String>,
+    scores: HashMap<String, f32>,
+    parent: Option<Point>,
+    point: Point,
+    color: Color,
+    #[serde(default)]
+    count: i32,
+    #[serde(default = "default_ratio")]
+    ratio: f64,
+    #[serde(skip)]
+    cached: Option<String>,
+}
+
+fn default_ratio() -> f64 {
+    0.5
+}
+
+#[derive(AvroSchema, Debug, Deserialize, PartialEq, Serialize)]
+struct Wrapper<T> {
+    inner: T,
+}
+
+#[derive(AvroSchema, Serialize)]
+struct Millis(i64);
+
+fn field<'a>(schema: &'a Schema, name: &str) -> &'a RecordField {
+    match *schema {
+        Schema::Record {
+            ref fields,
+            ref lookup,
+            ..
+        } => &fields[lookup[name]],
+        _ => panic!("not a record: {:?}", schema),
+    }
+}
+
+#[test]
+fn test_derive_record() {
+    let schema = Test::get_schema();
+    let expected = Schema::parse_str(
+        r#"
+        {
+            "type": "record",
+            "name": "TestRecord",
+            "namespace": "com.example",
+            "fields": [
+                {"name": "id", "type": "long"},
+                {"name": "label", "type": "string"},
+                {"name": "tags", "type": {"type": "array", "items": "string"}},
+                {"name": "scores", "type": {"type": "map", "values": "float"}},
+                {
+                    "name": "parent",
+                    "type": [
+                        "null",
+                        {
+                            "type": "record",
+                            "name": "Point",
+                            "namespace": "com.example",
+                            "fields": [
+                                {"name": "x", "type": "int"},
+                                {"name": "y", "type": "double"}
+                            ]
+                        }
+                    ]
+                },
+                {"name": "point", "type": "com.example.Point"},
+                {
+                    "name": "color",
+                    "type": {
+                        "type": "enum",
+                        "name": "Color",
+                        "namespace": "com.example",
+                        "symbols": ["Red", "GREEN"]
+                    }
+                },
+                {"name": "count", "type": "int", "default": 0},
+                {"name": "ratio", "type": "double", "default": 0.5}
+            ]
+        }
+        "#,
+    )
+    .unwrap();
+    assert_eq!(schema, expected);
+
+    match schema {
+        Schema::Record { ref doc, .. } => {
+            assert_eq!(doc.as_deref(), Some("A test record,\nover two lines."))
+        }
+        _ => unreachable!(),
+    }
+    assert_eq!(field(&schema, "id").doc.as_deref(), Some("The identifier."));
+    assert_eq!(field(&schema, "id").default, None);
+    assert_eq!(field(&schema, "count").default, Some(0.into()));
+    assert_eq!(field(&schema, "ratio").default, Some(0.5.into()));
+    assert_eq!(field(&schema, "point").position, 5);
+    match field(&schema, "point").schema {
+        Schema::Ref { ref name } => assert_eq!(name.fullname(None), "com.example.Point"),
+        ref other => panic!("expected a reference, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_derive_write_and_read() {
+    let schema = Test::get_schema();
+    let mut scores = HashMap::new();
+    scores.insert("math".to_string(), 9.5);
+    let test = Test {
+        id: 27,
+        name: "foo".to_string(),
+        tags: vec!["a".to_string(), "b".to_string()],
+        scores,
+        parent: None,
+        point: Point { x: 1, y: -2.5 },
+        color: Color::Green,
+        count: 3,
+        ratio: 0.25,
+        cached: None,
+    };
+
+    let mut writer = Writer::new(&schema, Vec::new());
+    writer.append_ser(&test).unwrap();
+    let input = writer.into_inner().unwrap();
+
+    let mut reader = Reader::with_schema(&schema, &input[..]).unwrap();
+    let value = reader.next().unwrap().unwrap();
+    assert_eq!(from_value::<Test>(&value).unwrap(), test);
+    assert!(reader.next().is_none());
+}
+
+#[test]
+fn test_derive_generic_and_newtype() {
+    assert_eq!(
+        Wrapper::<Millis>::get_schema(),
+        Schema::parse_str(
+            r#"
+            {
+                "type": "record",
+                "name": "Wrapper",
+                "fields": [{"name": "inner", "type": "long"}]
+            }
+            "#,
+        )
+        .unwrap()
+    );
+    assert_eq!(Millis::get_schema(), Schema::Long);
+}
+
+#[allow(dead_code)]
+#[derive(AvroSchema, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+enum Status {
+    InProgress,
+    #[serde(rename = "done")]
+    Done,
+}
+
+#[derive(AvroSchema, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Task {
+    task_id: i64,
+    #[serde(rename = "state")]
+    current_status: Status,
+}
+
+#[test]
+fn test_derive_rename_all() {
+    assert_eq!(
+        Task::get_schema(),
+        Schema::parse_str(
+            r#"
+            {
+                "type": "record",
+                "name": "Task",
+                "fields": [
+                    {"name": "taskId", "type": "long"},
+                    {
+                        "name": "state",
+                        "type": {"type": "enum", "name": "Status", "symbols": ["IN_PROGRESS", "done"]}
+                    }
+                ]
+            }
+            "#,
+        )
+        .unwrap()
+    );
+}
@@ -0,0 +1,393 @@
+//! Command-line tools to inspect and convert Avro files, in the spirit of the Java avro-tools.
+//!
+//! Every input and output can be a file or `-`, for the standard input or output, which is also
+//! used when they are left out. The output of `cat` and `concat` is their last argument, when
+//! there are several of them.
+use std::env;
+use std::error::Error;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::process;
+use std::str::FromStr;
+
+use avro_rs::{from_avro_json, to_avro_json, BlockReader, Codec, Rabin, Reader, Schema, Writer};
+use md5::Md5;
+use serde_json::Value as JsonValue;
+use sha2::Sha256;
+
+type Result<T> = std::result::Result<T, Box<dyn Error>>;
+
+const USAGE: &str = "usage: avro-tools <command> [options] [arguments]
+
+commands:
+  getschema [input]                                print the schema of a data file
+  getmeta [--key <key>] [input]                    print the metadata of a data file
+  tojson [--pretty] [--reader-schema-file <file>] [input]
+                                                   print the records of a data file as JSON
+  fromjson --schema-file <file> [--codec <codec>] [input] [output]
+                                                   write JSON records into a data file
+  count [input]                                    print the number of records of a data file
+  cat [--offset <n>] [--limit <n>] [--codec <codec>] [input...] [output]
+                                                   copy some records of data files into one
+  concat [--codec <codec>] [input...] [output]     concatenate data files into one, copying their
+                                                   blocks without decoding them
+  recodec [--codec <codec>] [input] [output]       compress a data file with another codec
+  fingerprint [--algorithm rabin|md5|sha256] [schema]
+                                                   print the fingerprint of a schema
+  canonical [schema]                               print the Parsing Canonical Form of a schema";
+
+fn main() {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    if let Err(e) = run(&args) {
+        eprintln!("error: {}", e);
+        process::exit(1);
+    }
+}
+
+/// The options and positional arguments of a command.
+struct Args {
+    options: Vec<(String, Option<String>)>,
+    positional: Vec<String>,
+}
+
+impl Args {
+    /// Split `args` into options, among the `flags` which take no value and the `valued` ones
+    /// which take one, and positional arguments.
+    fn parse(args: &[String], flags: &[&str], valued: &[&str]) -> Result<Args> {
+        let mut options = Vec::new();
+        let mut positional = Vec::new();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            if flags.contains(&arg.as_str()) {
+                options.push((arg.clone(), None));
+            } else if valued.contains(&arg.as_str()) {
+                let value = args
+                    .next()
+                    .ok_or_else(|| format!("missing value for {}", arg))?;
+                options.push((arg.clone(), Some(value.clone())));
+            } else if arg.starts_with("--") {
+                return Err(format!("unknown option {}", arg).into());
+            } else {
+                positional.push(arg.clone());
+            }
+        }
+        Ok(Args {
+            options,
+            positional,
+        })
+    }
+
+    fn flag(&self, name: &str) -> bool {
+        self.options.iter().any(|(option, _)| option == name)
+    }
+
+    fn value(&self, name: &str) -> Option<&str> {
+        self.options
+            .iter()
+            .rev()
+            .find(|(option, _)| option == name)
+            .and_then(|(_, value)| value.as_deref())
+    }
+
+    fn parsed<T: FromStr>(&self, name: &str) -> Result<Option<T>> {
+        self.value(name)
+            .map(|value| {
+                value
+                    .parse()
+                    .map_err(|_| format!("invalid value for {}: {}", name, value).into())
+            })
+            .transpose()
+    }
+
+    fn codec(&self) -> Result<Option<Codec>> {
+        self.value("--codec")
+            .map(|codec| Codec::from_str(codec).map_err(|_| format!("unknown codec {}", codec)))
+            .transpose()
+            .map_err(Into::into)
+    }
+
+    /// Get the single input of a command, which defaults to `-`.
+    fn input(&self) -> Result<&str> {
+        match self.positional.as_slice() {
+            [] => Ok("-"),
+            [input] => Ok(input),
+            _ => Err("too many arguments".into()),
+        }
+    }
+
+    /// Get the single input and output of a command, which default to `-`.
+    fn input_output(&self) -> Result<(&str, &str)> {
+        match self.positional.as_slice() {
+            [] => Ok(("-", "-")),
+            [input] => Ok((input, "-")),
+            [input, output] => Ok((input, output)),
+            _ => Err("too many arguments".into()),
+        }
+    }
+
+    /// Get the inputs and the output of a command, which is the last argument when there are
+    /// several of them: a lone argument is an input.
+    fn inputs_output(&self) -> Result<(Vec<&str>, &str)> {
+        let (inputs, output) = match self.positional.split_last() {
+            None => (vec!["-"], "-"),
+            Some((input, [])) => (vec![input.as_str()], "-"),
+            Some((output, inputs)) => {
+                (inputs.iter().map(String::as_str).collect(), output.as_str())
+            }
+        };
+        if output != "-" && inputs.contains(&output) {
+            return Err(format!("{} is both an input and the output", output).into());
+        }
+        Ok((inputs, output))
+    }
+}
+
+fn open(path: &str) -> Result<Box<dyn Read>> {
+    Ok(match path {
+        "-" => Box::new(BufReader::new(io::stdin())),
+        path => Box::new(BufReader::new(
+            File::open(path).map_err(|e| format!("{}: {}", path, e))?,
+        )),
+    })
+}
+
+fn create(path: &str) -> Result<Box<dyn Write>> {
+    Ok(match path {
+        "-" => Box::new(BufWriter::new(io::stdout())),
+        path => Box::new(BufWriter::new(
+            File::create(path).map_err(|e| format!("{}: {}", path, e))?,
+        )),
+    })
+}
+
+fn read_schema(path: &str) -> Result<Schema> {
+    let mut raw = String::new();
+    open(path)?.read_to_string(&mut raw)?;
+    Ok(Schema::parse_str(&raw)?)
+}
+
+fn run(args: &[String]) -> Result<()> {
+    let (command, args) = match args.split_first() {
+        Some((command, args)) => (command.as_str(), args),
+        None => return Err(USAGE.into()),
+    };
+    match command {
+        "getschema" => getschema(&Args::parse(args, &[], &[])?),
+        "getmeta" => getmeta(&Args::parse(args, &[], &["--key"])?),
+        "tojson" => tojson(&Args::parse(
+            args,
+            &["--pretty"],
+            &["--reader-schema-file"],
+        )?),
+        "fromjson" => fromjson(&Args::parse(args, &[], &["--schema-file", "--codec"])?),
+        "count" => count(&Args::parse(args, &[], &[])?),
+        "cat" => cat(&Args::parse(
+            args,
+            &[],
+            &["--offset", "--limit", "--codec"],
+        )?),
+        "concat" => concat(&Args::parse(args, &[], &["--codec"])?),
+        "recodec" => recodec(&Args::parse(args, &[], &["--codec"])?),
+        "fingerprint" => fingerprint(&Args::parse(args, &[], &["--algorithm"])?),
+        "canonical" => canonical(&Args::parse(args, &[], &[])?),
+        "help" | "-h" | "--help" => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        command => Err(format!("unknown command {}\n\n{}", command, USAGE).into()),
+    }
+}
+
+fn getschema(args: &Args) -> Result<()> {
+    let input = args.input()?;
+    let reader = Reader::new(open(input)?)?;
+    let schema: JsonValue = serde_json::to_value(reader.writer_schema())?;
+    println!("{}", serde_json::to_string_pretty(&schema)?);
+    Ok(())
+}
+
+fn getmeta(args: &Args) -> Result<()> {
+    let input = args.input()?;
+    let reader = Reader::new(open(input)?)?;
+    let codec: &str = reader.codec().into();
+    let mut meta = vec![
+        (
+            "avro.schema".to_string(),
+            serde_json::to_string(reader.writer_schema())?,
+        ),
+        ("avro.codec".to_string(), codec.to_string()),
+    ];
+    let mut user_metadata = reader
+        .user_metadata()
+        .iter()
+        .map(|(key, value)| (key.clone(), String::from_utf8_lossy(value).into_owned()))
+        .collect::<Vec<_>>();
+    user_metadata.sort();
+    meta.extend(user_metadata);
+    for (key, value) in meta {
+        match args.value("--key") {
+            Some(wanted) if wanted != key => {}
+            Some(_) => println!("{}", value),
+            None => println!("{}\t{}", key, value),
+        }
+    }
+    Ok(())
+}
+
+fn tojson(args: &Args) -> Result<()> {
+    let input = args.input()?;
+    let reader_schema = args
+        .value("--reader-schema-file")
+        .map(read_schema)
+        .transpose()?;
+    let reader = match reader_schema {
+        Some(ref schema) => Reader::with_schema(schema, open(input)?)?,
+        None => Reader::new(open(input)?)?,
+    };
+    let schema = reader_schema
+        .clone()
+        .unwrap_or_else(|| reader.writer_schema().clone());
+
+    let mut output = create("-")?;
+    for value in reader {
+        let json = to_avro_json(&value?, &schema)?;
+        if args.flag("--pretty") {
+            serde_json::to_writer_pretty(&mut output, &json)?;
+        } else {
+            serde_json::to_writer(&mut output, &json)?;
+        }
+        writeln!(output)?;
+    }
+    output.flush()?;
+    Ok(())
+}
+
+fn fromjson(args: &Args) -> Result<()> {
+    let (input, output) = args.input_output()?;
+    let schema = read_schema(args.value("--schema-file").ok_or("missing --schema-file")?)?;
+    let mut raw = String::new();
+    open(input)?.read_to_string(&mut raw)?;
+
+    let mut writer = Writer::with_codec(
+        &schema,
+        create(output)?,
+        args.codec()?.unwrap_or(Codec::Null),
+    );
+    for json in serde_json::Deserializer::from_str(&raw).into_iter::<JsonValue>() {
+        writer.append(from_avro_json(&json?, &schema)?)?;
+    }
+    writer.into_inner()?.flush()?;
+    Ok(())
+}
+
+fn count(args: &Args) -> Result<()> {
+    let input = args.input()?;
+    let mut count = 0usize;
+    for value in Reader::new(open(input)?)? {
+        value?;
+        count += 1;
+    }
+    println!("{}", count);
+    Ok(())
+}
+
+/// Copy the records of several data files with the same schema into one, skipping the first
+/// `--offset` records and stopping after `--limit` records, if any.
+fn cat(args: &Args) -> Result<()> {
+    let (inputs, output) = args.inputs_output()?;
+    let mut offset = args.parsed::<usize>("--offset")?.unwrap_or(0);
+    let mut limit = args.parsed::<usize>("--limit")?.unwrap_or(usize::MAX);
+
+    let mut readers = Vec::new();
+    for input in &inputs {
+        readers.push(Reader::new(open(input)?)?);
+    }
+    let schema = readers[0].writer_schema().clone();
+    let codec = match args.codec()? {
+        Some(codec) => codec,
+        None => readers[0].codec(),
+    };
+    for (input, reader) in inputs.iter().zip(&readers) {
+        if *reader.writer_schema() != schema {
+            return Err(format!("{} does not have the schema of {}", input, inputs[0]).into());
+        }
+    }
+
+    let mut writer = Writer::with_codec(&schema, create(output)?, codec);
+    for reader in readers {
+        for value in reader {
+            let value = value?;
+            if offset > 0 {
+                offset -= 1;
+                continue;
+            }
+            if limit == 0 {
+                break;
+            }
+            limit -= 1;
+            writer.append(value)?;
+        }
+    }
+    writer.into_inner()?.flush()?;
+    Ok(())
+}
+
+/// Copy the data blocks of several data files with the same schema into one, without decoding
+/// them.
+fn concat(args: &Args) -> Result<()> {
+    let (inputs, output) = args.inputs_output()?;
+    let mut readers = Vec::new();
+    for input in &inputs {
+        readers.push(BlockReader::new(open(input)?)?);
+    }
+    let schema = readers[0].writer_schema().clone();
+    let codec = match args.codec()? {
+        Some(codec) => codec,
+        None => readers[0].codec(),
+    };
+
+    let mut writer = Writer::with_codec(&schema, create(output)?, codec);
+    for (input, reader) in inputs.iter().zip(readers) {
+        if *reader.writer_schema() != schema {
+            return Err(format!("{} does not have the schema of {}", input, inputs[0]).into());
+        }
+        writer.append_blocks(reader)?;
+    }
+    writer.into_inner()?.flush()?;
+    Ok(())
+}
+
+fn recodec(args: &Args) -> Result<()> {
+    let (input, output) = args.input_output()?;
+    let reader = Reader::new(open(input)?)?;
+    let schema = reader.writer_schema().clone();
+
+    let mut writer = Writer::with_codec(
+        &schema,
+        create(output)?,
+        args.codec()?.unwrap_or(Codec::Null),
+    );
+    for value in reader {
+        writer.append(value?)?;
+    }
+    writer.into_inner()?.flush()?;
+    Ok(())
+}
+
+fn fingerprint(args: &Args) -> Result<()> {
+    let input = args.input()?;
+    let schema = read_schema(input)?;
+    match args.value("--algorithm").unwrap_or("rabin") {
+        "rabin" => println!("{:016x}", Rabin::fingerprint64(&schema)),
+        "md5" => println!("{}", schema.fingerprint::<Md5>()),
+        "sha256" => println!("{}", schema.fingerprint::<Sha256>()),
+        algorithm => return Err(format!("unknown algorithm {}", algorithm).into()),
+    }
+    Ok(())
+}
+
+fn canonical(args: &Args) -> Result<()> {
+    let input = args.input()?;
+    println!("{}", read_schema(input)?.canonical_form());
+    Ok(())
+}
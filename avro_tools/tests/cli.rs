@@ -0,0 +1,174 @@
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+use avro_rs::types::{Record, Value};
+use avro_rs::{Codec, Reader, Schema, Writer};
+
+const SCHEMA: &str = r#"
+{
+    "type": "record",
+    "name": "test",
+    "fields": [
+        {"name": "a", "type": "long"},
+        {"name": "b", "type": ["null", "string"]}
+    ]
+}
+"#;
+
+/// Run avro-tools with `args`, writing `input` to its standard input.
+fn run(args: &[&str], input: &[u8]) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_avro-tools"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(input).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn stdout(args: &[&str], input: &[u8]) -> String {
+    let output = run(args, input);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+fn data_file(codec: Codec, count: i64) -> Vec<u8> {
+    let schema = Schema::parse_str(SCHEMA).unwrap();
+    let mut writer = Writer::with_codec(&schema, Vec::new(), codec);
+    writer
+        .add_user_metadata("producer".to_string(), "tests")
+        .unwrap();
+    for a in 0..count {
+        let mut record = Record::new(&schema).unwrap();
+        record.put("a", a);
+        record.put("b", Value::Union(Box::new(Value::String(a.to_string()))));
+        writer.append(record).unwrap();
+    }
+    writer.into_inner().unwrap()
+}
+
+fn read_all(data: &[u8]) -> Vec<Value> {
+    Reader::new(data).unwrap().map(Result::unwrap).collect()
+}
+
+#[test]
+fn test_inspect() {
+    let data = data_file(Codec::Deflate, 3);
+
+    let schema = Schema::parse_str(&stdout(&["getschema"], &data)).unwrap();
+    assert_eq!(schema, Schema::parse_str(SCHEMA).unwrap());
+    assert_eq!(
+        stdout(&["getmeta", "--key", "avro.codec"], &data),
+        "deflate\n"
+    );
+    assert_eq!(stdout(&["getmeta", "--key", "producer"], &data), "tests\n");
+    assert_eq!(stdout(&["count", "-"], &data), "3\n");
+    assert_eq!(
+        stdout(&["tojson"], &data),
+        "{\"a\":0,\"b\":{\"string\":\"0\"}}\n\
+         {\"a\":1,\"b\":{\"string\":\"1\"}}\n\
+         {\"a\":2,\"b\":{\"string\":\"2\"}}\n"
+    );
+}
+
+#[test]
+fn test_fromjson() {
+    let schema_path = std::env::temp_dir().join(format!("avro-tools-{}.avsc", std::process::id()));
+    std::fs::write(&schema_path, SCHEMA).unwrap();
+    let json = b"{\"a\": 0, \"b\": {\"string\": \"0\"}}\n{\"a\": 1, \"b\": {\"string\": \"1\"}}";
+
+    let output = run(
+        &["fromjson", "--schema-file", schema_path.to_str().unwrap()],
+        json,
+    );
+    std::fs::remove_file(&schema_path).unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        read_all(&output.stdout),
+        read_all(&data_file(Codec::Null, 2))
+    );
+}
+
+#[test]
+fn test_cat_and_recodec() {
+    let data = data_file(Codec::Null, 5);
+
+    let output = run(&["cat", "--offset", "1", "--limit", "2", "-", "-"], &data);
+    assert!(output.status.success());
+    assert_eq!(read_all(&output.stdout), read_all(&data)[1..3].to_vec());
+
+    let paths = ["first", "second"]
+        .iter()
+        .map(|name| {
+            let name = format!("avro-tools-{}-{}.avro", std::process::id(), name);
+            std::env::temp_dir().join(name)
+        })
+        .collect::<Vec<_>>();
+    std::fs::write(&paths[0], &data).unwrap();
+    std::fs::write(&paths[1], data_file(Codec::Deflate, 2)).unwrap();
+    let output = run(
+        &[
+            "concat",
+            paths[0].to_str().unwrap(),
+            paths[1].to_str().unwrap(),
+            "-",
+        ],
+        b"",
+    );
+    for path in &paths {
+        std::fs::remove_file(path).unwrap();
+    }
+    assert!(output.status.success());
+    let mut expected = read_all(&data);
+    expected.extend(read_all(&data_file(Codec::Null, 2)));
+    assert_eq!(read_all(&output.stdout), expected);
+
+    let output = run(&["recodec", "--codec", "deflate"], &data);
+    assert!(output.status.success());
+    assert_eq!(
+        Reader::new(&output.stdout[..]).unwrap().codec(),
+        Codec::Deflate
+    );
+    assert_eq!(read_all(&output.stdout), read_all(&data));
+
+    assert!(!run(&["recodec", "--codec", "lzma"], &data).status.success());
+}
+
+#[test]
+fn test_schema_commands() {
+    let schema = br#"{"type": "fixed", "name": "md5", "size": 16}"#;
+    assert_eq!(
+        stdout(&["canonical"], schema),
+        "{\"name\":\"md5\",\"type\":\"fixed\",\"size\":16}\n"
+    );
+    assert_eq!(stdout(&["fingerprint"], schema), "481b34e75cd85d8c\n");
+    assert_eq!(
+        stdout(&["fingerprint", "--algorithm", "sha256"], schema),
+        "28553295cf83da2a4cae96f8dfaca8a273cbc89942a144731c694fb9191c5b00\n"
+    );
+    assert!(!run(&["canonical", "-", "-"], schema).status.success());
+}
+
+#[test]
+fn test_arguments() {
+    let data = data_file(Codec::Null, 2);
+    let path = std::env::temp_dir().join(format!("avro-tools-{}-lone.avro", std::process::id()));
+    std::fs::write(&path, &data).unwrap();
+    let path = path.to_str().unwrap();
+
+    // a lone argument is the input, which is not overwritten
+    let output = run(&["cat", path], b"");
+    assert!(output.status.success());
+    assert_eq!(read_all(&output.stdout), read_all(&data));
+    assert!(!run(&["concat", path, path], b"").status.success());
+    assert_eq!(std::fs::read(path).unwrap(), data);
+
+    assert!(!run(&["count", path, path], b"").status.success());
+    std::fs::remove_file(path).unwrap();
+}
@@ -0,0 +1,475 @@
+//! Logic handling reading and writing data files over the asynchronous I/O traits of tokio.
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::io::{self, ErrorKind, Read};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures_core::Stream;
+use serde::Serialize;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, ReadBuf};
+
+use crate::errors::{AvroResult, Error};
+use crate::reader::{read_header, Block, SYNC_SIZE};
+use crate::schema::Schema;
+use crate::types::Value;
+use crate::util;
+use crate::writer::Writer;
+use crate::{Codec, CodecOptions};
+
+/// The number of bytes read at once from the underlying reader.
+const READ_SIZE: usize = 8192;
+
+/// The bytes read from an `AsyncRead` and not consumed yet, from which the data file logic shared
+/// with `Reader` reads synchronously.
+#[derive(Debug, Default)]
+struct Pending {
+    buf: Vec<u8>,
+    pos: usize,
+}
+
+impl Pending {
+    fn remaining(&self) -> &[u8] {
+        &self.buf[self.pos..]
+    }
+
+    /// Drop the bytes consumed so far.
+    fn compact(&mut self) {
+        self.buf.drain(..self.pos);
+        self.pos = 0;
+    }
+}
+
+impl Read for Pending {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = Read::read(&mut self.remaining(), buf)?;
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+/// Whether `error` is caused by the lack of bytes to read.
+fn is_eof(error: &Error) -> bool {
+    match *error {
+        Error::IO(ref e) => e.kind() == ErrorKind::UnexpectedEof,
+        _ => false,
+    }
+}
+
+/// Whether `bytes` start with a whole data block, or with bytes which cannot start one, so that
+/// reading them does not fail for lack of data.
+fn starts_with_block(mut bytes: &[u8]) -> bool {
+    match util::read_long(&mut bytes).and_then(|_| util::read_long(&mut bytes)) {
+        Ok(size) => match usize::try_from(size) {
+            Ok(size) => {
+                util::safe_len(size).is_err()
+                    || bytes.len() >= size.saturating_add(SYNC_SIZE as usize)
+            }
+            Err(_) => true,
+        },
+        Err(ref e) => !is_eof(e),
+    }
+}
+
+/// Read more bytes from `reader` at the end of `buf`, returning their number.
+fn poll_fill<R: AsyncRead + Unpin>(
+    reader: &mut R,
+    cx: &mut Context<'_>,
+    buf: &mut Vec<u8>,
+) -> Poll<io::Result<usize>> {
+    let len = buf.len();
+    buf.resize(len + READ_SIZE, 0);
+    let mut read_buf = ReadBuf::new(&mut buf[len..]);
+    let result = Pin::new(reader).poll_read(cx, &mut read_buf);
+    let n = read_buf.filled().len();
+    buf.truncate(len + n);
+    result.map_ok(|()| n)
+}
+
+/// Asynchronous interface for reading Avro formatted values, available with the `async` feature.
+///
+/// It works like `Reader`, over something implementing tokio's `AsyncRead` trait, and is a
+/// `Stream` of values:
+///
+/// ```
+/// # use avro_rs::{AsyncWriter, Schema};
+/// use avro_rs::AsyncReader;
+/// use tokio_stream::StreamExt;
+///
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() {
+/// # let schema = Schema::parse_str(r#""long""#).unwrap();
+/// # let mut writer = AsyncWriter::new(&schema, Vec::new());
+/// # writer.append(27i64).await.unwrap();
+/// # let input = writer.close().await.unwrap();
+/// let mut reader = AsyncReader::new(&input[..]).await.unwrap();
+/// while let Some(value) = reader.next().await {
+///     println!("{:?}", value.unwrap());
+/// }
+/// # }
+/// ```
+pub struct AsyncReader<'a, R> {
+    inner: R,
+    block: Block<Pending>,
+    reader_schema: Option<&'a Schema>,
+    should_resolve_schema: bool,
+    eof: bool,
+    errored: bool,
+}
+
+impl<'a, R: AsyncRead + Unpin> AsyncReader<'a, R> {
+    /// Creates an `AsyncReader` given something implementing the `AsyncRead` trait to read from.
+    /// No reader `Schema` will be set.
+    ///
+    /// **NOTE** The avro header is going to be read automatically upon creation of the
+    /// `AsyncReader`.
+    pub async fn new(reader: R) -> AvroResult<AsyncReader<'a, R>> {
+        Self::open(reader, None).await
+    }
+
+    /// Creates an `AsyncReader` given a reader `Schema` and something implementing the
+    /// `AsyncRead` trait to read from.
+    ///
+    /// **NOTE** The avro header is going to be read automatically upon creation of the
+    /// `AsyncReader`.
+    pub async fn with_schema(schema: &'a Schema, reader: R) -> AvroResult<AsyncReader<'a, R>> {
+        Self::open(reader, Some(schema)).await
+    }
+
+    async fn open(mut inner: R, reader_schema: Option<&'a Schema>) -> AvroResult<Self> {
+        let mut pending = Pending::default();
+        let mut eof = false;
+        // read until the whole header is available, before handing it to the shared logic
+        loop {
+            match read_header(&mut pending.remaining()) {
+                Ok(_) => break,
+                Err(ref e) if is_eof(e) && !eof => {
+                    let len = pending.buf.len();
+                    pending.buf.resize(len + READ_SIZE, 0);
+                    let n = inner.read(&mut pending.buf[len..]).await?;
+                    pending.buf.truncate(len + n);
+                    eof = n == 0;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        let block = Block::new(pending)?;
+        let should_resolve_schema =
+            matches!(reader_schema, Some(schema) if *schema != block.writer_schema);
+        Ok(AsyncReader {
+            inner,
+            block,
+            reader_schema,
+            should_resolve_schema,
+            eof,
+            errored: false,
+        })
+    }
+
+    /// Get a reference to the writer `Schema`.
+    pub fn writer_schema(&self) -> &Schema {
+        &self.block.writer_schema
+    }
+
+    /// Get a reference to the optional reader `Schema`.
+    pub fn reader_schema(&self) -> Option<&Schema> {
+        self.reader_schema
+    }
+
+    /// Get the `Codec` the data blocks are compressed with.
+    pub fn codec(&self) -> Codec {
+        self.block.codec
+    }
+
+    /// Set the `CodecOptions` to decompress the following data blocks with.
+    pub fn set_codec_options(&mut self, options: CodecOptions) {
+        self.block.codec_options = options;
+    }
+
+    /// Get the user metadata of the header, which excludes the `avro.` keys reserved for the
+    /// specification.
+    pub fn user_metadata(&self) -> &HashMap<String, Vec<u8>> {
+        &self.block.user_metadata
+    }
+}
+
+impl<'a, R: AsyncRead + Unpin> Stream for AsyncReader<'a, R> {
+    type Item = AvroResult<Value>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        // to prevent keep on reading after the first error occurs
+        if this.errored {
+            return Poll::Ready(None);
+        }
+
+        // read until the whole next data block is available, before handing it to the shared
+        // logic, unless the current one still holds values
+        while this.block.is_empty() {
+            while !this.eof {
+                let pending = this.block.get_mut();
+                if starts_with_block(pending.remaining()) {
+                    break;
+                }
+                pending.compact();
+                match poll_fill(&mut this.inner, cx, &mut pending.buf) {
+                    Poll::Ready(Ok(n)) => this.eof = n == 0,
+                    Poll::Ready(Err(e)) => {
+                        this.errored = true;
+                        return Poll::Ready(Some(Err(e.into())));
+                    }
+                    Poll::Pending => return Poll::Pending,
+                }
+            }
+            // empty data blocks are skipped, one whole block at a time
+            match this.block.read_block_next() {
+                Ok(true) => {}
+                Ok(false) => return Poll::Ready(None),
+                Err(e) => {
+                    this.errored = true;
+                    return Poll::Ready(Some(Err(e)));
+                }
+            }
+        }
+
+        let read_schema = if this.should_resolve_schema {
+            this.reader_schema
+        } else {
+            None
+        };
+        match this.block.read_next(read_schema) {
+            Ok(value) => Poll::Ready(value.map(Ok)),
+            Err(e) => {
+                this.errored = true;
+                Poll::Ready(Some(Err(e)))
+            }
+        }
+    }
+}
+
+/// Asynchronous interface for writing Avro formatted values, available with the `async` feature.
+///
+/// It works like `Writer`, which encodes the values, over something implementing tokio's
+/// `AsyncWrite` trait:
+///
+/// ```
+/// use avro_rs::{AsyncWriter, Schema};
+///
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() {
+/// let schema = Schema::parse_str(r#""long""#).unwrap();
+/// let mut writer = AsyncWriter::new(&schema, Vec::new());
+/// writer.append(27i64).await.unwrap();
+/// let output = writer.close().await.unwrap();
+/// # }
+/// ```
+pub struct AsyncWriter<'a, W> {
+    writer: Writer<'a, Vec<u8>>,
+    inner: W,
+}
+
+impl<'a, W: AsyncWrite + Unpin> AsyncWriter<'a, W> {
+    /// Creates an `AsyncWriter` given a `Schema` and something implementing the `AsyncWrite`
+    /// trait to write to.
+    /// No compression `Codec` will be used.
+    pub fn new(schema: &'a Schema, writer: W) -> Self {
+        Self::from_writer(Writer::new(schema, Vec::new()), writer)
+    }
+
+    /// Creates an `AsyncWriter` with a specific `Codec` given a `Schema` and something
+    /// implementing the `AsyncWrite` trait to write to.
+    pub fn with_codec(schema: &'a Schema, writer: W, codec: Codec) -> Self {
+        Self::from_writer(Writer::with_codec(schema, Vec::new(), codec), writer)
+    }
+
+    /// Creates an `AsyncWriter` writing what `writer` encodes, which allows to set every option
+    /// of the `Writer` builder:
+    ///
+    /// ```
+    /// use avro_rs::{AsyncWriter, Codec, Schema, Writer};
+    ///
+    /// # #[tokio::main(flavor = "current_thread")]
+    /// # async fn main() {
+    /// let schema = Schema::parse_str(r#""long""#).unwrap();
+    /// let writer = Writer::builder()
+    ///     .schema(&schema)
+    ///     .writer(Vec::new())
+    ///     .codec(Codec::Deflate)
+    ///     .block_size(100)
+    ///     .build();
+    /// let mut writer = AsyncWriter::from_writer(writer, Vec::new());
+    /// writer.append(27i64).await.unwrap();
+    /// let output = writer.close().await.unwrap();
+    /// # }
+    /// ```
+    ///
+    /// **NOTE** What `writer` has already encoded is written too.
+    pub fn from_writer(writer: Writer<'a, Vec<u8>>, inner: W) -> Self {
+        AsyncWriter { writer, inner }
+    }
+
+    /// Get a reference to the `Schema` associated to an `AsyncWriter`.
+    pub fn schema(&self) -> &'a Schema {
+        self.writer.schema()
+    }
+
+    /// Add user metadata to the header of the file, like
+    /// [`Writer::add_user_metadata`](struct.Writer.html#method.add_user_metadata).
+    pub fn add_user_metadata<T: AsRef<[u8]>>(&mut self, key: String, value: T) -> AvroResult<()> {
+        self.writer.add_user_metadata(key, value)
+    }
+
+    /// Append a compatible value (implementing the `ToAvro` trait) to an `AsyncWriter`, also
+    /// performing schema validation.
+    ///
+    /// Return the number of bytes written (it might be 0, see below).
+    ///
+    /// **NOTE** This function is not guaranteed to perform any actual write, since it relies on
+    /// internal buffering for performance reasons. If you want to be sure the value has been
+    /// written, then call [`flush`](struct.AsyncWriter.html#method.flush).
+    pub async fn append<T: Into<Value>>(&mut self, value: T) -> AvroResult<usize> {
+        let n = self.writer.append(value)?;
+        self.write_encoded().await?;
+        Ok(n)
+    }
+
+    /// Append anything implementing the `Serialize` trait to an `AsyncWriter` for
+    /// [`serde`](https://docs.serde.rs/serde/index.html) compatibility, also performing schema
+    /// validation.
+    ///
+    /// Return the number of bytes written.
+    ///
+    /// **NOTE** This function is not guaranteed to perform any actual write, since it relies on
+    /// internal buffering for performance reasons. If you want to be sure the value has been
+    /// written, then call [`flush`](struct.AsyncWriter.html#method.flush).
+    pub async fn append_ser<S: Serialize>(&mut self, value: S) -> AvroResult<usize> {
+        let n = self.writer.append_ser(value)?;
+        self.write_encoded().await?;
+        Ok(n)
+    }
+
+    /// Flush the content appended to an `AsyncWriter` and the underlying writer.
+    ///
+    /// Return the number of bytes written.
+    pub async fn flush(&mut self) -> AvroResult<usize> {
+        let n = self.writer.flush()?;
+        self.write_encoded().await?;
+        self.inner.flush().await?;
+        Ok(n)
+    }
+
+    /// Flush the content appended to an `AsyncWriter`, shut down the underlying writer and return
+    /// it, consuming the `AsyncWriter` itself.
+    pub async fn close(mut self) -> AvroResult<W> {
+        self.flush().await?;
+        self.inner.shutdown().await?;
+        Ok(self.inner)
+    }
+
+    /// Write the bytes encoded by the `Writer` to the underlying writer.
+    async fn write_encoded(&mut self) -> AvroResult<()> {
+        let encoded = self.writer.get_mut();
+        if !encoded.is_empty() {
+            self.inner.write_all(encoded).await?;
+            encoded.clear();
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{record, write_blocks, SCHEMA};
+    use crate::Reader;
+    use tokio_stream::StreamExt;
+
+    #[tokio::test]
+    async fn test_async_writer_and_reader() {
+        let schema = Schema::parse_str(SCHEMA).unwrap();
+        let writer = Writer::builder()
+            .schema(&schema)
+            .writer(Vec::new())
+            .codec(Codec::Deflate)
+            .block_size(64)
+            .build();
+        let mut writer = AsyncWriter::from_writer(writer, Vec::new());
+        writer
+            .add_user_metadata("producer".to_string(), "tests")
+            .unwrap();
+        for a in 0..100 {
+            writer.append(record(&schema, a)).await.unwrap();
+        }
+        let output = writer.close().await.unwrap();
+
+        let expected: Vec<Value> = (0..100).map(|a| record(&schema, a)).collect();
+        let values: Vec<Value> = Reader::new(&output[..])
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(values, expected);
+
+        // a duplex stream hands the bytes over in small chunks
+        let (mut client, server) = tokio::io::duplex(7);
+        let copy = tokio::spawn(async move {
+            client.write_all(&output).await.unwrap();
+            client.shutdown().await.unwrap();
+        });
+        let mut reader = AsyncReader::new(server).await.unwrap();
+        assert_eq!(reader.codec(), Codec::Deflate);
+        assert_eq!(reader.user_metadata()["producer"], b"tests");
+        let mut values = Vec::new();
+        while let Some(value) = reader.next().await {
+            values.push(value.unwrap());
+        }
+        assert_eq!(values, expected);
+        copy.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_async_reader_empty_blocks() {
+        let schema = Schema::parse_str(SCHEMA).unwrap();
+        let input = write_blocks(&schema, Codec::Null, 20);
+        let expected: Vec<Value> = (0..20).map(|a| record(&schema, a)).collect();
+
+        // Insert an empty data block after the first one.
+        let marker = &input[input.len() - 16..];
+        let first_end = input
+            .windows(16)
+            .enumerate()
+            .filter(|(_, w)| *w == marker)
+            .nth(1)
+            .unwrap()
+            .0
+            + 16;
+        let mut with_empty = input[..first_end].to_vec();
+        with_empty.extend_from_slice(&[0, 0]);
+        with_empty.extend_from_slice(marker);
+        with_empty.extend_from_slice(&input[first_end..]);
+
+        let (mut client, server) = tokio::io::duplex(7);
+        let copy = tokio::spawn(async move {
+            client.write_all(&with_empty).await.unwrap();
+            client.shutdown().await.unwrap();
+        });
+        let reader = AsyncReader::new(server).await.unwrap();
+        let values: Vec<Value> = reader.map(Result::unwrap).collect().await;
+        assert_eq!(values, expected);
+        copy.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_async_reader_errors() {
+        let schema = Schema::parse_str(SCHEMA).unwrap();
+        let mut writer = Writer::new(&schema, Vec::new());
+        writer.append(record(&schema, 1)).unwrap();
+        let output = writer.into_inner().unwrap();
+
+        assert!(AsyncReader::new(&output[..10]).await.is_err());
+
+        let mut reader = AsyncReader::new(&output[..output.len() - 1]).await.unwrap();
+        assert!(reader.next().await.unwrap().is_err());
+        assert!(reader.next().await.is_none());
+    }
+}
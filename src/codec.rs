@@ -1,13 +1,18 @@
 //! Logic for all supported compression codecs in Avro.
 use crate::errors::{AvroResult, Error};
 use crate::types::Value;
-use libflate::deflate::{Decoder, Encoder};
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+use flate2::Compression;
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+use std::fmt;
 use std::io::{Read, Write};
-use strum_macros::{EnumString, IntoStaticStr};
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
 
 /// The compression codec used to compress blocks.
-#[derive(Clone, Copy, Debug, PartialEq, EnumString, IntoStaticStr)]
-#[strum(serialize_all = "kebab_case")]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Codec {
     /// The `Null` codec simply passes through data uncompressed.
     Null,
@@ -20,6 +25,135 @@ pub enum Codec {
     /// compression library. Each compressed block is followed by the 4-byte, big-endian
     /// CRC32 checksum of the uncompressed data in the block.
     Snappy,
+    #[cfg(feature = "zstandard")]
+    /// The `Zstandard` codec uses Facebook's [Zstandard](https://facebook.github.io/zstd/)
+    /// compression library.
+    Zstandard,
+    #[cfg(feature = "bzip")]
+    /// The `Bzip2` codec uses the [bzip2](https://sourceware.org/bzip2/) compression library.
+    Bzip2,
+    #[cfg(feature = "xz")]
+    /// The `Xz` codec uses the [xz](https://tukaani.org/xz/) compression library.
+    Xz,
+    /// A codec registered with [`register_codec`](fn.register_codec.html) under the given name.
+    Custom(&'static str),
+}
+
+/// A compression codec that is not built in, which can be used once registered by name with
+/// [`register_codec`](fn.register_codec.html).
+pub trait CustomCodec: Send + Sync {
+    /// Compress a stream of bytes in-place.
+    fn compress(&self, stream: &mut Vec<u8>) -> AvroResult<()>;
+
+    /// Decompress a stream of bytes in-place.
+    fn decompress(&self, stream: &mut Vec<u8>) -> AvroResult<()>;
+}
+
+/// The parameters of the built-in codecs, to trade compression speed for size.
+///
+/// ```
+/// use avro_rs::CodecOptions;
+///
+/// let archive = CodecOptions::builder().deflate_level(9).zstandard_level(19).build();
+/// let stream = CodecOptions::builder().deflate_level(1).zstandard_level(1).build();
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, typed_builder::TypedBuilder)]
+pub struct CodecOptions {
+    /// The compression level of the `Deflate` codec, from 0 (no compression) to 9 (best
+    /// compression).
+    #[builder(default = 6)]
+    pub deflate_level: u32,
+    /// The compression level of the `Zstandard` codec, from 1 (fastest) to 22 (best
+    /// compression), or 0 for the default level of the library.
+    #[builder(default = 0)]
+    pub zstandard_level: i32,
+    /// The base 2 logarithm of the window size of the `Zstandard` codec, up to 31, which lets it
+    /// find matches further back at the cost of memory, or `None` to derive it from the level.
+    #[builder(default, setter(strip_option))]
+    pub zstandard_window_log: Option<u32>,
+    /// Whether the `Snappy` codec checks the CRC32 checksum of the blocks it decompresses. It only
+    /// applies to reading: the checksum is always written.
+    #[builder(default = true)]
+    pub snappy_checksum: bool,
+}
+
+impl Default for CodecOptions {
+    fn default() -> Self {
+        Self::builder().build()
+    }
+}
+
+/// The largest window the `Zstandard` codec can decompress with.
+#[cfg(feature = "zstandard")]
+const ZSTANDARD_WINDOW_LOG_MAX: u32 = if cfg!(target_pointer_width = "64") {
+    31
+} else {
+    30
+};
+
+lazy_static! {
+    static ref CUSTOM_CODECS: RwLock<HashMap<&'static str, Arc<dyn CustomCodec>>> =
+        RwLock::new(HashMap::new());
+}
+
+/// Register a custom codec under `name`, the value of `avro.codec` in the header of the data
+/// files it compresses, replacing any codec previously registered under that name.
+///
+/// Once registered, the codec can be used to write data with `Codec::Custom(name)`, and data
+/// files compressed with it can be read.
+///
+/// Fails if `name` is the name of a built-in codec.
+pub fn register_codec<C: CustomCodec + 'static>(name: &'static str, codec: C) -> AvroResult<()> {
+    if Codec::built_in(name).is_some() {
+        return Err(Error::BuiltInCodec(name.to_string()));
+    }
+    CUSTOM_CODECS.write().unwrap().insert(name, Arc::new(codec));
+    Ok(())
+}
+
+fn custom_codec(name: &str) -> AvroResult<(&'static str, Arc<dyn CustomCodec>)> {
+    CUSTOM_CODECS
+        .read()
+        .unwrap()
+        .get_key_value(name)
+        .map(|(name, codec)| (*name, codec.clone()))
+        .ok_or_else(|| Error::Codec(name.to_string()))
+}
+
+impl FromStr for Codec {
+    type Err = Error;
+
+    /// Get the codec named `name`, among the built-in and the registered ones.
+    fn from_str(name: &str) -> AvroResult<Self> {
+        match Codec::built_in(name) {
+            Some(codec) => Ok(codec),
+            None => custom_codec(name).map(|(name, _)| Codec::Custom(name)),
+        }
+    }
+}
+
+impl From<Codec> for &'static str {
+    fn from(value: Codec) -> Self {
+        match value {
+            Codec::Null => "null",
+            Codec::Deflate => "deflate",
+            #[cfg(feature = "snappy")]
+            Codec::Snappy => "snappy",
+            #[cfg(feature = "zstandard")]
+            Codec::Zstandard => "zstandard",
+            #[cfg(feature = "bzip")]
+            Codec::Bzip2 => "bzip2",
+            #[cfg(feature = "xz")]
+            Codec::Xz => "xz",
+            Codec::Custom(name) => name,
+        }
+    }
+}
+
+impl fmt::Display for Codec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str((*self).into())
+    }
 }
 
 impl From<Codec> for Value {
@@ -29,15 +163,42 @@ impl From<Codec> for Value {
 }
 
 impl Codec {
-    /// Compress a stream of bytes in-place.
+    fn built_in(name: &str) -> Option<Codec> {
+        match name {
+            "null" => Some(Codec::Null),
+            "deflate" => Some(Codec::Deflate),
+            #[cfg(feature = "snappy")]
+            "snappy" => Some(Codec::Snappy),
+            #[cfg(feature = "zstandard")]
+            "zstandard" => Some(Codec::Zstandard),
+            #[cfg(feature = "bzip")]
+            "bzip2" => Some(Codec::Bzip2),
+            #[cfg(feature = "xz")]
+            "xz" => Some(Codec::Xz),
+            _ => None,
+        }
+    }
+
+    /// Compress a stream of bytes in-place, with the default `CodecOptions`.
     pub fn compress(self, stream: &mut Vec<u8>) -> AvroResult<()> {
+        self.compress_with(stream, &CodecOptions::default())
+    }
+
+    /// Compress a stream of bytes in-place, with the given `CodecOptions`.
+    pub fn compress_with(self, stream: &mut Vec<u8>, options: &CodecOptions) -> AvroResult<()> {
         match self {
             Codec::Null => (),
             Codec::Deflate => {
-                let mut encoder = Encoder::new(Vec::new());
+                if options.deflate_level > 9 {
+                    return Err(Error::InvalidCodecOptions(format!(
+                        "deflate level {} is not between 0 and 9",
+                        options.deflate_level
+                    )));
+                }
+                let level = Compression::new(options.deflate_level);
+                let mut encoder = DeflateEncoder::new(Vec::new(), level);
                 encoder.write_all(stream)?;
-                // Deflate errors seem to just be io::Error
-                *stream = encoder.finish().into_result()?;
+                *stream = encoder.finish()?;
             }
             #[cfg(feature = "snappy")]
             Codec::Snappy => {
@@ -53,25 +214,56 @@ impl Codec {
 
                 *stream = encoded;
             }
+            #[cfg(feature = "zstandard")]
+            Codec::Zstandard => {
+                let mut encoder = zstd::Encoder::new(Vec::new(), options.zstandard_level)?;
+                if let Some(window_log) = options.zstandard_window_log {
+                    encoder.window_log(window_log)?;
+                }
+                encoder.write_all(stream)?;
+                *stream = encoder.finish()?;
+            }
+            #[cfg(feature = "bzip")]
+            Codec::Bzip2 => {
+                let mut encoder =
+                    bzip2::write::BzEncoder::new(Vec::new(), bzip2::Compression::default());
+                encoder.write_all(stream)?;
+                *stream = encoder.finish()?;
+            }
+            #[cfg(feature = "xz")]
+            Codec::Xz => {
+                let mut encoder = xz2::write::XzEncoder::new(Vec::new(), 6);
+                encoder.write_all(stream)?;
+                *stream = encoder.finish()?;
+            }
+            Codec::Custom(name) => custom_codec(name)?.1.compress(stream)?,
         };
 
         Ok(())
     }
 
-    /// Decompress a stream of bytes in-place.
+    /// Decompress a stream of bytes in-place, with the default `CodecOptions`.
     pub fn decompress(self, stream: &mut Vec<u8>) -> AvroResult<()> {
+        self.decompress_with(stream, &CodecOptions::default())
+    }
+
+    /// Decompress a stream of bytes in-place, with the given `CodecOptions`.
+    #[allow(unused_variables)]
+    pub fn decompress_with(self, stream: &mut Vec<u8>, options: &CodecOptions) -> AvroResult<()> {
         *stream = match self {
             Codec::Null => return Ok(()),
             Codec::Deflate => {
                 let mut decoded = Vec::new();
-                let mut decoder = Decoder::new(&stream[..]);
-                decoder.read_to_end(&mut decoded)?;
+                DeflateDecoder::new(&stream[..]).read_to_end(&mut decoded)?;
                 decoded
             }
             #[cfg(feature = "snappy")]
             Codec::Snappy => {
                 use byteorder::ByteOrder;
 
+                if stream.len() < 4 {
+                    return Err(Error::SnappyDecompress(stream.len()));
+                }
                 let decompressed_size = snap::decompress_len(&stream[..stream.len() - 4])?;
                 let mut decoded = vec![0; decompressed_size];
                 snap::Decoder::new().decompress(&stream[..stream.len() - 4], &mut decoded[..])?;
@@ -79,7 +271,7 @@ impl Codec {
                 let expected_crc = byteorder::BigEndian::read_u32(&stream[stream.len() - 4..]);
                 let actual_crc = crc::crc32::checksum_ieee(&decoded);
 
-                if expected_crc != actual_crc {
+                if options.snappy_checksum && expected_crc != actual_crc {
                     return Err(Error::SnappyCrcError {
                         expected: expected_crc,
                         found: actual_crc,
@@ -87,6 +279,27 @@ impl Codec {
                 }
                 decoded
             }
+            #[cfg(feature = "zstandard")]
+            Codec::Zstandard => {
+                let mut decoded = Vec::new();
+                let mut decoder = zstd::Decoder::new(&stream[..])?;
+                decoder.window_log_max(ZSTANDARD_WINDOW_LOG_MAX)?;
+                decoder.read_to_end(&mut decoded)?;
+                decoded
+            }
+            #[cfg(feature = "bzip")]
+            Codec::Bzip2 => {
+                let mut decoded = Vec::new();
+                bzip2::read::BzDecoder::new(&stream[..]).read_to_end(&mut decoded)?;
+                decoded
+            }
+            #[cfg(feature = "xz")]
+            Codec::Xz => {
+                let mut decoded = Vec::new();
+                xz2::read::XzDecoder::new(&stream[..]).read_to_end(&mut decoded)?;
+                decoded
+            }
+            Codec::Custom(name) => return custom_codec(name)?.1.decompress(stream),
         };
         Ok(())
     }
@@ -119,6 +332,31 @@ mod tests {
         assert_eq!(INPUT, stream.as_slice());
     }
 
+    #[test]
+    fn deflate_levels() {
+        let mut stored = INPUT.to_vec();
+        let options = CodecOptions::builder().deflate_level(0).build();
+        Codec::Deflate.compress_with(&mut stored, &options).unwrap();
+        assert!(stored.len() > INPUT.len());
+
+        let mut compressed = INPUT.to_vec();
+        let options = CodecOptions::builder().deflate_level(9).build();
+        Codec::Deflate
+            .compress_with(&mut compressed, &options)
+            .unwrap();
+        assert!(compressed.len() < INPUT.len());
+
+        Codec::Deflate.decompress(&mut stored).unwrap();
+        Codec::Deflate.decompress(&mut compressed).unwrap();
+        assert_eq!(INPUT, stored.as_slice());
+        assert_eq!(INPUT, compressed.as_slice());
+
+        let options = CodecOptions::builder().deflate_level(10).build();
+        assert!(Codec::Deflate
+            .compress_with(&mut INPUT.to_vec(), &options)
+            .is_err());
+    }
+
     #[cfg(feature = "snappy")]
     #[test]
     fn snappy_compress_and_decompress() {
@@ -131,6 +369,105 @@ mod tests {
         assert_eq!(INPUT, stream.as_slice());
     }
 
+    #[cfg(feature = "zstandard")]
+    #[test]
+    fn zstandard_compress_and_decompress() {
+        let codec = Codec::Zstandard;
+        let mut stream = INPUT.to_vec();
+        codec.compress(&mut stream).unwrap();
+        assert_ne!(INPUT, stream.as_slice());
+        assert!(INPUT.len() > stream.len());
+        codec.decompress(&mut stream).unwrap();
+        assert_eq!(INPUT, stream.as_slice());
+    }
+
+    #[cfg(feature = "snappy")]
+    #[test]
+    fn snappy_checksum() {
+        let mut stream = INPUT.to_vec();
+        Codec::Snappy.compress(&mut stream).unwrap();
+        *stream.last_mut().unwrap() ^= 1;
+
+        assert!(Codec::Snappy.decompress(&mut stream.clone()).is_err());
+        let options = CodecOptions::builder().snappy_checksum(false).build();
+        Codec::Snappy
+            .decompress_with(&mut stream, &options)
+            .unwrap();
+        assert_eq!(INPUT, stream.as_slice());
+    }
+
+    #[cfg(feature = "zstandard")]
+    #[test]
+    fn zstandard_level_and_window() {
+        let options = CodecOptions::builder()
+            .zstandard_level(19)
+            .zstandard_window_log(30)
+            .build();
+        let mut stream = INPUT.to_vec();
+        Codec::Zstandard
+            .compress_with(&mut stream, &options)
+            .unwrap();
+        assert!(INPUT.len() > stream.len());
+        Codec::Zstandard.decompress(&mut stream).unwrap();
+        assert_eq!(INPUT, stream.as_slice());
+    }
+
+    #[cfg(feature = "bzip")]
+    #[test]
+    fn bzip2_compress_and_decompress() {
+        let codec = Codec::Bzip2;
+        let mut stream = INPUT.to_vec();
+        codec.compress(&mut stream).unwrap();
+        assert_ne!(INPUT, stream.as_slice());
+        codec.decompress(&mut stream).unwrap();
+        assert_eq!(INPUT, stream.as_slice());
+    }
+
+    #[cfg(feature = "xz")]
+    #[test]
+    fn xz_compress_and_decompress() {
+        let codec = Codec::Xz;
+        let mut stream = INPUT.to_vec();
+        codec.compress(&mut stream).unwrap();
+        assert_ne!(INPUT, stream.as_slice());
+        codec.decompress(&mut stream).unwrap();
+        assert_eq!(INPUT, stream.as_slice());
+    }
+
+    /// Reverse the bytes of the stream.
+    struct Reverse;
+
+    impl CustomCodec for Reverse {
+        fn compress(&self, stream: &mut Vec<u8>) -> AvroResult<()> {
+            stream.reverse();
+            Ok(())
+        }
+
+        fn decompress(&self, stream: &mut Vec<u8>) -> AvroResult<()> {
+            stream.reverse();
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn custom_compress_and_decompress() {
+        assert!(Codec::from_str("reverse").is_err());
+        assert!(Codec::Custom("reverse").compress(&mut vec![]).is_err());
+
+        register_codec("reverse", Reverse).unwrap();
+        let codec = Codec::from_str("reverse").unwrap();
+        assert_eq!(codec, Codec::Custom("reverse"));
+        assert_eq!(<&str>::from(codec), "reverse");
+
+        let mut stream = INPUT.to_vec();
+        codec.compress(&mut stream).unwrap();
+        assert_eq!(stream.first(), INPUT.last());
+        codec.decompress(&mut stream).unwrap();
+        assert_eq!(INPUT, stream.as_slice());
+
+        assert!(register_codec("deflate", Reverse).is_err());
+    }
+
     #[test]
     fn codec_to_str() {
         assert_eq!(<&str>::from(Codec::Null), "null");
@@ -138,18 +475,34 @@ mod tests {
 
         #[cfg(feature = "snappy")]
         assert_eq!(<&str>::from(Codec::Snappy), "snappy");
+
+        #[cfg(feature = "zstandard")]
+        assert_eq!(<&str>::from(Codec::Zstandard), "zstandard");
+
+        #[cfg(feature = "bzip")]
+        assert_eq!(<&str>::from(Codec::Bzip2), "bzip2");
+
+        #[cfg(feature = "xz")]
+        assert_eq!(<&str>::from(Codec::Xz), "xz");
     }
 
     #[test]
     fn codec_from_str() {
-        use std::str::FromStr;
-
         assert_eq!(Codec::from_str("null").unwrap(), Codec::Null);
         assert_eq!(Codec::from_str("deflate").unwrap(), Codec::Deflate);
 
         #[cfg(feature = "snappy")]
         assert_eq!(Codec::from_str("snappy").unwrap(), Codec::Snappy);
 
+        #[cfg(feature = "zstandard")]
+        assert_eq!(Codec::from_str("zstandard").unwrap(), Codec::Zstandard);
+
+        #[cfg(feature = "bzip")]
+        assert_eq!(Codec::from_str("bzip2").unwrap(), Codec::Bzip2);
+
+        #[cfg(feature = "xz")]
+        assert_eq!(Codec::from_str("xz").unwrap(), Codec::Xz);
+
         assert!(Codec::from_str("not a codec").is_err());
     }
 }
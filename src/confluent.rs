@@ -0,0 +1,256 @@
+//! Logic for the wire format of the Confluent Schema Registry, where each datum is framed with
+//! the id of its schema in the registry.
+use std::collections::HashMap;
+use std::io::Read;
+
+use crate::errors::{AvroResult, Error};
+use crate::rabin::Rabin;
+use crate::reader::from_avro_datum;
+use crate::schema::Schema;
+use crate::types::Value;
+use crate::writer::to_avro_datum;
+
+/// The byte every datum starts with in the Confluent wire format.
+const MAGIC_BYTE: u8 = 0;
+
+/// Source of the mapping between schemas and their ids, such as a schema registry.
+pub trait SchemaResolver {
+    /// Find the `Schema` registered with the given id.
+    fn resolve_schema(&self, id: u32) -> AvroResult<Schema>;
+
+    /// Find the id under which the given `Schema` is registered.
+    fn resolve_id(&self, schema: &Schema) -> AvroResult<u32>;
+}
+
+/// A `SchemaResolver` holding the schemas registered with it in memory.
+///
+/// Schemas are identified by their [Parsing Canonical Form].
+///
+/// [Parsing Canonical Form]:
+/// https://avro.apache.org/docs/1.8.2/spec.html#Parsing+Canonical+Form+for+Schemas
+#[derive(Debug, Default)]
+pub struct InMemorySchemaResolver {
+    schemas: HashMap<u32, Schema>,
+    ids: HashMap<u64, u32>,
+}
+
+impl InMemorySchemaResolver {
+    /// Create an empty `InMemorySchemaResolver`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register `schema` with the given id, replacing any schema previously registered with it.
+    pub fn register(&mut self, id: u32, schema: Schema) {
+        let fingerprint = Rabin::fingerprint64(&schema);
+        if let Some(previous) = self.schemas.insert(id, schema) {
+            let previous = Rabin::fingerprint64(&previous);
+            if previous != fingerprint && self.ids.get(&previous) == Some(&id) {
+                // the previous schema may still be registered with another id
+                let other = self
+                    .schemas
+                    .iter()
+                    .filter(|&(_, schema)| Rabin::fingerprint64(schema) == previous)
+                    .map(|(&other, _)| other)
+                    .min();
+                match other {
+                    Some(other) => self.ids.insert(previous, other),
+                    None => self.ids.remove(&previous),
+                };
+            }
+        }
+        self.ids.insert(fingerprint, id);
+    }
+}
+
+impl SchemaResolver for InMemorySchemaResolver {
+    fn resolve_schema(&self, id: u32) -> AvroResult<Schema> {
+        self.schemas
+            .get(&id)
+            .cloned()
+            .ok_or(Error::UnknownSchemaId(id))
+    }
+
+    fn resolve_id(&self, schema: &Schema) -> AvroResult<u32> {
+        self.ids
+            .get(&Rabin::fingerprint64(schema))
+            .cloned()
+            .ok_or_else(|| Error::UnregisteredSchema(schema.canonical_form()))
+    }
+}
+
+/// Cache in front of a `SchemaResolver`, so that each schema and id is only resolved once.
+pub struct SchemaCache<R> {
+    resolver: R,
+    schemas: HashMap<u32, Schema>,
+    ids: HashMap<u64, u32>,
+}
+
+impl<R: SchemaResolver> SchemaCache<R> {
+    /// Create an empty `SchemaCache` in front of `resolver`.
+    pub fn new(resolver: R) -> Self {
+        SchemaCache {
+            resolver,
+            schemas: HashMap::new(),
+            ids: HashMap::new(),
+        }
+    }
+
+    /// Get a reference to the `SchemaResolver` behind a `SchemaCache`.
+    pub fn resolver(&self) -> &R {
+        &self.resolver
+    }
+
+    /// Find the `Schema` registered with the given id.
+    pub fn schema(&mut self, id: u32) -> AvroResult<&Schema> {
+        if !self.schemas.contains_key(&id) {
+            let schema = self.resolver.resolve_schema(id)?;
+            self.schemas.insert(id, schema);
+        }
+        Ok(&self.schemas[&id])
+    }
+
+    /// Find the id under which the given `Schema` is registered.
+    ///
+    /// The `Schema` is looked up by fingerprint, which is computed on every call: the id is meant
+    /// to be found once for each schema and then given to
+    /// [`to_confluent_datum`](fn.to_confluent_datum.html) for every datum.
+    pub fn id(&mut self, schema: &Schema) -> AvroResult<u32> {
+        let fingerprint = Rabin::fingerprint64(schema);
+        match self.ids.get(&fingerprint) {
+            Some(&id) => Ok(id),
+            None => {
+                let id = self.resolver.resolve_id(schema)?;
+                self.ids.insert(fingerprint, id);
+                Ok(id)
+            }
+        }
+    }
+}
+
+/// Encode a compatible value (implementing the `ToAvro` trait) into Avro format framed in the
+/// Confluent wire format, also performing schema validation.
+///
+/// The frame is made of the magic byte `0` and of `id`, the id `schema` is registered under, as
+/// a 4-byte big-endian integer. The id can be found with [`SchemaCache::id`](struct.SchemaCache.html#method.id).
+pub fn to_confluent_datum<T: Into<Value>>(
+    id: u32,
+    schema: &Schema,
+    value: T,
+) -> AvroResult<Vec<u8>> {
+    let mut buffer = vec![MAGIC_BYTE];
+    buffer.extend_from_slice(&id.to_be_bytes());
+    buffer.extend(to_avro_datum(schema, value)?);
+    Ok(buffer)
+}
+
+/// Decode a `Value` encoded in Avro format and framed in the Confluent wire format from anything
+/// implementing `io::Read`, finding the schema it was written with through `cache`.
+///
+/// In case a reader `Schema` is provided, schema resolution will also be performed.
+pub fn from_confluent_datum<R: SchemaResolver, I: Read>(
+    cache: &mut SchemaCache<R>,
+    reader: &mut I,
+    reader_schema: Option<&Schema>,
+) -> AvroResult<Value> {
+    let mut header = [0u8; 5];
+    reader.read_exact(&mut header)?;
+    if header[0] != MAGIC_BYTE {
+        return Err(Error::ConfluentMagicByte(header[0]));
+    }
+
+    let id = u32::from_be_bytes([header[1], header[2], header[3], header[4]]);
+    let writer_schema = cache.schema(id)?;
+    from_avro_datum(writer_schema, reader, reader_schema)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    /// A resolver counting how many times it is called.
+    struct CountingResolver {
+        inner: InMemorySchemaResolver,
+        calls: Cell<usize>,
+    }
+
+    impl SchemaResolver for CountingResolver {
+        fn resolve_schema(&self, id: u32) -> AvroResult<Schema> {
+            self.calls.set(self.calls.get() + 1);
+            self.inner.resolve_schema(id)
+        }
+
+        fn resolve_id(&self, schema: &Schema) -> AvroResult<u32> {
+            self.calls.set(self.calls.get() + 1);
+            self.inner.resolve_id(schema)
+        }
+    }
+
+    #[test]
+    fn test_confluent_datum() {
+        let mut resolver = InMemorySchemaResolver::new();
+        resolver.register(42, Schema::Long);
+        let mut cache = SchemaCache::new(CountingResolver {
+            inner: resolver,
+            calls: Cell::new(0),
+        });
+
+        let id = cache.id(&Schema::Long).unwrap();
+        assert_eq!(cache.id(&Schema::Long).unwrap(), id);
+        let encoded = to_confluent_datum(id, &Schema::Long, 27i64).unwrap();
+        assert_eq!(encoded, vec![0, 0, 0, 0, 42, 54]);
+
+        for _ in 0..2 {
+            assert_eq!(
+                from_confluent_datum(&mut cache, &mut &encoded[..], None).unwrap(),
+                Value::Long(27)
+            );
+        }
+        assert_eq!(
+            from_confluent_datum(&mut cache, &mut &encoded[..], Some(&Schema::Double)).unwrap(),
+            Value::Double(27.0)
+        );
+        assert_eq!(cache.resolver().calls.get(), 2);
+    }
+
+    #[test]
+    fn test_confluent_datum_errors() {
+        let mut cache = SchemaCache::new(InMemorySchemaResolver::new());
+
+        match cache.id(&Schema::Long) {
+            Err(Error::UnregisteredSchema(schema)) => assert_eq!(schema, r#""long""#),
+            other => panic!("expected an unregistered schema, got {:?}", other),
+        }
+        match from_confluent_datum(&mut cache, &mut &[0u8, 0, 0, 0, 42, 54][..], None) {
+            Err(Error::UnknownSchemaId(42)) => {}
+            other => panic!("expected an unknown schema id, got {:?}", other),
+        }
+        match from_confluent_datum(&mut cache, &mut &[1u8, 0, 0, 0, 42, 54][..], None) {
+            Err(Error::ConfluentMagicByte(1)) => {}
+            other => panic!("expected an invalid magic byte, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_register_again() {
+        let mut resolver = InMemorySchemaResolver::new();
+        resolver.register(1, Schema::Long);
+        resolver.register(1, Schema::String);
+        assert_eq!(resolver.resolve_schema(1).unwrap(), Schema::String);
+        assert_eq!(resolver.resolve_id(&Schema::String).unwrap(), 1);
+        assert!(resolver.resolve_id(&Schema::Long).is_err());
+
+        resolver.register(2, Schema::String);
+        resolver.register(3, Schema::Long);
+        resolver.register(1, Schema::Long);
+        assert_eq!(resolver.resolve_id(&Schema::String).unwrap(), 2);
+        assert_eq!(resolver.resolve_id(&Schema::Long).unwrap(), 1);
+
+        resolver.register(4, Schema::Boolean);
+        resolver.register(5, Schema::Boolean);
+        resolver.register(5, Schema::Null);
+        assert_eq!(resolver.resolve_id(&Schema::Boolean).unwrap(), 4);
+        assert_eq!(resolver.resolve_id(&Schema::Null).unwrap(), 5);
+    }
+}
@@ -0,0 +1,879 @@
+//! Logic for serde-compatible deserialization straight from Avro format, guided by a `Schema`.
+use std::rc::Rc;
+use std::str::from_utf8;
+
+use serde::{
+    de::{self, value::BorrowedStrDeserializer, DeserializeSeed, IgnoredAny, Visitor},
+    forward_to_deserialize_any,
+};
+use serde_json::Value as JsonValue;
+
+use crate::decode::{decode_block_len, decode_len, take};
+use crate::errors::Error;
+use crate::json::decode_bytes;
+use crate::schema::{RecordField, Root, Schema, SchemaKind, UnionSchema};
+use crate::schema_compatibility::SchemaCompatibility;
+use crate::util::{zag_i32, zag_i64};
+
+/// The top-level writer and reader schemas, which references to named types are resolved against.
+#[derive(Clone)]
+struct Roots<'s> {
+    writer: Rc<Root<'s>>,
+    reader: Option<Rc<Root<'s>>>,
+}
+
+/// A `serde::Deserializer` decoding values from Avro format as they are visited, guided by the
+/// `Schema` they were written with, instead of building an intermediate `Value`.
+///
+/// Strings and bytes are borrowed from the input. When a reader `Schema` is given, record fields
+/// are matched by name and aliases, missing ones get their default value, and enum symbols fall
+/// back to the default symbol, as in schema resolution.
+pub struct SchemaAwareDeserializer<'a, 's, 'de> {
+    input: &'a mut &'de [u8],
+    writer: &'s Schema,
+    reader: Option<&'s Schema>,
+    roots: Roots<'s>,
+}
+
+struct ArrayDeserializer<'a, 's, 'de> {
+    input: &'a mut &'de [u8],
+    writer: &'s Schema,
+    reader: Option<&'s Schema>,
+    roots: Roots<'s>,
+    // Number of items left in the current block.
+    remaining: usize,
+    done: bool,
+}
+
+struct MapDeserializer<'a, 's, 'de> {
+    input: &'a mut &'de [u8],
+    writer: &'s Schema,
+    reader: Option<&'s Schema>,
+    roots: Roots<'s>,
+    remaining: usize,
+    done: bool,
+}
+
+/// The value following the latest key given by a `RecordDeserializer`.
+enum PendingField<'s> {
+    Decoded(&'s Schema, Option<&'s Schema>),
+    Default(&'s RecordField),
+}
+
+struct RecordDeserializer<'a, 's, 'de> {
+    input: &'a mut &'de [u8],
+    roots: Roots<'s>,
+    writer_fields: std::slice::Iter<'s, RecordField>,
+    reader_fields: Option<&'s [RecordField]>,
+    // Reader fields without a writer counterpart, which are given their default value.
+    defaults: std::vec::IntoIter<&'s RecordField>,
+    pending: Option<PendingField<'s>>,
+}
+
+struct VariantDeserializer<'a, 's, 'de> {
+    input: &'a mut &'de [u8],
+    fields: &'s [RecordField],
+    roots: Roots<'s>,
+}
+
+/// Follow `schema` back to the named type it refers to, if it is a `Schema::Ref`, and to the
+/// schema it annotates, if it is a `Schema::Annotated`.
+fn resolve<'s>(schema: &'s Schema, root: &Root<'s>) -> Result<&'s Schema, Error> {
+    match *schema {
+        Schema::Ref { ref name } => {
+            let fullname = name.fullname(None);
+            root.lookup_named(&fullname).ok_or_else(|| {
+                Error::Decode(format!("unknown reference to named type {}", fullname))
+            })
+        }
+        Schema::Annotated { ref inner, .. } => resolve(inner, root),
+        _ => Ok(schema),
+    }
+}
+
+/// Find the branch of the reader `union` that values of the `writer` schema are read as: the
+/// first one of the same type, or else the first one they can be promoted to, following the rules
+/// of schema resolution.
+fn reader_branch<'s>(
+    union: &'s UnionSchema,
+    writer: &Schema,
+    root: &Root<'s>,
+) -> Result<&'s Schema, Error> {
+    let variants = || {
+        union
+            .variants()
+            .iter()
+            .map(move |variant| resolve(variant, root))
+    };
+    let matches = |variant: &Schema| SchemaCompatibility::match_schemas(writer, variant);
+    for variant in variants() {
+        let variant = variant?;
+        if SchemaKind::from(variant) == SchemaKind::from(writer) && matches(variant) {
+            return Ok(variant);
+        }
+    }
+    for variant in variants() {
+        let variant = variant?;
+        if matches(variant) {
+            return Ok(variant);
+        }
+    }
+    Err(Error::SchemaResolution(format!(
+        "no branch of the reader union matches the writer schema {:?}",
+        writer
+    )))
+}
+
+/// Convert the `default` value of a field of the reader `schema` from its JSON encoding, in which
+/// bytes and fixed are strings with one character from U+0000 to U+00FF per byte, into the JSON
+/// value deserialized in its place, in which they are arrays of bytes.
+fn default_value<'s>(
+    default: &JsonValue,
+    schema: &'s Schema,
+    root: &Root<'s>,
+) -> Result<JsonValue, Error> {
+    match *resolve(schema, root)? {
+        Schema::Bytes | Schema::Fixed { .. } | Schema::Decimal { .. } | Schema::Duration => {
+            Ok(JsonValue::Array(
+                decode_bytes(default)?
+                    .into_iter()
+                    .map(JsonValue::from)
+                    .collect(),
+            ))
+        }
+        Schema::Array(ref items) => match default {
+            JsonValue::Array(values) => values
+                .iter()
+                .map(|value| default_value(value, items, root))
+                .collect::<Result<_, _>>()
+                .map(JsonValue::Array),
+            _ => Ok(default.clone()),
+        },
+        Schema::Map(ref values) => match default {
+            JsonValue::Object(entries) => entries
+                .iter()
+                .map(|(key, value)| Ok((key.clone(), default_value(value, values, root)?)))
+                .collect::<Result<_, Error>>()
+                .map(JsonValue::Object),
+            _ => Ok(default.clone()),
+        },
+        Schema::Record { ref fields, .. } => match default {
+            JsonValue::Object(entries) => entries
+                .iter()
+                .map(|(key, value)| {
+                    let value = match fields.iter().find(|field| field.name == *key) {
+                        Some(field) => default_value(value, &field.schema, root)?,
+                        None => value.clone(),
+                    };
+                    Ok((key.clone(), value))
+                })
+                .collect::<Result<_, Error>>()
+                .map(JsonValue::Object),
+            _ => Ok(default.clone()),
+        },
+        // The default value of a union is a value of its first branch.
+        Schema::Union(ref union) => match union.variants().first() {
+            Some(first) => default_value(default, first, root),
+            None => Ok(default.clone()),
+        },
+        _ => Ok(default.clone()),
+    }
+}
+
+fn field_matches(reader: &RecordField, writer: &RecordField) -> bool {
+    reader.name == writer.name
+        || reader
+            .aliases
+            .iter()
+            .flatten()
+            .any(|alias| *alias == writer.name)
+}
+
+/// Read the index of an enum symbol, and find the symbol it is read as.
+fn read_symbol<'s>(
+    input: &mut &[u8],
+    symbols: &'s [String],
+    reader: Option<&'s Schema>,
+) -> Result<&'s str, Error> {
+    let index = zag_i32(input)?;
+    let symbol = symbols
+        .get(index as usize)
+        .ok_or_else(|| Error::Decode("enum symbol index out of bounds".to_string()))?;
+    match reader {
+        Some(Schema::Enum {
+            symbols: ref reader_symbols,
+            ref default,
+            ..
+        }) if !reader_symbols.contains(symbol) => default.as_deref().ok_or_else(|| {
+            Error::SchemaResolution(format!(
+                "Enum symbol {} is not among allowed symbols {:?}",
+                symbol, reader_symbols
+            ))
+        }),
+        _ => Ok(symbol),
+    }
+}
+
+impl<'a, 's, 'de> SchemaAwareDeserializer<'a, 's, 'de> {
+    /// Create a `SchemaAwareDeserializer` reading values of `writer` from the start of `input`,
+    /// as values of `reader` if it is given.
+    pub fn new(input: &'a mut &'de [u8], writer: &'s Schema, reader: Option<&'s Schema>) -> Self {
+        SchemaAwareDeserializer {
+            input,
+            writer,
+            reader,
+            roots: Roots {
+                writer: Rc::new(Root::new(writer)),
+                reader: reader.map(|reader| Rc::new(Root::new(reader))),
+            },
+        }
+    }
+
+    /// Follow the references to named types of both schemas, and read the branch of the value
+    /// if it is written as a union.
+    fn resolved(self) -> Result<Self, Error> {
+        let roots = self.roots;
+        let mut writer = resolve(self.writer, &roots.writer)?;
+        if let Schema::Union(ref union) = *writer {
+            let index = zag_i64(self.input)?;
+            let variant = union
+                .variants()
+                .get(index as usize)
+                .ok_or_else(|| Error::Decode("Union index out of bounds".to_string()))?;
+            writer = resolve(variant, &roots.writer)?;
+        }
+        let reader = match (self.reader, &roots.reader) {
+            (Some(reader), Some(root)) => match *resolve(reader, root)? {
+                Schema::Union(ref union) => Some(reader_branch(union, writer, root)?),
+                ref reader => Some(reader),
+            },
+            _ => None,
+        };
+        Ok(SchemaAwareDeserializer {
+            input: self.input,
+            writer,
+            reader,
+            roots,
+        })
+    }
+}
+
+impl<'a, 's, 'de> de::Deserializer<'de> for SchemaAwareDeserializer<'a, 's, 'de> {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let de = self.resolved()?;
+        let writer = de.writer;
+        match *writer {
+            Schema::Null => visitor.visit_unit(),
+            Schema::Boolean => match take(de.input, 1)?[0] {
+                0u8 => visitor.visit_bool(false),
+                1u8 => visitor.visit_bool(true),
+                _ => Err(Error::Decode("not a bool".to_string())),
+            },
+            Schema::Int | Schema::Date | Schema::TimeMillis => {
+                visitor.visit_i32(zag_i32(de.input)?)
+            }
+            Schema::Long
+            | Schema::TimeMicros
+            | Schema::TimestampMillis
+            | Schema::TimestampMicros => visitor.visit_i64(zag_i64(de.input)?),
+            Schema::Float => {
+                let mut buf = [0u8; std::mem::size_of::<f32>()];
+                buf.copy_from_slice(take(de.input, std::mem::size_of::<f32>())?);
+                visitor.visit_f32(f32::from_le_bytes(buf))
+            }
+            Schema::Double => {
+                let mut buf = [0u8; std::mem::size_of::<f64>()];
+                buf.copy_from_slice(take(de.input, std::mem::size_of::<f64>())?);
+                visitor.visit_f64(f64::from_le_bytes(buf))
+            }
+            Schema::Bytes => {
+                let len = decode_len(de.input)?;
+                visitor.visit_borrowed_bytes(take(de.input, len)?)
+            }
+            Schema::String | Schema::Uuid => {
+                let len = decode_len(de.input)?;
+                let s = from_utf8(take(de.input, len)?)
+                    .map_err(|_| Error::Decode("not a valid utf-8 string".to_string()))?;
+                visitor.visit_borrowed_str(s)
+            }
+            Schema::Fixed { size, .. } => visitor.visit_borrowed_bytes(take(de.input, size)?),
+            Schema::Duration => visitor.visit_borrowed_bytes(take(de.input, 12)?),
+            Schema::Decimal { ref inner, .. } => match *resolve(inner, &de.roots.writer)? {
+                Schema::Fixed { size, .. } => visitor.visit_borrowed_bytes(take(de.input, size)?),
+                _ => {
+                    let len = decode_len(de.input)?;
+                    visitor.visit_borrowed_bytes(take(de.input, len)?)
+                }
+            },
+            Schema::Enum { ref symbols, .. } => {
+                visitor.visit_str(read_symbol(de.input, symbols, de.reader)?)
+            }
+            Schema::Array(ref items) => visitor.visit_seq(ArrayDeserializer {
+                input: de.input,
+                writer: items,
+                reader: match de.reader {
+                    Some(Schema::Array(ref items)) => Some(items),
+                    _ => None,
+                },
+                roots: de.roots,
+                remaining: 0,
+                done: false,
+            }),
+            Schema::Map(ref values) => visitor.visit_map(MapDeserializer {
+                input: de.input,
+                writer: values,
+                reader: match de.reader {
+                    Some(Schema::Map(ref values)) => Some(values),
+                    _ => None,
+                },
+                roots: de.roots,
+                remaining: 0,
+                done: false,
+            }),
+            Schema::Record { ref fields, .. } => {
+                let reader_fields = match de.reader {
+                    Some(Schema::Record { ref fields, .. }) => Some(&fields[..]),
+                    _ => None,
+                };
+                let defaults = reader_fields
+                    .unwrap_or(&[])
+                    .iter()
+                    .filter(|reader| !fields.iter().any(|writer| field_matches(reader, writer)))
+                    .collect::<Vec<_>>();
+                visitor.visit_map(RecordDeserializer {
+                    input: de.input,
+                    roots: de.roots,
+                    writer_fields: fields.iter(),
+                    reader_fields,
+                    defaults: defaults.into_iter(),
+                    pending: None,
+                })
+            }
+            Schema::Union(_) => Err(Error::Decode(
+                "unions cannot immediately contain other unions".to_string(),
+            )),
+            Schema::Ref { .. } | Schema::Annotated { .. } => {
+                unreachable!("resolved schemas are neither references nor annotated")
+            }
+        }
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let de = self.resolved()?;
+        match *de.writer {
+            Schema::Null => visitor.visit_none(),
+            _ => visitor.visit_some(de),
+        }
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        _: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _: &'static str,
+        _: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let de = self.resolved()?;
+        let writer = de.writer;
+        match *writer {
+            Schema::Enum { ref symbols, .. } => {
+                let symbol = read_symbol(de.input, symbols, de.reader)?;
+                visitor.visit_enum(de::value::StrDeserializer::<Error>::new(symbol))
+            }
+            // Enum variants holding data are serialized as a record of their symbol and data.
+            Schema::Record { ref fields, .. } if fields.len() == 2 => {
+                visitor.visit_enum(VariantDeserializer {
+                    input: de.input,
+                    fields,
+                    roots: de.roots,
+                })
+            }
+            _ => Err(de::Error::custom("not an enum")),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 u8 u16 u32 u64 f32 f64 char str string bytes byte_buf unit unit_struct
+        seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+impl<'a, 's, 'de> de::SeqAccess<'de> for ArrayDeserializer<'a, 's, 'de> {
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Self::Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        if self.done {
+            return Ok(None);
+        }
+        if self.remaining == 0 {
+            match decode_block_len(self.input)? {
+                Some(len) => self.remaining = len,
+                None => {
+                    self.done = true;
+                    return Ok(None);
+                }
+            }
+        }
+        self.remaining -= 1;
+        seed.deserialize(SchemaAwareDeserializer {
+            input: &mut *self.input,
+            writer: self.writer,
+            reader: self.reader,
+            roots: self.roots.clone(),
+        })
+        .map(Some)
+    }
+}
+
+impl<'a, 's, 'de> de::MapAccess<'de> for MapDeserializer<'a, 's, 'de> {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        if self.done {
+            return Ok(None);
+        }
+        if self.remaining == 0 {
+            match decode_block_len(self.input)? {
+                Some(len) => self.remaining = len,
+                None => {
+                    self.done = true;
+                    return Ok(None);
+                }
+            }
+        }
+        self.remaining -= 1;
+        let len = decode_len(self.input)?;
+        let key = from_utf8(take(self.input, len)?)
+            .map_err(|_| Error::Decode("not a valid utf-8 string".to_string()))?;
+        seed.deserialize(BorrowedStrDeserializer::new(key))
+            .map(Some)
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        seed.deserialize(SchemaAwareDeserializer {
+            input: &mut *self.input,
+            writer: self.writer,
+            reader: self.reader,
+            roots: self.roots.clone(),
+        })
+    }
+}
+
+impl<'a, 's, 'de> de::MapAccess<'de> for RecordDeserializer<'a, 's, 'de> {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        for writer in self.writer_fields.by_ref() {
+            let name = match self.reader_fields {
+                None => {
+                    self.pending = Some(PendingField::Decoded(&writer.schema, None));
+                    &writer.name
+                }
+                Some(reader_fields) => {
+                    match reader_fields
+                        .iter()
+                        .find(|reader| field_matches(reader, writer))
+                    {
+                        Some(reader) => {
+                            self.pending =
+                                Some(PendingField::Decoded(&writer.schema, Some(&reader.schema)));
+                            &reader.name
+                        }
+                        None => {
+                            // The field is not read, but it still has to be skipped.
+                            de::Deserializer::deserialize_ignored_any(
+                                SchemaAwareDeserializer {
+                                    input: &mut *self.input,
+                                    writer: &writer.schema,
+                                    reader: None,
+                                    roots: self.roots.clone(),
+                                },
+                                IgnoredAny,
+                            )?;
+                            continue;
+                        }
+                    }
+                }
+            };
+            return seed
+                .deserialize(de::value::StrDeserializer::<Error>::new(name))
+                .map(Some);
+        }
+
+        match self.defaults.next() {
+            Some(reader) if reader.default.is_some() => {
+                self.pending = Some(PendingField::Default(reader));
+                seed.deserialize(de::value::StrDeserializer::<Error>::new(&reader.name))
+                    .map(Some)
+            }
+            Some(reader) => Err(Error::SchemaResolution(format!(
+                "missing field {} in record",
+                reader.name
+            ))),
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        match self.pending.take() {
+            Some(PendingField::Decoded(writer, reader)) => {
+                seed.deserialize(SchemaAwareDeserializer {
+                    input: &mut *self.input,
+                    writer,
+                    reader,
+                    roots: self.roots.clone(),
+                })
+            }
+            Some(PendingField::Default(reader)) => {
+                let default = match self.roots.reader {
+                    Some(ref root) => default_value(
+                        reader.default.as_ref().unwrap_or(&JsonValue::Null),
+                        &reader.schema,
+                        root,
+                    )?,
+                    None => reader.default.clone().unwrap_or_default(),
+                };
+                seed.deserialize(default).map_err(Error::from)
+            }
+            None => Err(de::Error::custom("value requested before its key")),
+        }
+    }
+}
+
+impl<'a, 's, 'de> de::EnumAccess<'de> for VariantDeserializer<'a, 's, 'de> {
+    type Error = Error;
+    type Variant = Self;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self::Variant), Self::Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let symbol = match *resolve(&self.fields[0].schema, &self.roots.writer)? {
+            Schema::Enum { ref symbols, .. } => read_symbol(self.input, symbols, None)?,
+            _ => {
+                return Err(de::Error::custom(
+                    "Expected first field of type Enum for the variant",
+                ))
+            }
+        };
+        let variant = seed.deserialize(de::value::StrDeserializer::<Error>::new(symbol))?;
+        Ok((variant, self))
+    }
+}
+
+impl<'a, 's, 'de> VariantDeserializer<'a, 's, 'de> {
+    /// Deserializer for the data of the variant, which has no reader schema.
+    fn data(self) -> SchemaAwareDeserializer<'a, 's, 'de> {
+        SchemaAwareDeserializer {
+            input: self.input,
+            writer: &self.fields[1].schema,
+            reader: None,
+            roots: Roots {
+                writer: self.roots.writer.clone(),
+                reader: None,
+            },
+        }
+    }
+}
+
+impl<'a, 's, 'de> de::VariantAccess<'de> for VariantDeserializer<'a, 's, 'de> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Error> {
+        de::Deserializer::deserialize_ignored_any(self.data(), IgnoredAny).map(|_| ())
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value, Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        seed.deserialize(self.data())
+    }
+
+    fn tuple_variant<V>(self, _: usize, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        de::Deserializer::deserialize_seq(self.data(), visitor)
+    }
+
+    fn struct_variant<V>(self, _: &'static [&'static str], visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        de::Deserializer::deserialize_map(self.data(), visitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::to_avro_datum_ser;
+    use serde::{Deserialize, Serialize};
+    use std::collections::HashMap;
+
+    #[derive(Debug, Deserialize, PartialEq, Serialize)]
+    enum Color {
+        Red,
+        Green,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq, Serialize)]
+    struct Inner {
+        z: f32,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq, Serialize)]
+    struct Test {
+        a: i64,
+        b: String,
+        c: Option<i32>,
+        d: Vec<Inner>,
+        e: HashMap<String, bool>,
+        f: Color,
+        g: Option<Inner>,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Borrowed<'a> {
+        b: &'a str,
+        h: &'a [u8],
+    }
+
+    const SCHEMA: &str = r#"
+    {
+        "type": "record",
+        "name": "Test",
+        "fields": [
+            {"name": "a", "type": "long"},
+            {"name": "b", "type": "string"},
+            {"name": "c", "type": ["null", "int"]},
+            {
+                "name": "d",
+                "type": {
+                    "type": "array",
+                    "items": {
+                        "type": "record",
+                        "name": "Inner",
+                        "fields": [{"name": "z", "type": "float"}]
+                    }
+                }
+            },
+            {"name": "e", "type": {"type": "map", "values": "boolean"}},
+            {"name": "f", "type": {"type": "enum", "name": "Color", "symbols": ["Red", "Green"]}},
+            {"name": "g", "type": ["null", "Inner"]}
+        ]
+    }
+    "#;
+
+    fn test_value() -> Test {
+        let mut e = HashMap::new();
+        e.insert("key".to_string(), true);
+        Test {
+            a: 27,
+            b: "foo".to_string(),
+            c: Some(3),
+            d: vec![Inner { z: 1.5 }, Inner { z: -2.0 }],
+            e,
+            f: Color::Green,
+            g: Some(Inner { z: 0.25 }),
+        }
+    }
+
+    fn deserialize<'de, T: de::Deserialize<'de>>(
+        input: &mut &'de [u8],
+        writer: &Schema,
+        reader: Option<&Schema>,
+    ) -> Result<T, Error> {
+        T::deserialize(SchemaAwareDeserializer::new(input, writer, reader))
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let schema = Schema::parse_str(SCHEMA).unwrap();
+        let value = test_value();
+        let encoded = to_avro_datum_ser(&schema, &value).unwrap();
+
+        let mut input = &encoded[..];
+        assert_eq!(
+            deserialize::<Test>(&mut input, &schema, None).unwrap(),
+            value
+        );
+        assert!(input.is_empty());
+    }
+
+    #[test]
+    fn test_borrowed() {
+        let schema = Schema::parse_str(
+            r#"
+            {
+                "type": "record",
+                "name": "Borrowed",
+                "fields": [
+                    {"name": "a", "type": "long"},
+                    {"name": "b", "type": "string"},
+                    {"name": "h", "type": "bytes"}
+                ]
+            }
+            "#,
+        )
+        .unwrap();
+        let encoded: &[u8] = &[54, 6, 102, 111, 111, 4, 1, 2];
+
+        let mut input = encoded;
+        let value = deserialize::<Borrowed>(&mut input, &schema, None).unwrap();
+        assert_eq!(
+            value,
+            Borrowed {
+                b: "foo",
+                h: &[1, 2],
+            }
+        );
+        assert!(input.is_empty());
+    }
+
+    #[test]
+    fn test_reader_schema() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        enum ReaderColor {
+            Red,
+            Blue,
+        }
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Reader {
+            renamed: String,
+            c: Option<i64>,
+            f: ReaderColor,
+            added: Vec<i32>,
+        }
+
+        let writer = Schema::parse_str(SCHEMA).unwrap();
+        let reader = Schema::parse_str(
+            r#"
+            {
+                "type": "record",
+                "name": "Test",
+                "fields": [
+                    {"name": "renamed", "aliases": ["b"], "type": "string"},
+                    {"name": "c", "type": ["null", "long"]},
+                    {
+                        "name": "f",
+                        "type": {
+                            "type": "enum",
+                            "name": "Color",
+                            "symbols": ["Red", "Blue"],
+                            "default": "Blue"
+                        }
+                    },
+                    {"name": "added", "type": {"type": "array", "items": "int"}, "default": [1]}
+                ]
+            }
+            "#,
+        )
+        .unwrap();
+        let encoded = to_avro_datum_ser(&writer, test_value()).unwrap();
+
+        let mut input = &encoded[..];
+        assert_eq!(
+            deserialize::<Reader>(&mut input, &writer, Some(&reader)).unwrap(),
+            Reader {
+                renamed: "foo".to_string(),
+                c: Some(3),
+                f: ReaderColor::Blue,
+                added: vec![1],
+            }
+        );
+        assert!(input.is_empty());
+    }
+
+    #[test]
+    fn test_promotions_and_defaults() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Reader {
+            a: Option<i64>,
+            bytes: Vec<u8>,
+            nested: Option<Vec<u8>>,
+        }
+
+        let writer = Schema::parse_str(
+            r#"{"type": "record", "name": "Test", "fields": [{"name": "a", "type": "int"}]}"#,
+        )
+        .unwrap();
+        let reader = Schema::parse_str(
+            r#"
+            {
+                "type": "record",
+                "name": "Test",
+                "fields": [
+                    {"name": "a", "type": ["null", "long"]},
+                    {"name": "bytes", "type": "bytes", "default": "\u0000a\u00ff"},
+                    {"name": "nested", "type": ["bytes", "null"], "default": "\u00e9"}
+                ]
+            }
+            "#,
+        )
+        .unwrap();
+
+        let mut input: &[u8] = &[6];
+        assert_eq!(
+            deserialize::<Reader>(&mut input, &writer, Some(&reader)).unwrap(),
+            Reader {
+                a: Some(3),
+                bytes: vec![0, 97, 0xff],
+                nested: Some(vec![0xe9]),
+            }
+        );
+        assert!(input.is_empty());
+
+        let reader = Schema::parse_str(r#"["null", "string"]"#).unwrap();
+        assert!(
+            deserialize::<Option<String>>(&mut &[6u8][..], &Schema::Int, Some(&reader)).is_err()
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let schema = Schema::parse_str(SCHEMA).unwrap();
+        let encoded = to_avro_datum_ser(&schema, test_value()).unwrap();
+
+        assert!(deserialize::<Test>(&mut &encoded[..encoded.len() - 1], &schema, None).is_err());
+        assert!(deserialize::<i32>(&mut &[1u8][..], &Schema::String, None).is_err());
+    }
+}
@@ -7,8 +7,8 @@ use uuid::Uuid;
 use crate::decimal::Decimal;
 use crate::duration::Duration;
 use crate::errors::{AvroResult, Error};
-use crate::schema::Schema;
-use crate::types::Value;
+use crate::schema::{Root, Schema};
+use crate::types::{Value, ValueRef};
 use crate::util::{safe_len, zag_i32, zag_i64};
 
 #[inline]
@@ -22,12 +22,18 @@ fn decode_int<R: Read>(reader: &mut R) -> AvroResult<Value> {
 }
 
 #[inline]
-fn decode_len<R: Read>(reader: &mut R) -> AvroResult<usize> {
+pub(crate) fn decode_len<R: Read>(reader: &mut R) -> AvroResult<usize> {
     zag_i64(reader).and_then(|len| safe_len(len as usize))
 }
 
 /// Decode a `Value` from avro format given its `Schema`.
 pub fn decode<R: Read>(schema: &Schema, reader: &mut R) -> AvroResult<Value> {
+    decode_internal(schema, &Root::new(schema), reader)
+}
+
+/// Decode a `Value` from avro format given its `Schema`, following references to named types
+/// through `root`, the top-level `Schema` it is part of.
+fn decode_internal<R: Read>(schema: &Schema, root: &Root, reader: &mut R) -> AvroResult<Value> {
     match *schema {
         Schema::Null => Ok(Value::Null),
         Schema::Boolean => {
@@ -41,13 +47,13 @@ pub fn decode<R: Read>(schema: &Schema, reader: &mut R) -> AvroResult<Value> {
             }
         }
         Schema::Decimal { ref inner, .. } => match **inner {
-            Schema::Fixed { .. } => match decode(inner, reader)? {
+            Schema::Fixed { .. } => match decode_internal(inner, root, reader)? {
                 Value::Fixed(_, bytes) => Ok(Value::Decimal(Decimal::from(bytes))),
                 _ => Err(Error::Decode(
                     "not a fixed value, required for decimal with fixed schema".to_string(),
                 )),
             },
-            Schema::Bytes => match decode(inner, reader)? {
+            Schema::Bytes => match decode_internal(inner, root, reader)? {
                 Value::Bytes(bytes) => Ok(Value::Decimal(Decimal::from(bytes))),
                 _ => Err(Error::Decode(
                     "not a bytes value, required for decimal with bytes schema".to_string(),
@@ -127,7 +133,7 @@ pub fn decode<R: Read>(schema: &Schema, reader: &mut R) -> AvroResult<Value> {
 
                 items.reserve(len as usize);
                 for _ in 0..len {
-                    items.push(decode(inner, reader)?);
+                    items.push(decode_internal(inner, root, reader)?);
                 }
             }
 
@@ -152,7 +158,7 @@ pub fn decode<R: Read>(schema: &Schema, reader: &mut R) -> AvroResult<Value> {
                 items.reserve(len);
                 for _ in 0..len {
                     if let Value::String(key) = decode(&Schema::String, reader)? {
-                        let value = decode(inner, reader)?;
+                        let value = decode_internal(inner, root, reader)?;
                         items.insert(key, value);
                     } else {
                         return Err(Error::Decode("map key is not a string".to_string()));
@@ -168,7 +174,7 @@ pub fn decode<R: Read>(schema: &Schema, reader: &mut R) -> AvroResult<Value> {
             let variant = variants
                 .get(index as usize)
                 .ok_or_else(|| Error::Decode("Union index out of bounds".to_string()))?;
-            let value = decode(variant, reader)?;
+            let value = decode_internal(variant, root, reader)?;
             Ok(Value::Union(Box::new(value)))
         }
         Schema::Record { ref fields, .. } => {
@@ -176,7 +182,10 @@ pub fn decode<R: Read>(schema: &Schema, reader: &mut R) -> AvroResult<Value> {
             let mut items = Vec::with_capacity(fields.len());
             for field in fields {
                 // This clone is also expensive. See if we can do away with it...
-                items.push((field.name.clone(), decode(&field.schema, reader)?));
+                items.push((
+                    field.name.clone(),
+                    decode_internal(&field.schema, root, reader)?,
+                ));
             }
             Ok(Value::Record(items))
         }
@@ -192,6 +201,169 @@ pub fn decode<R: Read>(schema: &Schema, reader: &mut R) -> AvroResult<Value> {
                 Err(Error::Decode("enum symbol not found".to_string()))
             }
         }
+        Schema::Ref { ref name } => {
+            let fullname = name.fullname(None);
+            let schema = root.lookup_named(&fullname).ok_or_else(|| {
+                Error::Decode(format!("unknown reference to named type {}", fullname))
+            })?;
+            decode_internal(schema, root, reader)
+        }
+        Schema::Annotated { ref inner, .. } => decode_internal(inner, root, reader),
+    }
+}
+
+/// Take the next `len` bytes out of `bytes`, without copying them.
+pub(crate) fn take<'a>(bytes: &mut &'a [u8], len: usize) -> AvroResult<&'a [u8]> {
+    if bytes.len() < len {
+        return Err(Error::IO(std::io::ErrorKind::UnexpectedEof.into()));
+    }
+    let (taken, rest) = bytes.split_at(len);
+    *bytes = rest;
+    Ok(taken)
+}
+
+/// Read the number of items of the next block of an array or a map, or `None` when the array or
+/// map is over.
+pub(crate) fn decode_block_len(bytes: &mut &[u8]) -> AvroResult<Option<usize>> {
+    let raw_len = zag_i64(bytes)?;
+    let len = match raw_len.cmp(&0) {
+        std::cmp::Ordering::Equal => return Ok(None),
+        std::cmp::Ordering::Less => {
+            let _size = zag_i64(bytes)?;
+            -raw_len
+        }
+        std::cmp::Ordering::Greater => raw_len,
+    };
+    safe_len(len as usize).map(Some)
+}
+
+/// Decode a `ValueRef` from avro format given its `Schema`, borrowing its strings and bytes from
+/// `bytes`, which is advanced past the decoded value.
+pub fn decode_ref<'a>(schema: &'a Schema, bytes: &mut &'a [u8]) -> AvroResult<ValueRef<'a>> {
+    decode_ref_internal(schema, &Root::new(schema), bytes)
+}
+
+fn decode_ref_internal<'a>(
+    schema: &'a Schema,
+    root: &Root<'a>,
+    bytes: &mut &'a [u8],
+) -> AvroResult<ValueRef<'a>> {
+    match *schema {
+        Schema::Null => Ok(ValueRef::Null),
+        Schema::Boolean => match take(bytes, 1)?[0] {
+            0u8 => Ok(ValueRef::Boolean(false)),
+            1u8 => Ok(ValueRef::Boolean(true)),
+            _ => Err(Error::Decode("not a bool".to_string())),
+        },
+        Schema::Decimal { ref inner, .. } => match **inner {
+            Schema::Fixed { size, .. } => take(bytes, size).map(ValueRef::Decimal),
+            Schema::Bytes => {
+                let len = decode_len(bytes)?;
+                take(bytes, len).map(ValueRef::Decimal)
+            }
+            _ => Err(Error::Decode(
+                "not a fixed or bytes type, required for decimal schema".to_string(),
+            )),
+        },
+        Schema::Uuid => match decode_ref_internal(&Schema::String, root, bytes)? {
+            ValueRef::String(s) => Ok(ValueRef::Uuid(Uuid::from_str(s)?)),
+            _ => Err(Error::Decode(
+                "not a string type, required for uuid".to_string(),
+            )),
+        },
+        Schema::Int => zag_i32(bytes).map(ValueRef::Int),
+        Schema::Date => zag_i32(bytes).map(ValueRef::Date),
+        Schema::TimeMillis => zag_i32(bytes).map(ValueRef::TimeMillis),
+        Schema::Long => zag_i64(bytes).map(ValueRef::Long),
+        Schema::TimeMicros => zag_i64(bytes).map(ValueRef::TimeMicros),
+        Schema::TimestampMillis => zag_i64(bytes).map(ValueRef::TimestampMillis),
+        Schema::TimestampMicros => zag_i64(bytes).map(ValueRef::TimestampMicros),
+        Schema::Duration => {
+            let mut buf = [0u8; 12];
+            buf.copy_from_slice(take(bytes, 12)?);
+            Ok(ValueRef::Duration(Duration::from(buf)))
+        }
+        Schema::Float => {
+            let mut buf = [0u8; std::mem::size_of::<f32>()];
+            buf.copy_from_slice(take(bytes, std::mem::size_of::<f32>())?);
+            Ok(ValueRef::Float(f32::from_le_bytes(buf)))
+        }
+        Schema::Double => {
+            let mut buf = [0u8; std::mem::size_of::<f64>()];
+            buf.copy_from_slice(take(bytes, std::mem::size_of::<f64>())?);
+            Ok(ValueRef::Double(f64::from_le_bytes(buf)))
+        }
+        Schema::Bytes => {
+            let len = decode_len(bytes)?;
+            take(bytes, len).map(ValueRef::Bytes)
+        }
+        Schema::String => {
+            let len = decode_len(bytes)?;
+            std::str::from_utf8(take(bytes, len)?)
+                .map(ValueRef::String)
+                .map_err(|_| Error::Decode("not a valid utf-8 string".to_string()))
+        }
+        Schema::Fixed { size, .. } => take(bytes, size).map(|buf| ValueRef::Fixed(size, buf)),
+        Schema::Array(ref inner) => {
+            let mut items = Vec::new();
+            while let Some(len) = decode_block_len(bytes)? {
+                items.reserve(len);
+                for _ in 0..len {
+                    items.push(decode_ref_internal(inner, root, bytes)?);
+                }
+            }
+            Ok(ValueRef::Array(items))
+        }
+        Schema::Map(ref inner) => {
+            let mut items = Vec::new();
+            while let Some(len) = decode_block_len(bytes)? {
+                items.reserve(len);
+                for _ in 0..len {
+                    if let ValueRef::String(key) =
+                        decode_ref_internal(&Schema::String, root, bytes)?
+                    {
+                        items.push((key, decode_ref_internal(inner, root, bytes)?));
+                    } else {
+                        return Err(Error::Decode("map key is not a string".to_string()));
+                    }
+                }
+            }
+            Ok(ValueRef::Map(items))
+        }
+        Schema::Union(ref inner) => {
+            let index = zag_i64(bytes)?;
+            let variant = inner
+                .variants()
+                .get(index as usize)
+                .ok_or_else(|| Error::Decode("Union index out of bounds".to_string()))?;
+            let value = decode_ref_internal(variant, root, bytes)?;
+            Ok(ValueRef::Union(Box::new(value)))
+        }
+        Schema::Record { ref fields, .. } => {
+            let mut items = Vec::with_capacity(fields.len());
+            for field in fields {
+                items.push((
+                    field.name.as_str(),
+                    decode_ref_internal(&field.schema, root, bytes)?,
+                ));
+            }
+            Ok(ValueRef::Record(items))
+        }
+        Schema::Enum { ref symbols, .. } => {
+            let index = zag_i32(bytes)?;
+            symbols
+                .get(index as usize)
+                .map(|symbol| ValueRef::Enum(index, symbol))
+                .ok_or_else(|| Error::Decode("enum symbol index out of bounds".to_string()))
+        }
+        Schema::Ref { ref name } => {
+            let fullname = name.fullname(None);
+            let schema = root.lookup_named(&fullname).ok_or_else(|| {
+                Error::Decode(format!("unknown reference to named type {}", fullname))
+            })?;
+            decode_ref_internal(schema, root, bytes)
+        }
+        Schema::Annotated { ref inner, .. } => decode_ref_internal(inner, root, bytes),
     }
 }
 
@@ -207,6 +379,43 @@ mod tests {
         assert_eq!(Array(vec!(Int(1), Int(2), Int(3))), result.unwrap());
     }
 
+    #[test]
+    fn test_decode_ref() {
+        let schema = Schema::parse_str(
+            r#"
+            {
+                "type": "record",
+                "name": "test",
+                "fields": [
+                    {"name": "a", "type": {"type": "map", "values": "bytes"}},
+                    {"name": "b", "type": ["null", "string"]},
+                    {"name": "c", "type": {"type": "enum", "name": "e", "symbols": ["x", "y"]}}
+                ]
+            }
+            "#,
+        )
+        .unwrap();
+        let input: &[u8] = &[2, 2, 107, 4, 1, 2, 0, 2, 6, 102, 111, 111, 2];
+
+        let mut bytes = input;
+        let value = decode_ref(&schema, &mut bytes).unwrap();
+        assert!(bytes.is_empty());
+        assert_eq!(
+            value,
+            ValueRef::Record(vec![
+                ("a", ValueRef::Map(vec![("k", ValueRef::Bytes(&[1, 2]))])),
+                ("b", ValueRef::Union(Box::new(ValueRef::String("foo")))),
+                ("c", ValueRef::Enum(1, "y")),
+            ])
+        );
+        assert_eq!(
+            Value::from(value),
+            decode(&schema, &mut &input[..]).unwrap()
+        );
+
+        assert!(decode_ref(&schema, &mut &input[..input.len() - 2]).is_err());
+    }
+
     #[test]
     fn test_decode_array_with_size() {
         let mut input: &[u8] = &[5, 6, 2, 4, 6, 0];
@@ -239,6 +448,7 @@ mod tests {
         let inner = Box::new(Schema::Fixed {
             size: 2,
             name: Name::new("decimal"),
+            custom_attributes: Default::default(),
         });
         let schema = Schema::Decimal {
             inner,
@@ -249,7 +459,7 @@ mod tests {
         let value = Value::Decimal(Decimal::from(bigint.to_signed_bytes_be()));
 
         let mut buffer = Vec::new();
-        encode(&value, &schema, &mut buffer);
+        encode(&value, &schema, &mut buffer).unwrap();
 
         let mut bytes = &buffer[..];
         let result = decode(&schema, &mut bytes).unwrap();
@@ -263,6 +473,7 @@ mod tests {
         let inner = Box::new(Schema::Fixed {
             size: 13,
             name: Name::new("decimal"),
+            custom_attributes: Default::default(),
         });
         let schema = Schema::Decimal {
             inner,
@@ -274,7 +485,7 @@ mod tests {
         ));
         let mut buffer = Vec::<u8>::new();
 
-        encode(&value, &schema, &mut buffer);
+        encode(&value, &schema, &mut buffer).unwrap();
         let mut bytes: &[u8] = &buffer[..];
         let result = decode(&schema, &mut bytes).unwrap();
         assert_eq!(result, value);
@@ -1,4 +1,5 @@
-use crate::schema::Schema;
+use crate::errors::{AvroResult, Error};
+use crate::schema::{Root, Schema};
 use crate::types::Value;
 use crate::util::{zig_i32, zig_i64};
 use std::convert::TryInto;
@@ -8,13 +9,13 @@ use std::convert::TryInto;
 /// **NOTE** This will not perform schema validation. The value is assumed to
 /// be valid with regards to the schema. Schema are needed only to guide the
 /// encoding for complex type values.
-pub fn encode(value: &Value, schema: &Schema, buffer: &mut Vec<u8>) {
+pub fn encode(value: &Value, schema: &Schema, buffer: &mut Vec<u8>) -> AvroResult<()> {
     encode_ref(&value, schema, buffer)
 }
 
 fn encode_bytes<B: AsRef<[u8]> + ?Sized>(s: &B, buffer: &mut Vec<u8>) {
     let bytes = s.as_ref();
-    encode(&Value::Long(bytes.len() as i64), &Schema::Long, buffer);
+    encode_long(bytes.len() as i64, buffer);
     buffer.extend_from_slice(bytes);
 }
 
@@ -31,7 +32,29 @@ fn encode_int(i: i32, buffer: &mut Vec<u8>) {
 /// **NOTE** This will not perform schema validation. The value is assumed to
 /// be valid with regards to the schema. Schema are needed only to guide the
 /// encoding for complex type values.
-pub fn encode_ref(value: &Value, schema: &Schema, buffer: &mut Vec<u8>) {
+pub fn encode_ref(value: &Value, schema: &Schema, buffer: &mut Vec<u8>) -> AvroResult<()> {
+    encode_internal(value, schema, &Root::new(schema), buffer)
+}
+
+/// Encode a `Value` into avro format, following references to named types through `root`, the
+/// top-level `Schema` that `schema` is part of.
+fn encode_internal(
+    value: &Value,
+    schema: &Schema,
+    root: &Root,
+    buffer: &mut Vec<u8>,
+) -> AvroResult<()> {
+    if let Schema::Ref { ref name } = *schema {
+        let fullname = name.fullname(None);
+        let schema = root.lookup_named(&fullname).ok_or_else(|| {
+            Error::Validation(format!("Unknown reference to named type {}", fullname))
+        })?;
+        return encode_internal(value, schema, root, buffer);
+    }
+    if let Schema::Annotated { ref inner, .. } = *schema {
+        return encode_internal(value, inner, root, buffer);
+    }
+
     match value {
         Value::Null => (),
         Value::Boolean(b) => buffer.push(if *b { 1u8 } else { 0u8 }),
@@ -54,9 +77,9 @@ pub fn encode_ref(value: &Value, schema: &Schema, buffer: &mut Vec<u8>) {
                             num_bytes, size
                         );
                     }
-                    encode(&Value::Fixed(size, bytes), inner, buffer)
+                    encode(&Value::Fixed(size, bytes), inner, buffer)?
                 }
-                Schema::Bytes => encode(&Value::Bytes(decimal.try_into().unwrap()), inner, buffer),
+                Schema::Bytes => encode(&Value::Bytes(decimal.try_into().unwrap()), inner, buffer)?,
                 _ => panic!("invalid inner type for decimal: {:?}", inner),
             },
             _ => panic!("invalid type for decimal: {:?}", schema),
@@ -85,10 +108,10 @@ pub fn encode_ref(value: &Value, schema: &Schema, buffer: &mut Vec<u8>) {
                 // Find the schema that is matched here. Due to validation, this should always
                 // return a value.
                 let (idx, inner_schema) = inner
-                    .find_schema(item)
+                    .find_schema_with_root(item, root)
                     .expect("Invalid Union validation occurred");
                 encode_long(idx as i64, buffer);
-                encode_ref(&*item, inner_schema, buffer);
+                encode_internal(&*item, inner_schema, root, buffer)?;
             }
         }
         Value::Array(items) => {
@@ -96,7 +119,7 @@ pub fn encode_ref(value: &Value, schema: &Schema, buffer: &mut Vec<u8>) {
                 if !items.is_empty() {
                     encode_long(items.len() as i64, buffer);
                     for item in items.iter() {
-                        encode_ref(item, inner, buffer);
+                        encode_internal(item, inner, root, buffer)?;
                     }
                 }
                 buffer.push(0u8);
@@ -108,7 +131,7 @@ pub fn encode_ref(value: &Value, schema: &Schema, buffer: &mut Vec<u8>) {
                     encode_long(items.len() as i64, buffer);
                     for (key, value) in items {
                         encode_bytes(key, buffer);
-                        encode_ref(value, inner, buffer);
+                        encode_internal(value, inner, root, buffer)?;
                     }
                 }
                 buffer.push(0u8);
@@ -121,22 +144,24 @@ pub fn encode_ref(value: &Value, schema: &Schema, buffer: &mut Vec<u8>) {
             } = *schema
             {
                 for (i, &(_, ref value)) in fields.iter().enumerate() {
-                    encode_ref(value, &schema_fields[i].schema, buffer);
+                    encode_internal(value, &schema_fields[i].schema, root, buffer)?;
                 }
             }
         }
     }
+    Ok(())
 }
 
-pub fn encode_to_vec(value: &Value, schema: &Schema) -> Vec<u8> {
+pub fn encode_to_vec(value: &Value, schema: &Schema) -> AvroResult<Vec<u8>> {
     let mut buffer = Vec::new();
-    encode(&value, schema, &mut buffer);
-    buffer
+    encode(&value, schema, &mut buffer)?;
+    Ok(buffer)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::schema::Name;
     use std::collections::HashMap;
 
     #[test]
@@ -147,7 +172,8 @@ mod tests {
             &Value::Array(empty),
             &Schema::Array(Box::new(Schema::Int)),
             &mut buf,
-        );
+        )
+        .unwrap();
         assert_eq!(vec![0u8], buf);
     }
 
@@ -159,7 +185,17 @@ mod tests {
             &Value::Map(empty),
             &Schema::Map(Box::new(Schema::Int)),
             &mut buf,
-        );
+        )
+        .unwrap();
         assert_eq!(vec![0u8], buf);
     }
+
+    #[test]
+    fn test_encode_unknown_reference() {
+        let mut buf = Vec::new();
+        let schema = Schema::Ref {
+            name: Name::new("Unknown"),
+        };
+        assert!(encode(&Value::Null, &schema, &mut buf).is_err());
+    }
 }
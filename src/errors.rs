@@ -14,6 +14,14 @@ pub enum Error {
     #[error("unrecognized codec: {0:?}")]
     Codec(String),
 
+    /// Error due to a `CodecOptions` parameter out of the range its codec accepts
+    #[error("invalid codec options: {0}")]
+    InvalidCodecOptions(String),
+
+    /// Error happened while registering a custom codec under the name of a built-in one
+    #[error("cannot register a custom codec under the name of the built-in codec {0:?}")]
+    BuiltInCodec(String),
+
     /// Errors happened while decoding Avro data (except for `std::io::Error`)
     #[error("decoding error: {0}")]
     Decode(String),
@@ -62,6 +70,44 @@ pub enum Error {
     #[error(transparent)]
     FromUtf8(#[from] std::string::FromUtf8Error),
 
+    /// Error happened while reading single-object encoded data that does not start with the
+    /// single-object marker
+    #[error("single-object encoded data must start with [0xC3, 0x01], found {0:x?}")]
+    SingleObjectMarker([u8; 2]),
+
+    /// Error happened while looking up a schema by its fingerprint
+    #[error("no schema with fingerprint {0:x}")]
+    UnknownSchemaFingerprint(u64),
+
+    /// Error happened while reading data in the Confluent wire format that does not start with
+    /// its magic byte
+    #[error("data in the Confluent wire format must start with 0, found {0}")]
+    ConfluentMagicByte(u8),
+
+    /// Error happened while looking up a schema by its id
+    #[error("no schema with id {0}")]
+    UnknownSchemaId(u32),
+
+    /// Error happened while looking up the id of a schema
+    #[error("schema is not registered: {0}")]
+    UnregisteredSchema(String),
+
+    /// Error happened while adding user metadata under a key reserved for the specification
+    #[error("metadata keys starting with \"avro.\" are reserved, found {0:?}")]
+    InvalidMetadataKey(String),
+
+    /// Error happened while changing the header of a file after writing it
+    #[error("the file header has already been written")]
+    FileHeaderAlreadyWritten,
+
+    /// Error happened while appending to a file with another schema than the one of the writer
+    #[error("the schema of the file does not match the schema of the writer")]
+    FileSchemaMismatch,
+
+    /// Error happening when a Snappy block is too short to end with its CRC32 checksum
+    #[error("Snappy block of {0} bytes is too short to hold its CRC32 checksum")]
+    SnappyDecompress(usize),
+
     /// Error happening when there is a mismatch of the snappy CRC
     #[error("bad Snappy CRC32; expected {expected:x} but got {found:x}")]
     SnappyCrcError { expected: u32, found: u32 },
@@ -0,0 +1,249 @@
+//! Logic handling the framing of Avro datums over streams of bytes, with the codec traits of
+//! tokio-util.
+use std::convert::TryFrom;
+use std::io::ErrorKind;
+
+use bytes::{Buf, BytesMut};
+use tokio_util::codec::{Decoder, Encoder};
+
+use crate::errors::{AvroResult, Error};
+use crate::rabin::Rabin;
+use crate::reader::{from_avro_datum, SchemaStore, SingleObjectReader};
+use crate::schema::Schema;
+use crate::types::Value;
+use crate::util;
+use crate::writer::{to_avro_datum, SINGLE_OBJECT_MARKER};
+
+/// How the datums are delimited from each other by a `DatumCodec`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Framing {
+    /// Each datum is preceded by its length, as an Avro `long`.
+    LengthDelimited,
+    /// Each datum is in the [single-object encoding], which delimits it by the end of its
+    /// encoding.
+    ///
+    /// As its length is not known beforehand, a datum split across several reads is decoded again
+    /// from its start as its bytes arrive, which makes `LengthDelimited` a better fit for large
+    /// datums.
+    ///
+    /// [single-object encoding]: https://avro.apache.org/docs/current/spec.html#single_object_encoding
+    SingleObject,
+}
+
+/// The only `Schema` a `DatumCodec` reads single-object encoded datums with.
+struct FixedSchema {
+    schema: Schema,
+    fingerprint: u64,
+}
+
+impl SchemaStore for FixedSchema {
+    fn find_by_fingerprint(&self, fingerprint: u64) -> Option<&Schema> {
+        if fingerprint == self.fingerprint {
+            Some(&self.schema)
+        } else {
+            None
+        }
+    }
+}
+
+/// Codec framing the Avro datums of a fixed `Schema` over a stream of bytes, available with the
+/// `async` feature.
+///
+/// It implements the `Encoder` and `Decoder` traits of tokio-util, so that a `Framed` stream
+/// reads and writes values:
+///
+/// ```
+/// use avro_rs::{DatumCodec, Framing, Schema};
+/// use futures_util::{SinkExt, StreamExt};
+/// use tokio_util::codec::Framed;
+///
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() {
+/// let schema = Schema::parse_str(r#""long""#).unwrap();
+/// let (client, server) = tokio::io::duplex(64);
+/// let codec = DatumCodec::new(schema.clone(), Framing::LengthDelimited);
+/// let mut sink = Framed::new(client, codec);
+/// let mut stream = Framed::new(server, DatumCodec::new(schema, Framing::LengthDelimited));
+/// sink.send(27i64).await.unwrap();
+/// let value = stream.next().await.unwrap().unwrap();
+/// # }
+/// ```
+pub struct DatumCodec {
+    schema: FixedSchema,
+    framing: Framing,
+}
+
+impl DatumCodec {
+    /// Create a `DatumCodec` for datums of the given `Schema`, delimited with `framing`.
+    pub fn new(schema: Schema, framing: Framing) -> Self {
+        let fingerprint = Rabin::fingerprint64(&schema);
+        DatumCodec {
+            schema: FixedSchema {
+                schema,
+                fingerprint,
+            },
+            framing,
+        }
+    }
+
+    /// Get a reference to the `Schema` associated to a `DatumCodec`.
+    pub fn schema(&self) -> &Schema {
+        &self.schema.schema
+    }
+
+    /// Get the `Framing` of a `DatumCodec`.
+    pub fn framing(&self) -> Framing {
+        self.framing
+    }
+
+    /// Decode the datum at the beginning of `bytes`, advancing it past its frame.
+    fn decode_frame(&self, bytes: &mut &[u8]) -> AvroResult<Value> {
+        match self.framing {
+            Framing::LengthDelimited => {
+                let len = util::safe_len(usize::try_from(util::read_long(bytes)?)?)?;
+                if bytes.len() < len {
+                    return Err(Error::IO(ErrorKind::UnexpectedEof.into()));
+                }
+                let (mut frame, rest) = bytes.split_at(len);
+                *bytes = rest;
+                from_avro_datum(&self.schema.schema, &mut frame, None).map_err(|e| match e {
+                    // the frame is complete, so the datum is damaged
+                    Error::IO(ref ioe) if ioe.kind() == ErrorKind::UnexpectedEof => {
+                        Error::Decode("datum longer than its frame".to_string())
+                    }
+                    e => e,
+                })
+            }
+            Framing::SingleObject => SingleObjectReader::new(&self.schema).read_value(bytes),
+        }
+    }
+}
+
+impl<T: Into<Value>> Encoder<T> for DatumCodec {
+    type Error = Error;
+
+    fn encode(&mut self, item: T, dst: &mut BytesMut) -> AvroResult<()> {
+        let datum = to_avro_datum(&self.schema.schema, item)?;
+        let mut header = Vec::new();
+        match self.framing {
+            Framing::LengthDelimited => util::zig_i64(datum.len() as i64, &mut header),
+            Framing::SingleObject => {
+                header.extend_from_slice(&SINGLE_OBJECT_MARKER);
+                header.extend_from_slice(&self.schema.fingerprint.to_le_bytes());
+            }
+        }
+        dst.reserve(header.len() + datum.len());
+        dst.extend_from_slice(&header);
+        dst.extend_from_slice(&datum);
+        Ok(())
+    }
+}
+
+impl Decoder for DatumCodec {
+    type Item = Value;
+    type Error = Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> AvroResult<Option<Value>> {
+        if self.framing == Framing::LengthDelimited {
+            // wait for the whole frame, whose length is known from its prefix
+            let mut prefix = &src[..];
+            let len = match util::read_long(&mut prefix) {
+                Ok(len) => util::safe_len(usize::try_from(len)?)?,
+                Err(Error::IO(ref e)) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+                Err(e) => return Err(e),
+            };
+            let frame_len = src.len() - prefix.len() + len;
+            if src.len() < frame_len {
+                src.reserve(frame_len - src.len());
+                return Ok(None);
+            }
+        }
+
+        let mut bytes = &src[..];
+        match self.decode_frame(&mut bytes) {
+            Ok(value) => {
+                let len = src.len() - bytes.len();
+                src.advance(len);
+                Ok(Some(value))
+            }
+            // wait for the rest of the frame
+            Err(Error::IO(ref e)) if e.kind() == ErrorKind::UnexpectedEof => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{record, SCHEMA};
+    use crate::SingleObjectWriter;
+    use futures_util::{SinkExt, StreamExt};
+    use tokio_util::codec::{FramedRead, FramedWrite};
+
+    async fn round_trip(framing: Framing) {
+        let schema = Schema::parse_str(SCHEMA).unwrap();
+        let expected: Vec<Value> = (0..50).map(|a| record(&schema, a)).collect();
+
+        let (client, server) = tokio::io::duplex(4);
+        let mut sink = FramedWrite::new(client, DatumCodec::new(schema.clone(), framing));
+        let values = expected.clone();
+        let send = tokio::spawn(async move {
+            for value in values {
+                sink.send(value).await.unwrap();
+            }
+        });
+
+        let stream = FramedRead::new(server, DatumCodec::new(schema, framing));
+        let values: Vec<Value> = stream.map(Result::unwrap).collect().await;
+        send.await.unwrap();
+        assert_eq!(values, expected);
+    }
+
+    #[tokio::test]
+    async fn test_length_delimited() {
+        round_trip(Framing::LengthDelimited).await;
+    }
+
+    #[tokio::test]
+    async fn test_single_object() {
+        round_trip(Framing::SingleObject).await;
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        let schema = Schema::parse_str(SCHEMA).unwrap();
+        let mut codec = DatumCodec::new(schema.clone(), Framing::SingleObject);
+
+        let encoded = SingleObjectWriter::new(&schema)
+            .to_vec(record(&schema, 3))
+            .unwrap();
+        let mut src = BytesMut::from(&encoded[..5]);
+        assert!(codec.decode(&mut src).unwrap().is_none());
+        src.extend_from_slice(&encoded[5..]);
+        assert_eq!(codec.decode(&mut src).unwrap(), Some(record(&schema, 3)));
+        assert!(src.is_empty());
+
+        let other = Schema::parse_str(r#""long""#).unwrap();
+        let encoded = SingleObjectWriter::new(&other).to_vec(3i64).unwrap();
+        match codec.decode(&mut BytesMut::from(&encoded[..])) {
+            Err(Error::UnknownSchemaFingerprint(_)) => {}
+            other => panic!("expected an unknown fingerprint, got {:?}", other),
+        }
+
+        let mut codec = DatumCodec::new(schema.clone(), Framing::LengthDelimited);
+        let encoded = to_avro_datum(&schema, record(&schema, 3)).unwrap();
+        let mut src = BytesMut::from(&[encoded.len() as u8 * 2][..]);
+        assert!(codec.decode(&mut src).unwrap().is_none());
+        assert!(src.capacity() > encoded.len());
+        src.extend_from_slice(&encoded[..2]);
+        assert!(codec.decode(&mut src).unwrap().is_none());
+        src.extend_from_slice(&encoded[2..]);
+        assert_eq!(codec.decode(&mut src).unwrap(), Some(record(&schema, 3)));
+        assert!(src.is_empty());
+
+        // a frame of 1 byte holding the beginning of a record
+        let mut src = BytesMut::from(&[2u8, 6][..]);
+        assert!(codec.decode(&mut src).is_err());
+    }
+}
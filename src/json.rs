@@ -0,0 +1,506 @@
+//! Logic for the [JSON encoding] of Avro data, as produced and consumed by the `JsonEncoder` and
+//! `JsonDecoder` of the Java implementation.
+//!
+//! [JSON encoding]: https://avro.apache.org/docs/current/spec.html#json_encoding
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::str::FromStr;
+
+use serde_json::{Map, Number, Value as JsonValue};
+use uuid::Uuid;
+
+use crate::decimal::Decimal;
+use crate::duration::Duration;
+use crate::errors::{AvroResult, Error};
+use crate::schema::{Root, Schema};
+use crate::types::Value;
+
+/// Encode a `Value` into the Avro JSON encoding given its `Schema`, also performing schema
+/// validation.
+///
+/// Unions are encoded as `null` or as a single-entry object from the name of the matched branch to
+/// its value, and bytes and fixed as strings with one character from U+0000 to U+00FF per byte.
+pub fn to_avro_json(value: &Value, schema: &Schema) -> AvroResult<JsonValue> {
+    if !value.validate(schema) {
+        return Err(Error::Validation("value does not match schema".to_string()));
+    }
+    Ok(encode_internal(value, schema, &Root::new(schema)))
+}
+
+/// Decode a `Value` from the Avro JSON encoding given its `Schema`.
+pub fn from_avro_json(json: &JsonValue, schema: &Schema) -> AvroResult<Value> {
+    decode_internal(json, schema, &Root::new(schema))
+}
+
+fn encode_bytes(bytes: &[u8]) -> JsonValue {
+    JsonValue::String(bytes.iter().map(|&byte| char::from(byte)).collect())
+}
+
+fn encode_double(x: f64) -> JsonValue {
+    Number::from_f64(x)
+        .map(JsonValue::Number)
+        .unwrap_or_else(|| {
+            JsonValue::String(
+                if x.is_nan() {
+                    "NaN"
+                } else if x > 0.0 {
+                    "Infinity"
+                } else {
+                    "-Infinity"
+                }
+                .to_string(),
+            )
+        })
+}
+
+/// Return the name a branch of a union goes by in the JSON encoding, which is the fullname of
+/// named types and the name of the underlying type of logical types.
+fn branch_name(schema: &Schema) -> String {
+    match *schema {
+        Schema::Null => "null".to_string(),
+        Schema::Boolean => "boolean".to_string(),
+        Schema::Int | Schema::Date | Schema::TimeMillis => "int".to_string(),
+        Schema::Long | Schema::TimeMicros | Schema::TimestampMillis | Schema::TimestampMicros => {
+            "long".to_string()
+        }
+        Schema::Float => "float".to_string(),
+        Schema::Double => "double".to_string(),
+        Schema::Bytes => "bytes".to_string(),
+        Schema::String | Schema::Uuid => "string".to_string(),
+        Schema::Array(_) => "array".to_string(),
+        Schema::Map(_) => "map".to_string(),
+        Schema::Union(_) => "union".to_string(),
+        Schema::Record { ref name, .. }
+        | Schema::Enum { ref name, .. }
+        | Schema::Fixed { ref name, .. }
+        | Schema::Ref { ref name } => name.fullname(None),
+        Schema::Decimal { ref inner, .. } | Schema::Annotated { ref inner, .. } => {
+            branch_name(inner)
+        }
+        // This is the name a duration is serialized with.
+        Schema::Duration => "duration".to_string(),
+    }
+}
+
+/// Encode a `Value` into the Avro JSON encoding, following references to named types through
+/// `root`, the top-level `Schema` that `schema` is part of.
+///
+/// **NOTE** The value is assumed to be valid with regards to the schema.
+fn encode_internal(value: &Value, schema: &Schema, root: &Root) -> JsonValue {
+    if let Schema::Ref { ref name } = *schema {
+        let fullname = name.fullname(None);
+        let schema = root
+            .lookup_named(&fullname)
+            .unwrap_or_else(|| panic!("unknown reference to named type {}", fullname));
+        return encode_internal(value, schema, root);
+    }
+    if let Schema::Annotated { ref inner, .. } = *schema {
+        return encode_internal(value, inner, root);
+    }
+
+    match value {
+        Value::Null => JsonValue::Null,
+        Value::Boolean(b) => JsonValue::Bool(*b),
+        Value::Int(i) | Value::Date(i) | Value::TimeMillis(i) => JsonValue::from(*i),
+        Value::Long(i)
+        | Value::TimestampMillis(i)
+        | Value::TimestampMicros(i)
+        | Value::TimeMicros(i) => JsonValue::from(*i),
+        // Going through the shortest representation of the float keeps `0.1f32` from being
+        // encoded as `0.10000000149011612`.
+        Value::Float(x) => encode_double(x.to_string().parse().unwrap_or_else(|_| f64::from(*x))),
+        Value::Double(x) => encode_double(*x),
+        Value::Decimal(decimal) => match schema {
+            Schema::Decimal { inner, .. } => match **inner {
+                Schema::Fixed { size, .. } => {
+                    encode_bytes(&decimal.to_sign_extended_bytes_with_len(size).unwrap())
+                }
+                Schema::Bytes => {
+                    let bytes: Vec<u8> = decimal.try_into().unwrap();
+                    encode_bytes(&bytes)
+                }
+                _ => panic!("invalid inner type for decimal: {:?}", inner),
+            },
+            _ => panic!("invalid type for decimal: {:?}", schema),
+        },
+        &Value::Duration(duration) => {
+            let bytes: [u8; 12] = duration.into();
+            encode_bytes(&bytes)
+        }
+        Value::Uuid(uuid) => JsonValue::String(uuid.to_string()),
+        Value::Bytes(bytes) | Value::Fixed(_, bytes) => encode_bytes(bytes),
+        Value::String(s) | Value::Enum(_, s) => JsonValue::String(s.clone()),
+        Value::Union(item) => match *schema {
+            Schema::Union(ref inner) => {
+                let (_, inner_schema) = inner
+                    .find_schema_with_root(item, root)
+                    .expect("Invalid Union validation occurred");
+                match **item {
+                    Value::Null => JsonValue::Null,
+                    _ => {
+                        let mut object = Map::new();
+                        object.insert(
+                            branch_name(inner_schema),
+                            encode_internal(item, inner_schema, root),
+                        );
+                        JsonValue::Object(object)
+                    }
+                }
+            }
+            _ => panic!("invalid type for union: {:?}", schema),
+        },
+        Value::Array(items) => match *schema {
+            Schema::Array(ref inner) => JsonValue::Array(
+                items
+                    .iter()
+                    .map(|item| encode_internal(item, inner, root))
+                    .collect(),
+            ),
+            _ => panic!("invalid type for array: {:?}", schema),
+        },
+        Value::Map(items) => match *schema {
+            Schema::Map(ref inner) => JsonValue::Object(
+                items
+                    .iter()
+                    .map(|(key, value)| (key.clone(), encode_internal(value, inner, root)))
+                    .collect(),
+            ),
+            _ => panic!("invalid type for map: {:?}", schema),
+        },
+        Value::Record(fields) => match *schema {
+            Schema::Record {
+                fields: ref schema_fields,
+                ..
+            } => JsonValue::Object(
+                fields
+                    .iter()
+                    .zip(schema_fields)
+                    .map(|((name, value), field)| {
+                        (name.clone(), encode_internal(value, &field.schema, root))
+                    })
+                    .collect(),
+            ),
+            _ => panic!("invalid type for record: {:?}", schema),
+        },
+    }
+}
+
+pub(crate) fn decode_bytes(json: &JsonValue) -> AvroResult<Vec<u8>> {
+    match json {
+        JsonValue::String(s) => s
+            .chars()
+            .map(|c| {
+                u32::from(c)
+                    .try_into()
+                    .map_err(|_| Error::Decode(format!("character {:?} does not encode a byte", c)))
+            })
+            .collect(),
+        _ => Err(Error::Decode(format!("expected bytes, found {}", json))),
+    }
+}
+
+fn decode_fixed(json: &JsonValue, size: usize) -> AvroResult<Vec<u8>> {
+    let bytes = decode_bytes(json)?;
+    if bytes.len() != size {
+        return Err(Error::Decode(format!(
+            "expected {} bytes of fixed, found {}",
+            size,
+            bytes.len()
+        )));
+    }
+    Ok(bytes)
+}
+
+fn decode_int(json: &JsonValue) -> AvroResult<i32> {
+    json.as_i64()
+        .and_then(|i| i.try_into().ok())
+        .ok_or_else(|| Error::Decode(format!("expected int, found {}", json)))
+}
+
+fn decode_long(json: &JsonValue) -> AvroResult<i64> {
+    json.as_i64()
+        .ok_or_else(|| Error::Decode(format!("expected long, found {}", json)))
+}
+
+fn decode_double(json: &JsonValue) -> AvroResult<f64> {
+    match json {
+        JsonValue::Number(number) => number.as_f64(),
+        JsonValue::String(s) => match s.as_str() {
+            "NaN" => Some(f64::NAN),
+            "Infinity" => Some(f64::INFINITY),
+            "-Infinity" => Some(f64::NEG_INFINITY),
+            _ => None,
+        },
+        _ => None,
+    }
+    .ok_or_else(|| Error::Decode(format!("expected a floating point number, found {}", json)))
+}
+
+fn decode_string(json: &JsonValue) -> AvroResult<&str> {
+    json.as_str()
+        .ok_or_else(|| Error::Decode(format!("expected string, found {}", json)))
+}
+
+/// Decode a `Value` from the Avro JSON encoding, following references to named types through
+/// `root`, the top-level `Schema` that `schema` is part of.
+fn decode_internal(json: &JsonValue, schema: &Schema, root: &Root) -> AvroResult<Value> {
+    match *schema {
+        Schema::Null => match json {
+            JsonValue::Null => Ok(Value::Null),
+            _ => Err(Error::Decode(format!("expected null, found {}", json))),
+        },
+        Schema::Boolean => json
+            .as_bool()
+            .map(Value::Boolean)
+            .ok_or_else(|| Error::Decode(format!("expected boolean, found {}", json))),
+        Schema::Int => decode_int(json).map(Value::Int),
+        Schema::Date => decode_int(json).map(Value::Date),
+        Schema::TimeMillis => decode_int(json).map(Value::TimeMillis),
+        Schema::Long => decode_long(json).map(Value::Long),
+        Schema::TimeMicros => decode_long(json).map(Value::TimeMicros),
+        Schema::TimestampMillis => decode_long(json).map(Value::TimestampMillis),
+        Schema::TimestampMicros => decode_long(json).map(Value::TimestampMicros),
+        Schema::Float => decode_double(json).map(|x| Value::Float(x as f32)),
+        Schema::Double => decode_double(json).map(Value::Double),
+        Schema::Bytes => decode_bytes(json).map(Value::Bytes),
+        Schema::String => decode_string(json).map(|s| Value::String(s.to_string())),
+        Schema::Uuid => Ok(Value::Uuid(Uuid::from_str(decode_string(json)?)?)),
+        Schema::Fixed { size, .. } => {
+            decode_fixed(json, size).map(|bytes| Value::Fixed(size, bytes))
+        }
+        Schema::Duration => {
+            let mut bytes = [0u8; 12];
+            bytes.copy_from_slice(&decode_fixed(json, 12)?);
+            Ok(Value::Duration(Duration::from(bytes)))
+        }
+        Schema::Decimal { ref inner, .. } => {
+            let bytes = match **inner {
+                Schema::Fixed { size, .. } => decode_fixed(json, size)?,
+                _ => decode_bytes(json)?,
+            };
+            Ok(Value::Decimal(Decimal::from(bytes)))
+        }
+        Schema::Enum { ref symbols, .. } => {
+            let symbol = decode_string(json)?;
+            symbols
+                .iter()
+                .position(|item| item == symbol)
+                .map(|index| Value::Enum(index as i32, symbol.to_string()))
+                .ok_or_else(|| {
+                    Error::Decode(format!(
+                        "enum symbol {} is not among allowed symbols {:?}",
+                        symbol, symbols
+                    ))
+                })
+        }
+        Schema::Array(ref inner) => match json {
+            JsonValue::Array(items) => items
+                .iter()
+                .map(|item| decode_internal(item, inner, root))
+                .collect::<AvroResult<_>>()
+                .map(Value::Array),
+            _ => Err(Error::Decode(format!("expected array, found {}", json))),
+        },
+        Schema::Map(ref inner) => match json {
+            JsonValue::Object(items) => items
+                .iter()
+                .map(|(key, value)| Ok((key.clone(), decode_internal(value, inner, root)?)))
+                .collect::<AvroResult<HashMap<_, _>>>()
+                .map(Value::Map),
+            _ => Err(Error::Decode(format!("expected map, found {}", json))),
+        },
+        Schema::Union(ref inner) => {
+            let variants = inner.variants();
+            let (variant, value) = match json {
+                JsonValue::Null => (variants.iter().find(|v| **v == Schema::Null), json),
+                JsonValue::Object(object) if object.len() == 1 => {
+                    let (name, value) = object.iter().next().unwrap();
+                    (variants.iter().find(|v| branch_name(v) == *name), value)
+                }
+                _ => {
+                    return Err(Error::Decode(format!(
+                        "expected null or a single-entry object for union, found {}",
+                        json
+                    )))
+                }
+            };
+            let variant = variant
+                .ok_or_else(|| Error::Decode(format!("no union branch matches {}", json)))?;
+            Ok(Value::Union(Box::new(decode_internal(
+                value, variant, root,
+            )?)))
+        }
+        Schema::Record { ref fields, .. } => match json {
+            JsonValue::Object(object) => fields
+                .iter()
+                .map(|field| {
+                    let value = object.get(&field.name).ok_or_else(|| {
+                        Error::Decode(format!("missing field {} in record", field.name))
+                    })?;
+                    Ok((
+                        field.name.clone(),
+                        decode_internal(value, &field.schema, root)?,
+                    ))
+                })
+                .collect::<AvroResult<_>>()
+                .map(Value::Record),
+            _ => Err(Error::Decode(format!("expected record, found {}", json))),
+        },
+        Schema::Ref { ref name } => {
+            let fullname = name.fullname(None);
+            let schema = root.lookup_named(&fullname).ok_or_else(|| {
+                Error::Decode(format!("unknown reference to named type {}", fullname))
+            })?;
+            decode_internal(json, schema, root)
+        }
+        Schema::Annotated { ref inner, .. } => decode_internal(json, inner, root),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_primitives() {
+        let cases = vec![
+            (Value::Null, Schema::Null, json!(null)),
+            (Value::Boolean(true), Schema::Boolean, json!(true)),
+            (Value::Int(-3), Schema::Int, json!(-3)),
+            (Value::Long(1 << 40), Schema::Long, json!(1u64 << 40)),
+            (Value::Float(0.1), Schema::Float, json!(0.1)),
+            (Value::Double(2.5), Schema::Double, json!(2.5)),
+            (Value::Double(f64::NAN), Schema::Double, json!("NaN")),
+            (
+                Value::Double(f64::NEG_INFINITY),
+                Schema::Double,
+                json!("-Infinity"),
+            ),
+            (
+                Value::Bytes(vec![0, 97, 0xff]),
+                Schema::Bytes,
+                json!("\u{0}a\u{ff}"),
+            ),
+            (
+                Value::String("été".to_string()),
+                Schema::String,
+                json!("été"),
+            ),
+        ];
+        for (value, schema, json) in cases {
+            assert_eq!(to_avro_json(&value, &schema).unwrap(), json);
+            let decoded = from_avro_json(&json, &schema).unwrap();
+            match (decoded, value) {
+                (Value::Double(x), Value::Double(y)) if y.is_nan() => assert!(x.is_nan()),
+                (decoded, value) => assert_eq!(decoded, value),
+            }
+        }
+    }
+
+    #[test]
+    fn test_complex() {
+        let schema = Schema::parse_str(
+            r#"
+            {
+                "type": "record",
+                "name": "Node",
+                "namespace": "test",
+                "fields": [
+                    {"name": "label", "type": ["null", "string", "Node"]},
+                    {
+                        "name": "kind",
+                        "type": {"type": "enum", "name": "Kind", "symbols": ["A", "B"]}
+                    },
+                    {"name": "hash", "type": {"type": "fixed", "name": "Hash", "size": 2}},
+                    {
+                        "name": "tags",
+                        "type": {"type": "map", "values": {"type": "array", "items": "int"}}
+                    },
+                    {
+                        "name": "at",
+                        "type": ["null", {"type": "long", "logicalType": "timestamp-millis"}]
+                    }
+                ]
+            }
+            "#,
+        )
+        .unwrap();
+        let value = Value::Record(vec![
+            (
+                "label".to_string(),
+                Value::Union(Box::new(Value::String("root".to_string()))),
+            ),
+            ("kind".to_string(), Value::Enum(1, "B".to_string())),
+            ("hash".to_string(), Value::Fixed(2, vec![1, 2])),
+            (
+                "tags".to_string(),
+                Value::Map(
+                    vec![("x".to_string(), Value::Array(vec![Value::Int(1)]))]
+                        .into_iter()
+                        .collect(),
+                ),
+            ),
+            (
+                "at".to_string(),
+                Value::Union(Box::new(Value::TimestampMillis(7))),
+            ),
+        ]);
+        let json = json!({
+            "label": {"string": "root"},
+            "kind": "B",
+            "hash": "\u{1}\u{2}",
+            "tags": {"x": [1]},
+            "at": {"long": 7}
+        });
+        assert_eq!(to_avro_json(&value, &schema).unwrap(), json);
+        assert_eq!(from_avro_json(&json, &schema).unwrap(), value);
+
+        let nested = json!({
+            "label": {"test.Node": {
+                "label": null,
+                "kind": "A",
+                "hash": "ab",
+                "tags": {},
+                "at": null
+            }},
+            "kind": "A",
+            "hash": "ab",
+            "tags": {},
+            "at": null
+        });
+        let decoded = from_avro_json(&nested, &schema).unwrap();
+        assert_eq!(to_avro_json(&decoded, &schema).unwrap(), nested);
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        let union = Schema::parse_str(r#"["null", "int"]"#).unwrap();
+        let cases = vec![
+            (json!(1), union.clone()),
+            (json!({"long": 1}), union.clone()),
+            (json!({"int": 1, "null": null}), union),
+            (json!(1i64 << 40), Schema::Int),
+            (json!("\u{100}"), Schema::Bytes),
+            (
+                json!("ab"),
+                Schema::parse_str(r#"{"type": "fixed", "name": "F", "size": 3}"#).unwrap(),
+            ),
+            (
+                json!("C"),
+                Schema::parse_str(r#"{"type": "enum", "name": "E", "symbols": ["A"]}"#).unwrap(),
+            ),
+            (
+                json!({}),
+                Schema::parse_str(
+                    r#"{"type": "record", "name": "R", "fields": [{"name": "a", "type": "int"}]}"#,
+                )
+                .unwrap(),
+            ),
+        ];
+        for (json, schema) in cases {
+            assert!(from_avro_json(&json, &schema).is_err(), "{} decoded", json);
+        }
+        assert!(to_avro_json(&Value::Long(1), &Schema::Int).is_err());
+    }
+}
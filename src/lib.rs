@@ -41,12 +41,12 @@
 //! avro-rs = "x.y"
 //! ```
 //!
-//! Or in case you want to leverage the **Snappy** codec:
+//! Or in case you want to leverage the **Snappy**, **Zstandard**, **Bzip2** or **Xz** codecs:
 //!
 //! ```toml
 //! [dependencies.avro-rs]
 //! version = "x.y"
-//! features = ["snappy"]
+//! features = ["snappy", "zstandard", "bzip", "xz"]
 //! ```
 //!
 //! # Upgrading to a newer minor version
@@ -83,6 +83,56 @@
 //! println!("{:?}", schema);
 //! ```
 //!
+//! Schemas spread over several documents that refer to each other's named types can be parsed
+//! together, in any order:
+//!
+//! ```
+//! use avro_rs::Schema;
+//!
+//! let raw_customer = r#"
+//!     {
+//!         "type": "record",
+//!         "name": "Customer",
+//!         "fields": [{"name": "address", "type": "Address"}]
+//!     }
+//! "#;
+//!
+//! let raw_address = r#"
+//!     {
+//!         "type": "record",
+//!         "name": "Address",
+//!         "fields": [{"name": "city", "type": "string"}]
+//!     }
+//! "#;
+//!
+//! // missing or duplicate named types are reported as errors
+//! let schemas = Schema::parse_list(&[raw_customer, raw_address]).unwrap();
+//! ```
+//!
+//! Schemas can also be derived from the definition of Rust structs and enums with the `derive`
+//! feature, which honors the serde attributes `rename`, `rename_all`, `default` and `skip`, maps
+//! `Option<T>` to a union with `null` and turns doc comments into `doc`:
+//!
+//! ```toml
+//! [dependencies.avro-rs]
+//! version = "x.y"
+//! features = ["derive"]
+//! ```
+//!
+//! ```ignore
+//! use avro_rs::{AvroSchema, Schema};
+//!
+//! /// A test record.
+//! #[derive(AvroSchema)]
+//! #[avro(namespace = "com.example")]
+//! struct Test {
+//!     a: i64,
+//!     b: Option<String>,
+//! }
+//!
+//! let schema: Schema = Test::get_schema();
+//! ```
+//!
 //! The library provides also a programmatic interface to define schemas without encoding them in
 //! JSON (for advanced use), but we highly recommend the JSON interface. Please read the API
 //! reference in case you are interested.
@@ -206,7 +256,7 @@
 //!
 //! ## Using codecs to compress data
 //!
-//! Avro supports three different compression codecs when encoding data:
+//! Avro supports several compression codecs when encoding data:
 //!
 //! * **Null**: leaves data uncompressed;
 //! * **Deflate**: writes the data block using the deflate algorithm as specified in RFC 1951, and
@@ -215,6 +265,12 @@
 //! * **Snappy**: uses Google's [Snappy](http://google.github.io/snappy/) compression library. Each
 //! compressed block is followed by the 4-byte, big-endianCRC32 checksum of the uncompressed data in
 //! the block. You must enable the `snappy` feature to use this codec.
+//! * **Zstandard**: uses Facebook's [Zstandard](https://facebook.github.io/zstd/) compression
+//!   library. You must enable the `zstandard` feature to use this codec.
+//! * **Bzip2**: uses the [bzip2](https://sourceware.org/bzip2/) compression library. You must enable
+//!   the `bzip` feature to use this codec.
+//! * **Xz**: uses the [xz](https://tukaani.org/xz/) compression library. You must enable the `xz`
+//!   feature to use this codec.
 //!
 //! To specify a codec to use to compress data, just specify it while creating a `Writer`:
 //! ```
@@ -236,6 +292,15 @@
 //! let mut writer = Writer::with_codec(&schema, Vec::new(), Codec::Deflate);
 //! ```
 //!
+//! The compression level of the `Deflate` and `Zstandard` codecs, the window of the latter and whether
+//! `Snappy` checks the checksum of the blocks it reads can be set with `CodecOptions`, either through
+//! the `codec_options` of `Writer::builder()` or with `Reader::set_codec_options`.
+//!
+//! Other codecs can be plugged in by implementing `CustomCodec` and registering them under the name
+//! they go by in the header of data files, with `register_codec`. They can then be used to write data
+//! with `Codec::Custom(name)` and data files compressed with them can be read, while reading a data
+//! file compressed with an unknown codec fails.
+//!
 //! # Reading data
 //!
 //! As far as reading Avro encoded data goes, we can just use the schema encoded with the data to
@@ -408,6 +473,85 @@
 //! }
 //! ```
 //!
+//! # Reading with several threads
+//!
+//! `ParallelReader` reads the data blocks of a file in sequence, but decompresses and decodes
+//! them on several threads, yielding the values in their original order. Its `ParallelOptions` set
+//! the number of threads, the number of blocks each of them decodes at once and how many batches of
+//! blocks are read ahead:
+//!
+//! ```rust
+//! use avro_rs::{ParallelOptions, ParallelReader};
+//! # use avro_rs::Schema;
+//! # use avro_rs::types::Record;
+//! # use avro_rs::Writer;
+//! #
+//! # let raw_schema = r#"
+//! #     {
+//! #         "type": "record",
+//! #         "name": "test",
+//! #         "fields": [
+//! #             {"name": "a", "type": "long", "default": 42},
+//! #             {"name": "b", "type": "string"}
+//! #         ]
+//! #     }
+//! # "#;
+//! # let reader_schema = Schema::parse_str(raw_schema).unwrap();
+//! # let mut writer = Writer::new(&reader_schema, Vec::new());
+//! # let mut record = Record::new(writer.schema()).unwrap();
+//! # record.put("a", 27i64);
+//! # record.put("b", "foo");
+//! # writer.append(record).unwrap();
+//! # let input = writer.into_inner().unwrap();
+//!
+//! let options = ParallelOptions::builder().threads(4).batch_size(8).build();
+//! let reader = ParallelReader::with_schema(&reader_schema, &input[..], options).unwrap();
+//!
+//! for value in reader {
+//!     println!("{:?}", value.unwrap());
+//! }
+//! ```
+//!
+//! # Reading and writing asynchronously
+//!
+//! With the `async` feature, `AsyncReader` and `AsyncWriter` read and write data files over the
+//! `AsyncRead` and `AsyncWrite` traits of [tokio](https://tokio.rs), sharing the logic of `Reader` and
+//! `Writer`. `AsyncReader` is a `Stream` of values:
+//!
+//! ```toml
+//! [dependencies.avro-rs]
+//! version = "x.y"
+//! features = ["async"]
+//! ```
+//!
+//! ```ignore
+//! use avro_rs::{AsyncReader, AsyncWriter};
+//! use tokio_stream::StreamExt;
+//!
+//! let mut writer = AsyncWriter::new(&schema, output);
+//! writer.append(record).await.unwrap();
+//! writer.close().await.unwrap();
+//!
+//! let mut reader = AsyncReader::new(input).await.unwrap();
+//! while let Some(value) = reader.next().await {
+//!     println!("{:?}", value.unwrap());
+//! }
+//! ```
+//!
+//! `DatumCodec` frames single datums of a `Schema`, preceded by their length or in the
+//! single-object encoding, with the `Encoder` and `Decoder` traits of
+//! [tokio-util](https://docs.rs/tokio-util), so that a `Framed` stream reads and writes values:
+//!
+//! ```ignore
+//! use avro_rs::{DatumCodec, Framing};
+//! use futures::{SinkExt, StreamExt};
+//! use tokio_util::codec::Framed;
+//!
+//! let mut framed = Framed::new(socket, DatumCodec::new(schema, Framing::LengthDelimited));
+//! framed.send(record).await.unwrap();
+//! let value = framed.next().await.unwrap().unwrap();
+//! ```
+//!
 //! # Putting everything together
 //!
 //! The following is an example of how to combine everything showed so far and it is meant to be a
@@ -646,9 +790,6 @@
 //!
 //! This library supports checking for schemas compatibility.
 //!
-//! Note: It does not yet support named schemas (more on
-//! https://github.com/flavray/avro-rs/pull/76).
-//!
 //! Examples of checking for compatibility:
 //!
 //! 1. Compatible schemas
@@ -676,33 +817,73 @@
 //! let readers_schema = Schema::parse_str(r#"{"type": "array", "items":"int"}"#).unwrap();
 //! assert_eq!(false, SchemaCompatibility::can_read(&writers_schema, &readers_schema));
 //! ```
+//!
+//! 3. Reporting incompatibilities
+//!
+//! Explanation: `check_can_read` tells which parts of the schemas are incompatible, and why
+//!
+//! ```rust
+//! use avro_rs::{Schema, schema_compatibility::{SchemaCompatibility, SchemaIncompatibilityType}};
+//!
+//! let writers_schema = Schema::parse_str(r#"{"type": "array", "items":"long"}"#).unwrap();
+//! let readers_schema = Schema::parse_str(r#"{"type": "array", "items":"int"}"#).unwrap();
+//! let result = SchemaCompatibility::check_can_read(&writers_schema, &readers_schema);
+//! let incompatibility = &result.incompatibilities()[0];
+//! assert_eq!(incompatibility.kind, SchemaIncompatibilityType::TypeMismatch);
+//! assert_eq!(incompatibility.location, "/items");
+//! ```
 
+#[cfg(feature = "async")]
+mod async_io;
 mod codec;
 mod de;
+mod de_schema;
 mod decimal;
 mod decode;
 mod duration;
 mod encode;
 mod errors;
+#[cfg(feature = "async")]
+mod framing;
+mod json;
+mod parallel;
+mod rabin;
 mod reader;
 mod ser;
+mod ser_schema;
+#[cfg(test)]
+mod test_util;
 mod util;
 mod writer;
 
+pub mod confluent;
 pub mod schema;
 pub mod schema_compatibility;
 pub mod types;
 
-pub use crate::codec::Codec;
+pub use crate::codec::{register_codec, Codec, CodecOptions, CustomCodec};
 pub use crate::de::from_value;
 pub use crate::decimal::Decimal;
 pub use crate::duration::{Days, Duration, Millis, Months};
 pub use crate::errors::Error;
-pub use crate::reader::{from_avro_datum, Reader};
-pub use crate::schema::Schema;
+#[cfg(feature = "async")]
+pub use crate::framing::{DatumCodec, Framing};
+pub use crate::json::{from_avro_json, to_avro_json};
+pub use crate::parallel::{ParallelOptions, ParallelReader};
+pub use crate::rabin::Rabin;
+pub use crate::reader::{
+    from_avro_datum, from_avro_datum_de, BlockReader, DeserializeIter, RawBlock, Reader,
+    RecoveryStats, SchemaStore, SingleObjectReader, SkippedRange,
+};
+pub use crate::schema::{AvroSchema, AvroSchemaComponent, Schema};
 pub use crate::ser::to_value;
 pub use crate::util::max_allocation_bytes;
-pub use crate::writer::{to_avro_datum, Writer};
+pub use crate::writer::{to_avro_datum, to_avro_datum_ser, SingleObjectWriter, Writer};
+
+#[cfg(feature = "async")]
+pub use crate::async_io::{AsyncReader, AsyncWriter};
+#[cfg(feature = "derive")]
+pub use avro_rs_derive::AvroSchema;
 
 #[cfg(test)]
 mod tests {
@@ -0,0 +1,454 @@
+//! Logic handling reading from Avro format with several threads.
+use std::collections::{BTreeMap, HashMap};
+use std::io::Read;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+
+use crate::errors::{AvroResult, Error};
+use crate::reader::{from_avro_datum, BlockReader, RawBlock};
+use crate::schema::Schema;
+use crate::types::Value;
+use crate::{Codec, CodecOptions};
+
+/// The limits of a `ParallelReader`, to trade memory for throughput.
+///
+/// ```
+/// use avro_rs::ParallelOptions;
+///
+/// let options = ParallelOptions::builder().threads(4).batch_size(8).build();
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, typed_builder::TypedBuilder)]
+pub struct ParallelOptions {
+    /// The number of threads decompressing and decoding data blocks, by default the available
+    /// parallelism of the machine.
+    #[builder(default = thread::available_parallelism().map_or(1, |n| n.get()))]
+    pub threads: usize,
+    /// The number of data blocks a thread decompresses and decodes at once.
+    #[builder(default = 1)]
+    pub batch_size: usize,
+    /// The maximum number of batches of data blocks read ahead of the values yielded, decoded or
+    /// not, which bounds the memory used.
+    #[builder(default = 2 * threads)]
+    pub max_batches: usize,
+}
+
+impl Default for ParallelOptions {
+    fn default() -> Self {
+        Self::builder().build()
+    }
+}
+
+/// A batch of data blocks to decode, with its index in the data file.
+type Batch = (usize, Vec<RawBlock>);
+/// The values decoded from a batch, with its index in the data file.
+type Decoded = (usize, AvroResult<Vec<Value>>);
+
+/// What the decoding threads share.
+struct Decoding {
+    writer_schema: Schema,
+    reader_schema: Option<Schema>,
+    codec: Codec,
+    codec_options: CodecOptions,
+    cancelled: AtomicBool,
+}
+
+impl Decoding {
+    /// Decompress and decode the values of a batch of data blocks.
+    fn decode(&self, blocks: Vec<RawBlock>) -> AvroResult<Vec<Value>> {
+        let mut values = Vec::with_capacity(blocks.iter().map(|b| b.record_count).sum());
+        for mut block in blocks {
+            if block.record_count > 0 {
+                self.codec
+                    .decompress_with(&mut block.data, &self.codec_options)?;
+            }
+            let mut bytes = &block.data[..];
+            for _ in 0..block.record_count {
+                values.push(from_avro_datum(
+                    &self.writer_schema,
+                    &mut bytes,
+                    self.reader_schema.as_ref(),
+                )?);
+            }
+        }
+        Ok(values)
+    }
+
+    /// Decode a batch of data blocks like `decode`, turning a panic into an error, so that the
+    /// thread keeps on decoding and the values of the batch are still waited for.
+    fn decode_or_recover(&self, blocks: Vec<RawBlock>) -> AvroResult<Vec<Value>> {
+        panic::catch_unwind(AssertUnwindSafe(|| self.decode(blocks))).unwrap_or_else(|payload| {
+            let message = payload
+                .downcast_ref::<&str>()
+                .copied()
+                .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+                .unwrap_or("unknown cause");
+            Err(Error::Decode(format!(
+                "a decoding thread panicked: {}",
+                message
+            )))
+        })
+    }
+}
+
+/// The error yielded once the decoding threads are gone.
+fn stopped() -> Error {
+    Error::Decode("the decoding threads stopped".to_string())
+}
+
+/// The threads decoding batches of data blocks.
+struct Workers {
+    batches: Option<Sender<Batch>>,
+    decoded: Receiver<Decoded>,
+    handles: Vec<JoinHandle<()>>,
+    decoding: Arc<Decoding>,
+}
+
+impl Workers {
+    fn spawn(decoding: Decoding, threads: usize) -> Workers {
+        let decoding = Arc::new(decoding);
+        let (batches, batches_rx) = channel::<Batch>();
+        let batches_rx = Arc::new(Mutex::new(batches_rx));
+        let (decoded_tx, decoded) = channel();
+
+        let handles = (0..threads.max(1))
+            .map(|_| {
+                let decoding = decoding.clone();
+                let batches = batches_rx.clone();
+                let decoded = decoded_tx.clone();
+                thread::spawn(move || loop {
+                    let batch = batches.lock().unwrap().recv();
+                    let (index, blocks) = match batch {
+                        Ok(batch) => batch,
+                        Err(_) => return,
+                    };
+                    if decoding.cancelled.load(Ordering::Relaxed) {
+                        return;
+                    }
+                    if decoded
+                        .send((index, decoding.decode_or_recover(blocks)))
+                        .is_err()
+                    {
+                        return;
+                    }
+                })
+            })
+            .collect();
+
+        Workers {
+            batches: Some(batches),
+            decoded,
+            handles,
+            decoding,
+        }
+    }
+}
+
+impl Drop for Workers {
+    fn drop(&mut self) {
+        self.decoding.cancelled.store(true, Ordering::Relaxed);
+        self.batches.take();
+        for handle in self.handles.drain(..) {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// Interface for reading Avro formatted values with several threads, for the data files whose
+/// decompression and decoding is too much work for one.
+///
+/// The data blocks are read in sequence and sent in batches to threads which decompress and
+/// decode them, possibly performing schema resolution, while the values are yielded in their
+/// original order. To be used as an iterator:
+///
+/// ```no_run
+/// # use avro_rs::{ParallelOptions, ParallelReader};
+/// # use std::io::Cursor;
+/// # let input = Cursor::new(Vec::<u8>::new());
+/// let options = ParallelOptions::builder().threads(4).batch_size(8).build();
+/// for value in ParallelReader::new(input, options).unwrap() {
+///     match value {
+///         Ok(v) => println!("{:?}", v),
+///         Err(e) => println!("Error: {}", e),
+///     };
+/// }
+/// ```
+///
+/// **NOTE** The threads are started by the first call to `next`.
+pub struct ParallelReader<R> {
+    blocks: BlockReader<R>,
+    reader_schema: Option<Schema>,
+    options: ParallelOptions,
+    codec_options: CodecOptions,
+    workers: Option<Workers>,
+    // Index of the next batch to read.
+    next_batch: usize,
+    // Index of the next batch to yield the values of.
+    next_yielded: usize,
+    // Whether all the data blocks have been read, or failed to be.
+    blocks_done: bool,
+    // Batches decoded ahead of the one to yield next, or which failed to be read.
+    decoded: BTreeMap<usize, AvroResult<Vec<Value>>>,
+    values: std::vec::IntoIter<Value>,
+    errored: bool,
+}
+
+impl<R: Read> ParallelReader<R> {
+    /// Creates a `ParallelReader` given something implementing the `io::Read` trait to read from
+    /// and its `ParallelOptions`.
+    /// No reader `Schema` will be set.
+    ///
+    /// **NOTE** The avro header is going to be read automatically upon creation of the
+    /// `ParallelReader`.
+    pub fn new(reader: R, options: ParallelOptions) -> AvroResult<ParallelReader<R>> {
+        Ok(ParallelReader {
+            blocks: BlockReader::new(reader)?,
+            reader_schema: None,
+            options,
+            codec_options: CodecOptions::default(),
+            workers: None,
+            next_batch: 0,
+            next_yielded: 0,
+            blocks_done: false,
+            decoded: BTreeMap::new(),
+            values: Vec::new().into_iter(),
+            errored: false,
+        })
+    }
+
+    /// Creates a `ParallelReader` given a reader `Schema`, something implementing the `io::Read`
+    /// trait to read from and its `ParallelOptions`.
+    ///
+    /// **NOTE** The avro header is going to be read automatically upon creation of the
+    /// `ParallelReader`.
+    pub fn with_schema(
+        schema: &Schema,
+        reader: R,
+        options: ParallelOptions,
+    ) -> AvroResult<ParallelReader<R>> {
+        let mut reader = Self::new(reader, options)?;
+        // Check if the reader and writer schemas disagree.
+        if reader.writer_schema() != schema {
+            reader.reader_schema = Some(schema.clone());
+        }
+        Ok(reader)
+    }
+
+    /// Get a reference to the writer `Schema`.
+    pub fn writer_schema(&self) -> &Schema {
+        self.blocks.writer_schema()
+    }
+
+    /// Get the `Codec` the data blocks are compressed with.
+    pub fn codec(&self) -> Codec {
+        self.blocks.codec()
+    }
+
+    /// Set the `CodecOptions` to decompress the data blocks with.
+    ///
+    /// **NOTE** This has no effect once the threads are started.
+    pub fn set_codec_options(&mut self, options: CodecOptions) {
+        self.codec_options = options;
+    }
+
+    /// Get the user metadata of the header, which excludes the `avro.` keys reserved for the
+    /// specification.
+    pub fn user_metadata(&self) -> &HashMap<String, Vec<u8>> {
+        self.blocks.user_metadata()
+    }
+
+    /// Read batches of data blocks and send them to the threads, up to the maximum number of
+    /// batches read ahead.
+    fn read_ahead(&mut self) {
+        if self.workers.is_none() {
+            let decoding = Decoding {
+                writer_schema: self.writer_schema().clone(),
+                reader_schema: self.reader_schema.take(),
+                codec: self.codec(),
+                codec_options: self.codec_options,
+                cancelled: AtomicBool::new(false),
+            };
+            self.workers = Some(Workers::spawn(decoding, self.options.threads));
+        }
+        let workers = self.workers.as_ref().unwrap();
+
+        let batch_size = self.options.batch_size.max(1);
+        let max_batches = self.options.max_batches.max(1);
+        while !self.blocks_done && self.next_batch - self.next_yielded < max_batches {
+            let mut batch = Vec::with_capacity(batch_size);
+            let mut error = None;
+            for block in self.blocks.by_ref().take(batch_size) {
+                match block {
+                    Ok(block) => batch.push(block),
+                    Err(e) => {
+                        error = Some(e);
+                        break;
+                    }
+                }
+            }
+            self.blocks_done = batch.len() < batch_size;
+
+            if !batch.is_empty() {
+                let batches = workers.batches.as_ref().unwrap();
+                if batches.send((self.next_batch, batch)).is_err() {
+                    self.decoded.insert(self.next_batch, Err(stopped()));
+                    self.blocks_done = true;
+                }
+                self.next_batch += 1;
+            }
+            // the error is yielded after the values of the previous batches
+            if let Some(e) = error {
+                self.decoded.insert(self.next_batch, Err(e));
+                self.next_batch += 1;
+            }
+        }
+    }
+
+    /// Wait for the values of the next batch.
+    fn next_batch(&mut self) -> Option<AvroResult<Vec<Value>>> {
+        self.read_ahead();
+        if self.next_yielded == self.next_batch {
+            return None;
+        }
+        let workers = self.workers.as_ref().unwrap();
+        while !self.decoded.contains_key(&self.next_yielded) {
+            match workers.decoded.recv() {
+                Ok((index, values)) => self.decoded.insert(index, values),
+                Err(_) => self.decoded.insert(self.next_yielded, Err(stopped())),
+            };
+        }
+        let values = self.decoded.remove(&self.next_yielded);
+        self.next_yielded += 1;
+        values
+    }
+}
+
+impl<R: Read> Iterator for ParallelReader<R> {
+    type Item = AvroResult<Value>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // to prevent keep on reading after the first error occurs
+        if self.errored {
+            return None;
+        }
+        loop {
+            if let Some(value) = self.values.next() {
+                return Some(Ok(value));
+            }
+            match self.next_batch()? {
+                Ok(values) => self.values = values.into_iter(),
+                Err(e) => {
+                    self.errored = true;
+                    self.workers.take();
+                    return Some(Err(e));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{write_blocks, SCHEMA};
+    use crate::{register_codec, CustomCodec, Reader};
+
+    #[test]
+    fn test_parallel_reader() {
+        let schema = Schema::parse_str(SCHEMA).unwrap();
+        let input = write_blocks(&schema, Codec::Deflate, 500);
+        let expected: Vec<Value> = Reader::new(&input[..])
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(expected.len(), 500);
+
+        for &(threads, batch_size, max_batches) in &[(1, 1, 1), (4, 1, 8), (3, 5, 2), (8, 100, 1)] {
+            let options = ParallelOptions::builder()
+                .threads(threads)
+                .batch_size(batch_size)
+                .max_batches(max_batches)
+                .build();
+            let reader = ParallelReader::new(&input[..], options).unwrap();
+            assert_eq!(reader.codec(), Codec::Deflate);
+            let values: Vec<Value> = reader.map(Result::unwrap).collect();
+            assert_eq!(values, expected);
+        }
+    }
+
+    #[test]
+    fn test_parallel_reader_with_schema() {
+        let schema = Schema::parse_str(SCHEMA).unwrap();
+        let reader_schema = Schema::parse_str(
+            r#"
+            {
+              "type": "record",
+              "name": "test",
+              "fields": [
+                {"name": "b", "type": "string"},
+                {"name": "c", "type": "int", "default": 1}
+              ]
+            }
+            "#,
+        )
+        .unwrap();
+        let input = write_blocks(&schema, Codec::Null, 100);
+        let expected: Vec<Value> = Reader::with_schema(&reader_schema, &input[..])
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+
+        let reader =
+            ParallelReader::with_schema(&reader_schema, &input[..], ParallelOptions::default())
+                .unwrap();
+        let values: Vec<Value> = reader.map(Result::unwrap).collect();
+        assert_eq!(values, expected);
+    }
+
+    #[test]
+    fn test_parallel_reader_error() {
+        let schema = Schema::parse_str(SCHEMA).unwrap();
+        let mut input = write_blocks(&schema, Codec::Null, 100);
+        // damage the sync marker ending the last data block
+        let len = input.len();
+        input[len - 1] ^= 0xff;
+        let expected = Reader::new(&input[..])
+            .unwrap()
+            .take_while(Result::is_ok)
+            .count();
+
+        let options = ParallelOptions::builder().threads(2).batch_size(2).build();
+        let mut reader = ParallelReader::new(&input[..], options).unwrap();
+        assert_eq!(reader.by_ref().take_while(Result::is_ok).count(), expected);
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn test_parallel_reader_panic() {
+        struct Panicking;
+
+        impl CustomCodec for Panicking {
+            fn compress(&self, _: &mut Vec<u8>) -> AvroResult<()> {
+                Ok(())
+            }
+
+            fn decompress(&self, _: &mut Vec<u8>) -> AvroResult<()> {
+                panic!("cannot decompress")
+            }
+        }
+
+        register_codec("panicking", Panicking).unwrap();
+        let schema = Schema::parse_str(SCHEMA).unwrap();
+        let input = write_blocks(&schema, Codec::Custom("panicking"), 100);
+
+        let options = ParallelOptions::builder().threads(2).build();
+        let mut reader = ParallelReader::new(&input[..], options).unwrap();
+        match reader.next() {
+            Some(Err(Error::Decode(message))) => assert!(message.contains("cannot decompress")),
+            other => panic!("expected a decoding error, got {:?}", other),
+        }
+        assert!(reader.next().is_none());
+    }
+}
@@ -0,0 +1,112 @@
+//! Implementation of the 64-bit Rabin fingerprint (CRC-64-AVRO) used by Avro.
+use crate::schema::Schema;
+// `GenericArray` is deprecated by its latest releases, but it is what `digest` 0.9 is built on.
+#[allow(deprecated)]
+use digest::{consts::U8, generic_array::GenericArray, FixedOutput, Reset, Update};
+
+/// The fingerprint of an empty input, which is also the irreducible polynomial of the fingerprint.
+const EMPTY: u64 = 0xc15d_213a_a4d7_a795;
+
+const FINGERPRINT_TABLE: [u64; 256] = fingerprint_table();
+
+const fn fingerprint_table() -> [u64; 256] {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut fingerprint = i as u64;
+        let mut j = 0;
+        while j < 8 {
+            fingerprint = (fingerprint >> 1) ^ (EMPTY & 0u64.wrapping_sub(fingerprint & 1));
+            j += 1;
+        }
+        table[i] = fingerprint;
+        i += 1;
+    }
+    table
+}
+
+/// The [CRC-64-AVRO] fingerprinting algorithm, which is the one used by the single-object
+/// encoding.
+///
+/// It implements `digest::Digest`, so that it can be used with
+/// [`Schema::fingerprint`](../schema/enum.Schema.html#method.fingerprint). The fingerprint is
+/// output as 8 little-endian bytes.
+///
+/// [CRC-64-AVRO]: https://avro.apache.org/docs/current/spec.html#schema_fingerprints
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rabin {
+    result: u64,
+}
+
+impl Default for Rabin {
+    fn default() -> Self {
+        Rabin { result: EMPTY }
+    }
+}
+
+impl Rabin {
+    /// Return the CRC-64-AVRO fingerprint of the Parsing Canonical Form of `schema`.
+    pub fn fingerprint64(schema: &Schema) -> u64 {
+        let mut rabin = Rabin::default();
+        rabin.update(schema.canonical_form());
+        rabin.result
+    }
+}
+
+impl Update for Rabin {
+    fn update(&mut self, data: impl AsRef<[u8]>) {
+        for &byte in data.as_ref() {
+            self.result = (self.result >> 8)
+                ^ FINGERPRINT_TABLE[((self.result ^ u64::from(byte)) & 0xff) as usize];
+        }
+    }
+}
+
+#[allow(deprecated)]
+impl FixedOutput for Rabin {
+    type OutputSize = U8;
+
+    fn finalize_into(self, out: &mut GenericArray<u8, Self::OutputSize>) {
+        out.copy_from_slice(&self.result.to_le_bytes());
+    }
+
+    fn finalize_into_reset(&mut self, out: &mut GenericArray<u8, Self::OutputSize>) {
+        out.copy_from_slice(&self.result.to_le_bytes());
+        self.reset();
+    }
+}
+
+impl Reset for Rabin {
+    fn reset(&mut self) {
+        self.result = EMPTY;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use digest::Digest;
+
+    #[test]
+    fn test_rabin_fingerprints() {
+        // Test vectors from the Java implementation of Avro
+        let cases = [
+            (r#""null""#, 7_195_948_357_588_979_594i64),
+            (r#""long""#, -3_434_872_931_120_570_953),
+            (r#""string""#, -8_142_146_995_180_207_161),
+            (
+                r#"{"type":"fixed","name":"foo","size":15}"#,
+                1_756_455_273_707_447_556,
+            ),
+        ];
+        for &(raw_schema, expected) in cases.iter() {
+            let schema = Schema::parse_str(raw_schema).unwrap();
+            assert_eq!(Rabin::fingerprint64(&schema), expected as u64);
+            assert_eq!(
+                schema.fingerprint::<Rabin>().bytes,
+                (expected as u64).to_le_bytes().to_vec()
+            );
+        }
+        assert_eq!(Rabin::digest(b"")[..], EMPTY.to_le_bytes());
+    }
+}
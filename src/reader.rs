@@ -1,36 +1,142 @@
 //! Logic handling reading from Avro format at user level.
-use std::io::{ErrorKind, Read};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::io::{self, ErrorKind, Read, Seek, SeekFrom};
+use std::marker::PhantomData;
 use std::str::{from_utf8, FromStr};
 
+use serde::de::{Deserialize, DeserializeOwned};
 use serde_json::from_slice;
 
-use crate::decode::decode;
+use crate::de_schema::SchemaAwareDeserializer;
+use crate::decode::{decode, decode_ref};
 use crate::errors::{AvroResult, Error};
+use crate::rabin::Rabin;
 use crate::schema::Schema;
-use crate::types::Value;
+use crate::types::{Value, ValueRef};
 use crate::util;
-use crate::Codec;
+use crate::writer::SINGLE_OBJECT_MARKER;
+use crate::{Codec, CodecOptions};
+
+/// The content of the header of a data file.
+pub(crate) struct Header {
+    pub(crate) schema: Schema,
+    pub(crate) codec: Codec,
+    pub(crate) marker: [u8; 16],
+    pub(crate) user_metadata: HashMap<String, Vec<u8>>,
+}
+
+/// Read the header of a data file: its magic bytes, its metadata, from which the `Schema`, the
+/// `Codec` and the user metadata are extracted, and its sync marker.
+pub(crate) fn read_header<R: Read>(reader: &mut R) -> AvroResult<Header> {
+    let meta_schema = Schema::Map(Box::new(Schema::Bytes));
+
+    let mut buf = [0u8; 4];
+    reader.read_exact(&mut buf)?;
+
+    if buf != [b'O', b'b', b'j', 1u8] {
+        return Err(Error::Decode("wrong magic in header".to_string()));
+    }
+
+    if let Value::Map(meta) = decode(&meta_schema, reader)? {
+        // TODO: surface original parse schema errors instead of coalescing them here
+        let schema = meta
+            .get("avro.schema")
+            .and_then(|bytes| {
+                if let Value::Bytes(ref bytes) = *bytes {
+                    from_slice(bytes.as_ref()).ok()
+                } else {
+                    None
+                }
+            })
+            .and_then(|json| Schema::parse(&json).ok());
+        let schema = schema.ok_or_else(|| Error::Parse("unable to parse schema".to_string()))?;
+
+        let codec = match meta.get("avro.codec") {
+            Some(Value::Bytes(ref bytes)) => from_utf8(bytes.as_ref())
+                .map_err(|e| Error::Decode(e.to_string()))
+                .and_then(Codec::from_str)?,
+            Some(_) => return Err(Error::Decode("codec is not bytes".to_string())),
+            None => Codec::Null,
+        };
+
+        let user_metadata = meta
+            .into_iter()
+            .filter(|(key, _)| !key.starts_with("avro."))
+            .filter_map(|(key, value)| match value {
+                Value::Bytes(bytes) => Some((key, bytes)),
+                _ => None,
+            })
+            .collect();
+
+        let mut marker = [0u8; 16];
+        reader.read_exact(&mut marker)?;
+
+        Ok(Header {
+            schema,
+            codec,
+            marker,
+            user_metadata,
+        })
+    } else {
+        Err(Error::Decode("no metadata in header".to_string()))
+    }
+}
+
+/// A reader keeping track of the number of bytes read through it, which is its position in the
+/// data file as long as it starts at its beginning.
+#[derive(Debug, Clone)]
+struct PositionedReader<R> {
+    inner: R,
+    position: u64,
+}
+
+impl<R: Read> Read for PositionedReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.position += n as u64;
+        Ok(n)
+    }
+}
+
+/// The size of a sync marker.
+pub(crate) const SYNC_SIZE: u64 = 16;
+/// The number of bytes read at once while scanning for a sync marker.
+const SYNC_SCAN_SIZE: usize = 8192;
 
 // Internal Block reader.
 #[derive(Debug, Clone)]
-struct Block<R> {
-    reader: R,
+pub(crate) struct Block<R> {
+    reader: PositionedReader<R>,
+    // Position of the current data block, right after the sync marker preceding it.
+    block_start: u64,
+    // Whether the current data block has been read up to its sync marker.
+    synced: bool,
     // Internal buffering to reduce allocation.
     buf: Vec<u8>,
     buf_idx: usize,
     // Number of elements expected to exist within this block.
     message_count: usize,
     marker: [u8; 16],
-    codec: Codec,
-    writer_schema: Schema,
+    pub(crate) codec: Codec,
+    pub(crate) codec_options: CodecOptions,
+    pub(crate) writer_schema: Schema,
+    pub(crate) user_metadata: HashMap<String, Vec<u8>>,
 }
 
 impl<R: Read> Block<R> {
-    fn new(reader: R) -> AvroResult<Block<R>> {
+    pub(crate) fn new(reader: R) -> AvroResult<Block<R>> {
         let mut block = Block {
-            reader,
+            reader: PositionedReader {
+                inner: reader,
+                position: 0,
+            },
+            block_start: 0,
+            synced: true,
             codec: Codec::Null,
+            codec_options: CodecOptions::default(),
             writer_schema: Schema::Null,
+            user_metadata: HashMap::new(),
             buf: vec![],
             buf_idx: 0,
             message_count: 0,
@@ -44,57 +150,21 @@ impl<R: Read> Block<R> {
     /// Try to read the header and to set the writer `Schema`, the `Codec` and the marker based on
     /// its content.
     fn read_header(&mut self) -> AvroResult<()> {
-        let meta_schema = Schema::Map(Box::new(Schema::Bytes));
-
-        let mut buf = [0u8; 4];
-        self.reader.read_exact(&mut buf)?;
-
-        if buf != [b'O', b'b', b'j', 1u8] {
-            return Err(Error::Decode("wrong magic in header".to_string()));
-        }
-
-        if let Value::Map(meta) = decode(&meta_schema, &mut self.reader)? {
-            // TODO: surface original parse schema errors instead of coalescing them here
-            let schema = meta
-                .get("avro.schema")
-                .and_then(|bytes| {
-                    if let Value::Bytes(ref bytes) = *bytes {
-                        from_slice(bytes.as_ref()).ok()
-                    } else {
-                        None
-                    }
-                })
-                .and_then(|json| Schema::parse(&json).ok());
-            if let Some(schema) = schema {
-                self.writer_schema = schema;
-            } else {
-                return Err(Error::Parse("unable to parse schema".to_string()));
-            }
-
-            if let Some(codec) = meta
-                .get("avro.codec")
-                .and_then(|codec| {
-                    if let Value::Bytes(ref bytes) = *codec {
-                        from_utf8(bytes.as_ref()).ok()
-                    } else {
-                        None
-                    }
-                })
-                .and_then(|codec| Codec::from_str(codec).ok())
-            {
-                self.codec = codec;
-            }
-        } else {
-            return Err(Error::Decode("no metadata in header".to_string()));
-        }
-
-        let mut buf = [0u8; 16];
-        self.reader.read_exact(&mut buf)?;
-        self.marker = buf;
-
+        let header = read_header(&mut self.reader)?;
+        self.writer_schema = header.schema;
+        self.codec = header.codec;
+        self.marker = header.marker;
+        self.user_metadata = header.user_metadata;
+        self.block_start = self.reader.position;
         Ok(())
     }
 
+    /// Get a mutable reference to the underlying reader.
+    #[cfg(feature = "async")]
+    pub(crate) fn get_mut(&mut self) -> &mut R {
+        &mut self.reader.inner
+    }
+
     fn fill_buf(&mut self, n: usize) -> AvroResult<()> {
         // The buffer needs to contain exactly `n` elements, otherwise codecs will potentially read
         // invalid bytes.
@@ -115,56 +185,112 @@ impl<R: Read> Block<R> {
 
     /// Try to read a data block, also performing schema resolution for the objects contained in
     /// the block. The objects are stored in an internal buffer to the `Reader`.
-    fn read_block_next(&mut self) -> AvroResult<()> {
+    ///
+    /// Return whether there was a data block left to read, possibly an empty one.
+    pub(crate) fn read_block_next(&mut self) -> AvroResult<bool> {
+        if !self.read_raw_block_next()? {
+            return Ok(false);
+        }
+        if !self.is_empty() {
+            // NOTE (JAB): This doesn't fit this Reader pattern very well.
+            // `self.buf` is a growable buffer that is reused as the reader is iterated.
+            // For non `Codec::Null` variants, `decompress` will allocate a new `Vec`
+            // and replace `buf` with the new one, instead of reusing the same buffer.
+            // We can address this by using some "limited read" type to decode directly
+            // into the buffer. But this is fine, for now.
+            self.codec
+                .decompress_with(&mut self.buf, &self.codec_options)?;
+        }
+        Ok(true)
+    }
+
+    /// Try to read a data block, leaving its bytes compressed in the internal buffer, and return
+    /// whether there was one left to read.
+    fn read_raw_block_next(&mut self) -> AvroResult<bool> {
         assert!(self.is_empty(), "Expected self to be empty!");
-        match util::read_long(&mut self.reader) {
-            Ok(block_len) => {
-                self.message_count = block_len as usize;
-                let block_bytes = util::read_long(&mut self.reader)?;
-                self.fill_buf(block_bytes as usize)?;
-                let mut marker = [0u8; 16];
-                self.reader.read_exact(&mut marker)?;
-
-                if marker != self.marker {
-                    return Err(Error::Decode(
-                        "block marker does not match header marker".to_string(),
-                    ));
-                }
+        self.block_start = self.reader.position;
+        self.synced = false;
+        let block_len = match util::read_long(&mut self.reader) {
+            Ok(block_len) => block_len,
+            Err(Error::IO(ref e))
+                if e.kind() == ErrorKind::UnexpectedEof
+                    && self.reader.position == self.block_start =>
+            {
+                // to not return any error in case we only finished to read cleanly from the stream
+                self.synced = true;
+                return Ok(false);
+            }
+            Err(_) => return Err(Error::Decode("unable to read block".to_string())),
+        };
+        self.message_count = usize::try_from(block_len)?;
+        let block_bytes = util::read_long(&mut self.reader)?;
+        self.fill_buf(util::safe_len(usize::try_from(block_bytes)?)?)?;
+        let mut marker = [0u8; 16];
+        self.reader.read_exact(&mut marker)?;
 
-                // NOTE (JAB): This doesn't fit this Reader pattern very well.
-                // `self.buf` is a growable buffer that is reused as the reader is iterated.
-                // For non `Codec::Null` variants, `decompress` will allocate a new `Vec`
-                // and replace `buf` with the new one, instead of reusing the same buffer.
-                // We can address this by using some "limited read" type to decode directly
-                // into the buffer. But this is fine, for now.
-                self.codec.decompress(&mut self.buf)?;
+        if marker != self.marker {
+            return Err(Error::Decode(
+                "block marker does not match header marker".to_string(),
+            ));
+        }
+        self.synced = true;
+        Ok(true)
+    }
 
-                return Ok(());
-            }
-            Err(e) => {
-                if let Error::IO(ioe) = e {
-                    if let ErrorKind::UnexpectedEof = ioe.kind() {
-                        // to not return any error in case we only finished to read cleanly from the stream
-                        return Ok(());
-                    }
+    /// Skip the rest of the current data block, scanning for the sync marker ending it if it has
+    /// not been read yet, and return the number of values it still announced.
+    ///
+    /// The scan reads one byte at a time, so as not to read past the marker without being able
+    /// to seek back, which is only cheap over a buffered reader.
+    fn skip_block(&mut self) -> AvroResult<u64> {
+        let lost_records = self.message_count as u64;
+        self.message_count = 0;
+        self.buf.clear();
+        self.buf_idx = 0;
+
+        if !self.synced {
+            let mut window = [0u8; 16];
+            let mut byte = [0u8; 1];
+            let mut read = 0;
+            while read < window.len() || window != self.marker {
+                match self.reader.read_exact(&mut byte) {
+                    Ok(()) => {}
+                    Err(ref e) if e.kind() == ErrorKind::UnexpectedEof => break,
+                    Err(e) => return Err(e.into()),
                 }
+                window.copy_within(1.., 0);
+                window[window.len() - 1] = byte[0];
+                read += 1;
             }
-        };
-        Err(Error::Decode("unable to read block".to_string()))
+            self.synced = true;
+        }
+        Ok(lost_records)
+    }
+
+    /// Check that the next value can be decoded, without consuming it.
+    fn check_next_ref(&mut self) -> AvroResult<()> {
+        // empty data blocks are skipped
+        while self.is_empty() {
+            if !self.read_block_next()? {
+                return Ok(());
+            }
+        }
+        decode_ref(&self.writer_schema, &mut &self.buf[self.buf_idx..])?;
+        Ok(())
     }
 
     fn len(&self) -> usize {
         self.message_count
     }
 
-    fn is_empty(&self) -> bool {
+    pub(crate) fn is_empty(&self) -> bool {
         self.len() == 0
     }
 
-    fn read_next(&mut self, read_schema: Option<&Schema>) -> AvroResult<Option<Value>> {
-        if self.is_empty() {
-            self.read_block_next()?;
-            if self.is_empty() {
+    pub(crate) fn read_next(&mut self, read_schema: Option<&Schema>) -> AvroResult<Option<Value>> {
+        // empty data blocks are skipped
+        while self.is_empty() {
+            if !self.read_block_next()? {
                 return Ok(None);
             }
         }
@@ -176,6 +302,41 @@ impl<R: Read> Block<R> {
         self.message_count -= 1;
         Ok(Some(item))
     }
+
+    fn read_next_ref(&mut self) -> AvroResult<Option<ValueRef<'_>>> {
+        // empty data blocks are skipped
+        while self.is_empty() {
+            if !self.read_block_next()? {
+                return Ok(None);
+            }
+        }
+
+        let mut block_bytes = &self.buf[self.buf_idx..];
+        let b_original = block_bytes.len();
+        let item = decode_ref(&self.writer_schema, &mut block_bytes)?;
+        self.buf_idx += b_original - block_bytes.len();
+        self.message_count -= 1;
+        Ok(Some(item))
+    }
+
+    fn read_next_deserialize<T: DeserializeOwned>(
+        &mut self,
+        read_schema: Option<&Schema>,
+    ) -> AvroResult<Option<T>> {
+        // empty data blocks are skipped
+        while self.is_empty() {
+            if !self.read_block_next()? {
+                return Ok(None);
+            }
+        }
+
+        let mut block_bytes = &self.buf[self.buf_idx..];
+        let b_original = block_bytes.len();
+        let item = from_avro_datum_de(&self.writer_schema, &mut block_bytes, read_schema)?;
+        self.buf_idx += b_original - block_bytes.len();
+        self.message_count -= 1;
+        Ok(Some(item))
+    }
 }
 
 /// Main interface for reading Avro formatted values.
@@ -198,6 +359,52 @@ pub struct Reader<'a, R> {
     reader_schema: Option<&'a Schema>,
     errored: bool,
     should_resolve_schema: bool,
+    recovery: bool,
+    on_skip: Option<SkipCallback<'a>>,
+    recovery_stats: RecoveryStats,
+}
+
+/// A callback called with the ranges of bytes skipped in recovery mode.
+type SkipCallback<'a> = Box<dyn FnMut(&SkippedRange) + 'a>;
+
+/// A range of bytes of a data file skipped by a `Reader` in recovery mode, from the beginning of a
+/// damaged data block up to the next sync marker, or the end of the file.
+#[derive(Debug)]
+pub struct SkippedRange {
+    /// The position of the damaged data block.
+    pub start: u64,
+    /// The position right after the sync marker following the damaged data block.
+    pub end: u64,
+    /// The number of values of the damaged data block which could not be read, as announced by
+    /// its header. The values of the following blocks skipped while scanning for the next sync
+    /// marker, when the damaged block does not end with one, are not counted.
+    pub lost_records: u64,
+    /// The error which revealed the damage.
+    pub error: Error,
+}
+
+/// Statistics about the data skipped by a `Reader` in recovery mode.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RecoveryStats {
+    /// The number of damaged data blocks skipped.
+    pub skipped_blocks: u64,
+    /// The number of bytes skipped.
+    pub skipped_bytes: u64,
+    /// The number of values which could not be read.
+    pub lost_records: u64,
+}
+
+/// Whether `error` may be caused by damaged data, rather than by the reader `Schema` or the
+/// underlying reader.
+fn is_damage(error: &Error) -> bool {
+    match *error {
+        Error::IO(ref e) => matches!(
+            e.kind(),
+            ErrorKind::UnexpectedEof | ErrorKind::InvalidData | ErrorKind::InvalidInput
+        ),
+        Error::SchemaResolution(_) | Error::De(_) => false,
+        _ => true,
+    }
 }
 
 impl<'a, R: Read> Reader<'a, R> {
@@ -212,6 +419,9 @@ impl<'a, R: Read> Reader<'a, R> {
             reader_schema: None,
             errored: false,
             should_resolve_schema: false,
+            recovery: false,
+            on_skip: None,
+            recovery_stats: RecoveryStats::default(),
         };
         Ok(reader)
     }
@@ -227,6 +437,9 @@ impl<'a, R: Read> Reader<'a, R> {
             reader_schema: Some(schema),
             errored: false,
             should_resolve_schema: false,
+            recovery: false,
+            on_skip: None,
+            recovery_stats: RecoveryStats::default(),
         };
         // Check if the reader and writer schemas disagree.
         reader.should_resolve_schema = reader.writer_schema() != schema;
@@ -243,6 +456,71 @@ impl<'a, R: Read> Reader<'a, R> {
         self.reader_schema
     }
 
+    /// Get the `Codec` the data blocks are compressed with.
+    pub fn codec(&self) -> Codec {
+        self.block.codec
+    }
+
+    /// Set the `CodecOptions` to decompress the following data blocks with.
+    pub fn set_codec_options(&mut self, options: CodecOptions) {
+        self.block.codec_options = options;
+    }
+
+    /// Get the user metadata of the header, which excludes the `avro.` keys reserved for the
+    /// specification.
+    pub fn user_metadata(&self) -> &HashMap<String, Vec<u8>> {
+        &self.block.user_metadata
+    }
+
+    /// Enable or disable the recovery mode, in which the data blocks that cannot be read are
+    /// skipped, up to the next sync marker, instead of stopping at the first error.
+    ///
+    /// The skipped ranges of bytes are reported to the callback given to
+    /// [`on_skip`](struct.Reader.html#method.on_skip) and summed up in the
+    /// [`recovery_stats`](struct.Reader.html#method.recovery_stats).
+    ///
+    /// **NOTE** Errors of schema resolution and of the underlying reader are still returned.
+    ///
+    /// **NOTE** The sync marker ending a damaged data block is looked for one byte at a time, so
+    /// an unbuffered reader, like a `File`, should be wrapped in an `io::BufReader`.
+    pub fn set_recovery_mode(&mut self, recovery: bool) {
+        self.recovery = recovery;
+    }
+
+    /// Set a callback to call with each range of bytes skipped in recovery mode.
+    pub fn on_skip<F: FnMut(&SkippedRange) + 'a>(&mut self, callback: F) {
+        self.on_skip = Some(Box::new(callback));
+    }
+
+    /// Get the statistics about the data skipped in recovery mode so far.
+    pub fn recovery_stats(&self) -> &RecoveryStats {
+        &self.recovery_stats
+    }
+
+    /// Skip the current data block after `error`, if the recovery mode is enabled and the error
+    /// may be caused by damaged data, or return the error otherwise.
+    fn recover(&mut self, error: Error) -> AvroResult<()> {
+        if !self.recovery || !is_damage(&error) {
+            return Err(error);
+        }
+        let start = self.block.block_start;
+        let lost_records = self.block.skip_block()?;
+        let skipped = SkippedRange {
+            start,
+            end: self.block.reader.position,
+            lost_records,
+            error,
+        };
+
+        self.recovery_stats.skipped_blocks += 1;
+        self.recovery_stats.skipped_bytes += skipped.end - skipped.start;
+        self.recovery_stats.lost_records += skipped.lost_records;
+        if let Some(ref mut on_skip) = self.on_skip {
+            on_skip(&skipped);
+        }
+        Ok(())
+    }
+
     #[inline]
     fn read_next(&mut self) -> AvroResult<Option<Value>> {
         let read_schema = if self.should_resolve_schema {
@@ -251,7 +529,181 @@ impl<'a, R: Read> Reader<'a, R> {
             None
         };
 
-        self.block.read_next(read_schema)
+        loop {
+            match self.block.read_next(read_schema) {
+                Err(e) => self.recover(e)?,
+                result => return result,
+            }
+        }
+    }
+
+    /// Read the next value without copying its strings and bytes, which are borrowed from the
+    /// current block of data instead, until the following call.
+    ///
+    /// This works like a lending iterator, which is useful to scan large files without allocating
+    /// a `String` or a `Vec<u8>` for every string and bytes of every record:
+    ///
+    /// ```no_run
+    /// # use avro_rs::{types::ValueRef, Reader};
+    /// # use std::io::Cursor;
+    /// # let input = Cursor::new(Vec::<u8>::new());
+    /// let mut reader = Reader::new(input).unwrap();
+    /// while let Some(value) = reader.next_ref() {
+    ///     if let ValueRef::Record(fields) = value.unwrap() {
+    ///         println!("{:?}", fields[0]);
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// **NOTE** The values follow the writer `Schema`: no schema resolution is performed, even if
+    /// a reader `Schema` is set.
+    pub fn next_ref(&mut self) -> Option<AvroResult<ValueRef<'_>>> {
+        // to prevent keep on reading after the first error occurs
+        if self.errored {
+            return None;
+        };
+        if self.recovery {
+            // The value cannot be borrowed once it has to be read again after skipping a damaged
+            // block, so check that it can be read beforehand.
+            while let Err(e) = self.block.check_next_ref() {
+                if let Err(e) = self.recover(e) {
+                    self.errored = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+        match self.block.read_next_ref() {
+            Ok(opt) => opt.map(Ok),
+            Err(e) => {
+                self.errored = true;
+                Some(Err(e))
+            }
+        }
+    }
+
+    /// Turn a `Reader` into an iterator deserializing each value straight into a `T`, without
+    /// building an intermediate `Value`:
+    ///
+    /// ```no_run
+    /// # use avro_rs::Reader;
+    /// # use serde::Deserialize;
+    /// # use std::io::Cursor;
+    /// # let input = Cursor::new(Vec::<u8>::new());
+    /// #[derive(Debug, Deserialize)]
+    /// struct Test {
+    ///     a: i64,
+    ///     b: String,
+    /// }
+    ///
+    /// for test in Reader::new(input).unwrap().into_deserialize_iter::<Test>() {
+    ///     println!("{:?}", test.unwrap());
+    /// }
+    /// ```
+    ///
+    /// Schema resolution is performed against the reader `Schema`, if any.
+    pub fn into_deserialize_iter<T: DeserializeOwned>(self) -> DeserializeIter<'a, R, T> {
+        DeserializeIter {
+            reader: self,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<R: Read + Seek> Block<R> {
+    /// Move to the first data block starting after `position`, by scanning for the next sync
+    /// marker, or to the end of the data file if there is none.
+    fn sync(&mut self, position: u64) -> AvroResult<()> {
+        let marker = self.marker;
+        let reader = &mut self.reader.inner;
+        reader.seek(SeekFrom::Start(position))?;
+
+        let mut window = Vec::with_capacity(SYNC_SCAN_SIZE + marker.len());
+        let mut window_start = position;
+        let mut chunk = [0u8; SYNC_SCAN_SIZE];
+        let found = loop {
+            let n = reader.read(&mut chunk)?;
+            if n == 0 {
+                break None;
+            }
+            window.extend_from_slice(&chunk[..n]);
+            if let Some(i) = window.windows(marker.len()).position(|w| w == marker) {
+                break Some(window_start + (i + marker.len()) as u64);
+            }
+            // Keep the bytes which might be the beginning of a marker cut by the chunk.
+            let keep = window.len().min(marker.len() - 1);
+            window_start += (window.len() - keep) as u64;
+            window.drain(..window.len() - keep);
+        };
+
+        let position = match found {
+            Some(position) => reader.seek(SeekFrom::Start(position))?,
+            None => reader.seek(SeekFrom::End(0))?,
+        };
+        self.reader.position = position;
+        self.block_start = position;
+        self.message_count = 0;
+        self.buf.clear();
+        self.buf_idx = 0;
+        Ok(())
+    }
+}
+
+impl<'a, R: Read + Seek> Reader<'a, R> {
+    /// Move to the first data block starting after `position`, a byte offset from the beginning
+    /// of the data file, by scanning for the next sync marker, or to the end of the data file if
+    /// there is none.
+    ///
+    /// Together with [`past_sync`](struct.Reader.html#method.past_sync), this lets several
+    /// readers each read the data blocks starting within a range of bytes, or split, of the same
+    /// data file:
+    ///
+    /// ```no_run
+    /// # use avro_rs::Reader;
+    /// # use std::fs::File;
+    /// # let (start, end) = (0, 1024);
+    /// let mut reader = Reader::new(File::open("data.avro").unwrap()).unwrap();
+    /// reader.sync(start).unwrap();
+    /// while !reader.past_sync(end).unwrap() {
+    ///     match reader.next() {
+    ///         Some(value) => println!("{:?}", value.unwrap()),
+    ///         None => break,
+    ///     }
+    /// }
+    /// ```
+    pub fn sync(&mut self, position: u64) -> AvroResult<()> {
+        self.block.sync(position)?;
+        self.errored = false;
+        Ok(())
+    }
+
+    /// Get the current position in the data file, right after the last data block read.
+    pub fn tell(&self) -> u64 {
+        self.block.reader.position
+    }
+
+    /// Get the position of the current data block, right after the sync marker preceding it,
+    /// which can be given to [`sync`](struct.Reader.html#method.sync) to read it again.
+    pub fn previous_sync(&self) -> u64 {
+        self.block.block_start
+    }
+
+    /// Check whether the next value to read is in a data block starting past the sync marker
+    /// following `position`, or whether there is nothing left to read, that is whether the
+    /// values of the split ending at `position` have all been read.
+    pub fn past_sync(&mut self, position: u64) -> AvroResult<bool> {
+        while self.block.is_empty() && !self.errored {
+            match self.block.read_block_next() {
+                Ok(true) => {}
+                Ok(false) => break,
+                Err(e) => {
+                    if let Err(e) = self.recover(e) {
+                        self.errored = true;
+                        return Err(e);
+                    }
+                }
+            }
+        }
+        Ok(self.block.is_empty() || self.block.block_start >= position + SYNC_SIZE)
     }
 }
 
@@ -273,6 +725,125 @@ impl<'a, R: Read> Iterator for Reader<'a, R> {
     }
 }
 
+/// Iterator over the values of a `Reader` deserialized into a `T`, created by
+/// [`Reader::into_deserialize_iter`](struct.Reader.html#method.into_deserialize_iter).
+pub struct DeserializeIter<'a, R, T> {
+    reader: Reader<'a, R>,
+    marker: PhantomData<T>,
+}
+
+impl<'a, R: Read, T: DeserializeOwned> Iterator for DeserializeIter<'a, R, T> {
+    type Item = AvroResult<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let reader = &mut self.reader;
+        // to prevent keep on reading after the first error occurs
+        if reader.errored {
+            return None;
+        };
+        let read_schema = if reader.should_resolve_schema {
+            reader.reader_schema
+        } else {
+            None
+        };
+        loop {
+            match reader.block.read_next_deserialize(read_schema) {
+                Ok(opt) => return opt.map(Ok),
+                Err(e) => {
+                    if let Err(e) = reader.recover(e) {
+                        reader.errored = true;
+                        return Some(Err(e));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// A data block read as is, with its values still encoded and compressed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RawBlock {
+    /// The number of values in the block.
+    pub record_count: usize,
+    /// The encoded values, compressed with the `Codec` of the data file.
+    pub data: Vec<u8>,
+}
+
+/// Interface for reading the data blocks of a data file without decoding their values, to copy
+/// them to another data file with [`Writer::append_blocks`](struct.Writer.html#method.append_blocks).
+///
+/// To be used as an iterator:
+///
+/// ```no_run
+/// # use avro_rs::BlockReader;
+/// # use std::io::Cursor;
+/// # let input = Cursor::new(Vec::<u8>::new());
+/// for block in BlockReader::new(input).unwrap() {
+///     let block = block.unwrap();
+///     println!("{} values in {} bytes", block.record_count, block.data.len());
+/// }
+/// ```
+pub struct BlockReader<R> {
+    block: Block<R>,
+    errored: bool,
+}
+
+impl<R: Read> BlockReader<R> {
+    /// Creates a `BlockReader` given something implementing the `io::Read` trait to read from.
+    ///
+    /// **NOTE** The avro header is going to be read automatically upon creation of the
+    /// `BlockReader`.
+    pub fn new(reader: R) -> AvroResult<BlockReader<R>> {
+        Ok(BlockReader {
+            block: Block::new(reader)?,
+            errored: false,
+        })
+    }
+
+    /// Get a reference to the writer `Schema`.
+    pub fn writer_schema(&self) -> &Schema {
+        &self.block.writer_schema
+    }
+
+    /// Get the `Codec` the data blocks are compressed with.
+    pub fn codec(&self) -> Codec {
+        self.block.codec
+    }
+
+    /// Get the user metadata of the header, which excludes the `avro.` keys reserved for the
+    /// specification.
+    pub fn user_metadata(&self) -> &HashMap<String, Vec<u8>> {
+        &self.block.user_metadata
+    }
+}
+
+impl<R: Read> Iterator for BlockReader<R> {
+    type Item = AvroResult<RawBlock>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // to prevent keep on reading after the first error occurs
+        if self.errored {
+            return None;
+        };
+        // empty data blocks are skipped
+        while self.block.is_empty() {
+            match self.block.read_raw_block_next() {
+                Ok(true) => {}
+                Ok(false) => return None,
+                Err(e) => {
+                    self.errored = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+        let record_count = std::mem::replace(&mut self.block.message_count, 0);
+        Some(Ok(RawBlock {
+            record_count,
+            data: std::mem::take(&mut self.block.buf),
+        }))
+    }
+}
+
 /// Decode a `Value` encoded in Avro format given its `Schema` and anything implementing `io::Read`
 /// to read from.
 ///
@@ -293,11 +864,111 @@ pub fn from_avro_datum<R: Read>(
     }
 }
 
+/// Deserialize a `T` from the start of a slice of Avro formatted data, given its `Schema`,
+/// without building an intermediate `Value`.
+///
+/// Strings and bytes can be borrowed from `input`, which is advanced past the decoded data. In
+/// case a reader `Schema` is provided, schema resolution will also be performed.
+pub fn from_avro_datum_de<'de, T: Deserialize<'de>>(
+    writer_schema: &Schema,
+    input: &mut &'de [u8],
+    reader_schema: Option<&Schema>,
+) -> AvroResult<T> {
+    T::deserialize(SchemaAwareDeserializer::new(
+        input,
+        writer_schema,
+        reader_schema,
+    ))
+}
+
+/// A collection of schemas that can be looked up by the CRC-64-AVRO fingerprint of their
+/// [Parsing Canonical Form], as computed by [`Rabin::fingerprint64`](struct.Rabin.html).
+///
+/// It is where a [`SingleObjectReader`](struct.SingleObjectReader.html) finds the schema that
+/// each datum was written with. It is implemented for a `HashMap` from fingerprints to schemas,
+/// the store of choice, and for a slice of schemas, which fingerprints them on every lookup.
+///
+/// [Parsing Canonical Form]:
+/// https://avro.apache.org/docs/1.8.2/spec.html#Parsing+Canonical+Form+for+Schemas
+pub trait SchemaStore {
+    /// Find the `Schema` with the given fingerprint.
+    fn find_by_fingerprint(&self, fingerprint: u64) -> Option<&Schema>;
+}
+
+/// Schemas keyed by their fingerprint, which are looked up in constant time.
+impl SchemaStore for HashMap<u64, Schema> {
+    fn find_by_fingerprint(&self, fingerprint: u64) -> Option<&Schema> {
+        self.get(&fingerprint)
+    }
+}
+
+/// Schemas whose fingerprints are computed on every lookup, each time going through the canonical
+/// form of the schemas until one matches. This is only fit for a handful of small schemas or for
+/// occasional lookups: a `HashMap` keyed by the fingerprints computed once is preferable otherwise.
+impl SchemaStore for [Schema] {
+    fn find_by_fingerprint(&self, fingerprint: u64) -> Option<&Schema> {
+        self.iter()
+            .find(|schema| Rabin::fingerprint64(schema) == fingerprint)
+    }
+}
+
+/// Reader for the [single-object encoding] of Avro data, which looks up the schema each datum was
+/// written with in a [`SchemaStore`](trait.SchemaStore.html).
+///
+/// It can read the messages written by a [`SingleObjectWriter`](struct.SingleObjectWriter.html)
+/// or by the `BinaryMessageEncoder` of the Java implementation.
+///
+/// [single-object encoding]: https://avro.apache.org/docs/current/spec.html#single_object_encoding
+pub struct SingleObjectReader<'a, S: SchemaStore + ?Sized> {
+    store: &'a S,
+    reader_schema: Option<&'a Schema>,
+}
+
+impl<'a, S: SchemaStore + ?Sized> SingleObjectReader<'a, S> {
+    /// Create a `SingleObjectReader` looking up writer schemas in `store`.
+    /// No reader `Schema` will be set.
+    pub fn new(store: &'a S) -> Self {
+        SingleObjectReader {
+            store,
+            reader_schema: None,
+        }
+    }
+
+    /// Create a `SingleObjectReader` looking up writer schemas in `store`, and performing schema
+    /// resolution with the given reader `Schema`.
+    pub fn with_schema(store: &'a S, reader_schema: &'a Schema) -> Self {
+        SingleObjectReader {
+            store,
+            reader_schema: Some(reader_schema),
+        }
+    }
+
+    /// Read a single-object encoded `Value` from anything implementing `io::Read`.
+    pub fn read_value<R: Read>(&self, reader: &mut R) -> AvroResult<Value> {
+        let mut marker = [0u8; 2];
+        reader.read_exact(&mut marker)?;
+        if marker != SINGLE_OBJECT_MARKER {
+            return Err(Error::SingleObjectMarker(marker));
+        }
+
+        let mut fingerprint = [0u8; 8];
+        reader.read_exact(&mut fingerprint)?;
+        let fingerprint = u64::from_le_bytes(fingerprint);
+        let writer_schema = self
+            .store
+            .find_by_fingerprint(fingerprint)
+            .ok_or(Error::UnknownSchemaFingerprint(fingerprint))?;
+
+        from_avro_datum(writer_schema, reader, self.reader_schema)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_util::write_blocks;
     use crate::types::Record;
-    use crate::Reader;
+    use crate::{Codec, Reader};
     use std::io::Cursor;
 
     const SCHEMA: &str = r#"
@@ -382,6 +1053,89 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_reader_next_ref() {
+        let mut reader = Reader::new(ENCODED).unwrap();
+        let mut values = Vec::new();
+        while let Some(value) = reader.next_ref() {
+            values.push(Value::from(value.unwrap()));
+        }
+        assert_eq!(
+            values,
+            Reader::new(ENCODED)
+                .unwrap()
+                .collect::<AvroResult<Vec<_>>>()
+                .unwrap()
+        );
+
+        let mut reader = Reader::new(ENCODED).unwrap();
+        assert_eq!(
+            reader.next_ref().unwrap().unwrap(),
+            ValueRef::Record(vec![
+                ("a", ValueRef::Long(27)),
+                ("b", ValueRef::String("foo"))
+            ])
+        );
+    }
+
+    #[test]
+    fn test_reader_into_deserialize_iter() {
+        #[derive(Debug, serde::Deserialize, PartialEq)]
+        struct Test {
+            a: i64,
+            b: String,
+        }
+
+        #[derive(Debug, serde::Deserialize, PartialEq)]
+        struct Renamed {
+            b: String,
+            c: Option<f64>,
+        }
+
+        let values = Reader::new(ENCODED)
+            .unwrap()
+            .into_deserialize_iter::<Test>()
+            .collect::<AvroResult<Vec<_>>>()
+            .unwrap();
+        assert_eq!(
+            values,
+            vec![
+                Test {
+                    a: 27,
+                    b: "foo".to_string(),
+                },
+                Test {
+                    a: 42,
+                    b: "bar".to_string(),
+                },
+            ]
+        );
+
+        let schema = Schema::parse_str(
+            r#"
+            {
+              "type": "record",
+              "name": "test",
+              "fields": [
+                {"name": "b", "type": "string"},
+                {"name": "c", "type": ["null", "double"], "default": null}
+              ]
+            }
+            "#,
+        )
+        .unwrap();
+        let mut iter = Reader::with_schema(&schema, ENCODED)
+            .unwrap()
+            .into_deserialize_iter::<Renamed>();
+        assert_eq!(
+            iter.next().unwrap().unwrap(),
+            Renamed {
+                b: "foo".to_string(),
+                c: None,
+            }
+        );
+    }
+
     #[test]
     fn test_reader_invalid_header() {
         let schema = Schema::parse_str(SCHEMA).unwrap();
@@ -389,6 +1143,17 @@ mod tests {
         assert!(Reader::with_schema(&schema, &invalid[..]).is_err());
     }
 
+    #[test]
+    fn test_reader_unknown_codec() {
+        let position = ENCODED.windows(4).position(|w| w == b"null").unwrap();
+        let mut invalid = ENCODED.to_vec();
+        invalid[position..position + 4].copy_from_slice(b"lzma");
+        match Reader::new(&invalid[..]) {
+            Err(Error::Codec(codec)) => assert_eq!(codec, "lzma"),
+            other => panic!("expected an unknown codec error, got {:?}", other.err()),
+        }
+    }
+
     #[test]
     fn test_reader_invalid_block() {
         let schema = Schema::parse_str(SCHEMA).unwrap();
@@ -413,6 +1178,265 @@ mod tests {
         assert!(Reader::new(empty).is_err());
     }
 
+    #[test]
+    fn test_reader_sync_and_tell() {
+        let schema = Schema::parse_str(SCHEMA).unwrap();
+        let input = write_blocks(&schema, Codec::Null, 20);
+
+        let mut reader = Reader::new(Cursor::new(&input[..])).unwrap();
+        let header_len = reader.previous_sync();
+        let first = reader.next().unwrap().unwrap();
+        assert_eq!(reader.previous_sync(), header_len);
+        let second_block = reader.tell();
+        assert!(second_block > header_len);
+
+        reader.sync(0).unwrap();
+        assert_eq!(reader.tell(), header_len);
+        assert_eq!(reader.next().unwrap().unwrap(), first);
+
+        reader.sync(header_len).unwrap();
+        assert_eq!(reader.previous_sync(), second_block);
+        assert_ne!(reader.next().unwrap().unwrap(), first);
+
+        reader.sync(input.len() as u64 - 1).unwrap();
+        assert_eq!(reader.tell(), input.len() as u64);
+        assert!(reader.next().is_none());
+        assert!(reader.past_sync(0).unwrap());
+    }
+
+    #[test]
+    fn test_reader_splits() {
+        let schema = Schema::parse_str(SCHEMA).unwrap();
+        let input = write_blocks(&schema, Codec::Null, 100);
+        let all = Reader::new(&input[..])
+            .unwrap()
+            .collect::<AvroResult<Vec<_>>>()
+            .unwrap();
+
+        let split_len = input.len() as u64 / 7;
+        let mut values = Vec::new();
+        for start in (0..input.len() as u64).step_by(split_len as usize) {
+            let mut reader = Reader::new(Cursor::new(&input[..])).unwrap();
+            reader.sync(start).unwrap();
+            while !reader.past_sync(start + split_len).unwrap() {
+                values.push(reader.next().unwrap().unwrap());
+            }
+        }
+        assert_eq!(values, all);
+    }
+
+    #[test]
+    fn test_reader_recovery() {
+        let schema = Schema::parse_str(SCHEMA).unwrap();
+        let input = write_blocks(&schema, Codec::Null, 20);
+        let all = Reader::new(&input[..])
+            .unwrap()
+            .collect::<AvroResult<Vec<_>>>()
+            .unwrap();
+
+        // Shrink the size of the second data block, so that it does not end with a marker.
+        let marker = &input[input.len() - 16..];
+        let mut markers = input
+            .windows(16)
+            .enumerate()
+            .filter(|(_, w)| *w == marker)
+            .map(|(i, _)| i as u64 + 16);
+        let first_block = markers.next().unwrap();
+        let first_count = util::read_long(&mut &input[first_block as usize..]).unwrap() as usize;
+        let (start, end) = (markers.next().unwrap(), markers.next().unwrap());
+        let mut block = &input[start as usize..];
+        let count = util::read_long(&mut block).unwrap() as u64;
+        let size_position = input.len() - block.len();
+        let mut damaged = input.clone();
+        damaged[size_position] -= 2;
+
+        let mut reader = Reader::new(&damaged[..]).unwrap();
+        assert!(reader.by_ref().any(|value| value.is_err()));
+        assert!(reader.next().is_none());
+
+        let mut skipped = Vec::new();
+        let mut reader = Reader::new(&damaged[..]).unwrap();
+        reader.set_recovery_mode(true);
+        reader.on_skip(|range| skipped.push((range.start, range.end, range.lost_records)));
+        let values = reader.by_ref().collect::<AvroResult<Vec<_>>>().unwrap();
+        assert_eq!(
+            reader.recovery_stats(),
+            &RecoveryStats {
+                skipped_blocks: 1,
+                skipped_bytes: end - start,
+                lost_records: count,
+            }
+        );
+        drop(reader);
+        assert_eq!(skipped, vec![(start, end, count)]);
+
+        assert_eq!(values.len() as u64, all.len() as u64 - count);
+        assert_eq!(values[..first_count], all[..first_count]);
+        assert_eq!(values[first_count..], all[first_count + count as usize..]);
+
+        let mut reader = Reader::new(&damaged[..]).unwrap();
+        reader.set_recovery_mode(true);
+        let mut read = 0;
+        while let Some(value) = reader.next_ref() {
+            value.unwrap();
+            read += 1;
+        }
+        assert_eq!(read, values.len());
+    }
+
+    #[test]
+    fn test_reader_recovery_from_damaged_count() {
+        let schema = Schema::parse_str(SCHEMA).unwrap();
+        let input = write_blocks(&schema, Codec::Null, 20);
+        let all = Reader::new(&input[..])
+            .unwrap()
+            .collect::<AvroResult<Vec<_>>>()
+            .unwrap();
+
+        // Overwrite the record count of the second data block with a varint which overflows.
+        let marker = &input[input.len() - 16..];
+        let mut markers = input
+            .windows(16)
+            .enumerate()
+            .filter(|(_, w)| *w == marker)
+            .map(|(i, _)| i + 16);
+        markers.next().unwrap();
+        let (start, end) = (markers.next().unwrap(), markers.next().unwrap());
+        let count = util::read_long(&mut &input[start..]).unwrap() as usize;
+        let mut damaged = input.clone();
+        damaged[start..start + 11].copy_from_slice(&[0xff; 11]);
+
+        let mut reader = Reader::new(&damaged[..]).unwrap();
+        reader.set_recovery_mode(true);
+        let values = reader.by_ref().collect::<AvroResult<Vec<_>>>().unwrap();
+        assert_eq!(
+            reader.recovery_stats(),
+            &RecoveryStats {
+                skipped_blocks: 1,
+                skipped_bytes: (end - start) as u64,
+                lost_records: 0,
+            }
+        );
+        assert_eq!(values.len(), all.len() - count);
+    }
+
+    #[cfg(feature = "snappy")]
+    #[test]
+    fn test_reader_recovery_from_short_snappy_block() {
+        let schema = Schema::parse_str(SCHEMA).unwrap();
+        let input = write_blocks(&schema, Codec::Snappy, 20);
+        let all = Reader::new(&input[..])
+            .unwrap()
+            .collect::<AvroResult<Vec<_>>>()
+            .unwrap();
+
+        // Insert a block of 1 record in 2 bytes after the first one, too short for a checksum.
+        let marker = &input[input.len() - 16..];
+        let first_end = input
+            .windows(16)
+            .enumerate()
+            .filter(|(_, w)| *w == marker)
+            .nth(1)
+            .unwrap()
+            .0
+            + 16;
+        let mut damaged = input[..first_end].to_vec();
+        damaged.extend_from_slice(&[2, 4, 0, 0]);
+        damaged.extend_from_slice(marker);
+        damaged.extend_from_slice(&input[first_end..]);
+
+        let mut reader = Reader::new(&damaged[..]).unwrap();
+        match reader.find_map(Result::err) {
+            Some(Error::SnappyDecompress(2)) => {}
+            other => panic!("expected a short Snappy block, got {:?}", other),
+        }
+
+        let mut reader = Reader::new(&damaged[..]).unwrap();
+        reader.set_recovery_mode(true);
+        let values = reader.by_ref().collect::<AvroResult<Vec<_>>>().unwrap();
+        assert_eq!(
+            reader.recovery_stats(),
+            &RecoveryStats {
+                skipped_blocks: 1,
+                skipped_bytes: 20,
+                lost_records: 1,
+            }
+        );
+        assert_eq!(values, all);
+    }
+
+    #[test]
+    fn test_block_reader() {
+        let schema = Schema::parse_str(SCHEMA).unwrap();
+        let input = write_blocks(&schema, Codec::Null, 20);
+
+        let blocks = BlockReader::new(&input[..]).unwrap();
+        assert_eq!(blocks.writer_schema(), &schema);
+        assert_eq!(blocks.codec(), Codec::Null);
+        let blocks = blocks.collect::<AvroResult<Vec<_>>>().unwrap();
+        assert!(blocks.len() > 1);
+        assert_eq!(
+            blocks.iter().map(|block| block.record_count).sum::<usize>(),
+            20
+        );
+
+        let mut data = &blocks[0].data[..];
+        let first = from_avro_datum(&schema, &mut data, None).unwrap();
+        assert_eq!(
+            Some(first),
+            Reader::new(&input[..]).unwrap().next().transpose().unwrap()
+        );
+
+        // Insert an empty data block after the first one.
+        let marker = &input[input.len() - 16..];
+        let first_end = input
+            .windows(16)
+            .enumerate()
+            .filter(|(_, w)| *w == marker)
+            .nth(1)
+            .unwrap()
+            .0
+            + 16;
+        let mut with_empty = input[..first_end].to_vec();
+        with_empty.extend_from_slice(&[0, 0]);
+        with_empty.extend_from_slice(marker);
+        with_empty.extend_from_slice(&input[first_end..]);
+        let read = BlockReader::new(&with_empty[..])
+            .unwrap()
+            .collect::<AvroResult<Vec<_>>>()
+            .unwrap();
+        assert_eq!(read, blocks);
+
+        let all = Reader::new(&input[..])
+            .unwrap()
+            .collect::<AvroResult<Vec<_>>>()
+            .unwrap();
+        let values = Reader::new(&with_empty[..])
+            .unwrap()
+            .collect::<AvroResult<Vec<_>>>()
+            .unwrap();
+        assert_eq!(values, all);
+
+        let mut reader = Reader::new(&with_empty[..]).unwrap();
+        let mut read = 0;
+        while let Some(value) = reader.next_ref() {
+            value.unwrap();
+            read += 1;
+        }
+        assert_eq!(read, all.len());
+
+        #[derive(Debug, serde::Deserialize)]
+        struct Test {
+            a: i64,
+        }
+        let deserialized = Reader::new(&with_empty[..])
+            .unwrap()
+            .into_deserialize_iter::<Test>()
+            .map(|test| test.unwrap().a)
+            .collect::<Vec<_>>();
+        assert_eq!(deserialized, (0..20).collect::<Vec<_>>());
+    }
+
     #[test]
     fn test_reader_only_header() {
         let invalid = ENCODED
@@ -425,4 +1449,53 @@ mod tests {
             assert!(value.is_err());
         }
     }
+
+    #[test]
+    fn test_single_object_reader() {
+        let schema = Schema::parse_str(SCHEMA).unwrap();
+        let mut record = Record::new(&schema).unwrap();
+        record.put("a", 27i64);
+        record.put("b", "foo");
+        let expected: Value = record.into();
+        let encoded = crate::SingleObjectWriter::new(&schema)
+            .to_vec(expected.clone())
+            .unwrap();
+
+        let mut store = HashMap::new();
+        store.insert(Rabin::fingerprint64(&schema), schema.clone());
+        let reader = SingleObjectReader::new(&store);
+        assert_eq!(reader.read_value(&mut &encoded[..]).unwrap(), expected);
+
+        let reader_schema = Schema::parse_str(
+            r#"{"type": "record", "name": "test", "fields": [{"name": "b", "type": "string"}]}"#,
+        )
+        .unwrap();
+        let schemas = [Schema::Null, schema];
+        let reader = SingleObjectReader::with_schema(&schemas[..], &reader_schema);
+        assert_eq!(
+            reader.read_value(&mut &encoded[..]).unwrap(),
+            Value::Record(vec![("b".to_string(), Value::String("foo".to_string()))])
+        );
+    }
+
+    #[test]
+    fn test_single_object_reader_errors() {
+        let schema = Schema::parse_str(UNION_SCHEMA).unwrap();
+        let encoded = crate::SingleObjectWriter::new(&schema)
+            .to_vec(Value::Union(Box::new(Value::Null)))
+            .unwrap();
+        let store = [Schema::Long];
+        let reader = SingleObjectReader::new(&store[..]);
+
+        match reader.read_value(&mut &encoded[..]) {
+            Err(Error::UnknownSchemaFingerprint(fingerprint)) => {
+                assert_eq!(fingerprint, Rabin::fingerprint64(&schema))
+            }
+            other => panic!("expected an unknown fingerprint, got {:?}", other),
+        }
+        match reader.read_value(&mut &encoded[1..]) {
+            Err(Error::SingleObjectMarker(_)) => {}
+            other => panic!("expected an invalid marker, got {:?}", other),
+        }
+    }
 }
@@ -4,12 +4,13 @@ use crate::types;
 use crate::util::MapHelper;
 use digest::Digest;
 use serde::{
-    ser::{SerializeMap, SerializeSeq},
+    ser::{self, SerializeMap, SerializeSeq},
     Deserialize, Serialize, Serializer,
 };
 use serde_json::{Map, Value};
 use std::borrow::Cow;
-use std::collections::HashMap;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::convert::TryInto;
 use std::fmt;
 use std::str::FromStr;
@@ -78,15 +79,24 @@ pub enum Schema {
         doc: Documentation,
         fields: Vec<RecordField>,
         lookup: HashMap<String, usize>,
+        custom_attributes: CustomAttributes,
     },
     /// An `enum` Avro schema.
+    ///
+    /// The `default` symbol is used when reading a symbol that is not among the `symbols`.
     Enum {
         name: Name,
         doc: Documentation,
         symbols: Vec<String>,
+        default: Option<String>,
+        custom_attributes: CustomAttributes,
     },
     /// A `fixed` Avro schema.
-    Fixed { name: Name, size: usize },
+    Fixed {
+        name: Name,
+        size: usize,
+        custom_attributes: CustomAttributes,
+    },
     /// Logical type which represents `Decimal` values. The underlying type is serialized and
     /// deserialized as `Schema::Bytes` or `Schema::Fixed`.
     ///
@@ -114,6 +124,23 @@ pub enum Schema {
     TimestampMicros,
     /// An amount of time defined by a number of months, days and milliseconds.
     Duration,
+    /// A reference to a named type (`record`, `enum` or `fixed`) defined elsewhere in the schema.
+    ///
+    /// References are what allow recursive schemas, such as linked lists and trees. The `name`
+    /// is always fully qualified.
+    ///
+    /// A `Schema` nested in another one is not self-contained when it contains such a reference
+    /// to a type defined outside of it: its canonical form refers to that type by name only, and
+    /// cannot be parsed on its own.
+    Ref { name: Name },
+    /// A schema of any other type, written in the object form along with attributes that are not
+    /// defined by the Avro specification, such as an unknown `logicalType`.
+    ///
+    /// The values of the `inner` schema are read and written as if it were not annotated.
+    Annotated {
+        inner: Box<Schema>,
+        custom_attributes: CustomAttributes,
+    },
 }
 
 impl PartialEq for Schema {
@@ -192,6 +219,67 @@ pub struct Name {
 /// Represents documentation for complex Avro schemas.
 pub type Documentation = Option<String>;
 
+/// Represents the attributes of a JSON Avro schema that are not defined by the Avro
+/// specification, such as `"connect.name"` or an unknown `logicalType`.
+///
+/// They have no effect on reading or writing data, but they are kept when a `Schema` is
+/// serialized back to JSON.
+pub type CustomAttributes = BTreeMap<String, Value>;
+
+/// Attributes defined by the Avro specification for a `record`.
+const RECORD_ATTRIBUTES: &[&str] = &["type", "name", "namespace", "doc", "aliases", "fields"];
+/// Attributes defined by the Avro specification for an `enum`.
+const ENUM_ATTRIBUTES: &[&str] = &[
+    "type",
+    "name",
+    "namespace",
+    "doc",
+    "aliases",
+    "symbols",
+    "default",
+];
+/// Attributes defined by the Avro specification for a `fixed`.
+const FIXED_ATTRIBUTES: &[&str] = &["type", "name", "namespace", "aliases", "size"];
+/// Attributes defined by the Avro specification for a `field` of a `record`.
+const FIELD_ATTRIBUTES: &[&str] = &["name", "doc", "type", "default", "order", "aliases"];
+/// Attributes of a logical type understood by this library.
+const LOGICAL_TYPE_ATTRIBUTES: &[&str] = &["logicalType", "precision", "scale"];
+/// Logical types understood by this library. Any other `logicalType` is kept as a custom
+/// attribute.
+const LOGICAL_TYPES: &[&str] = &[
+    "decimal",
+    "uuid",
+    "date",
+    "time-millis",
+    "time-micros",
+    "timestamp-millis",
+    "timestamp-micros",
+    "duration",
+];
+
+/// Parse the `aliases` of a named type or of a record field.
+fn parse_aliases(complex: &Map<String, Value>) -> Option<Vec<String>> {
+    complex
+        .get("aliases")
+        .and_then(|aliases| aliases.as_array())
+        .and_then(|aliases| {
+            aliases
+                .iter()
+                .map(|alias| alias.as_str())
+                .map(|alias| alias.map(|a| a.to_string()))
+                .collect::<Option<_>>()
+        })
+}
+
+/// Collect the attributes of `complex` that do not appear in `known`.
+fn parse_custom_attributes(complex: &Map<String, Value>, known: &[&str]) -> CustomAttributes {
+    complex
+        .iter()
+        .filter(|(key, _)| !known.contains(&key.as_str()))
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect()
+}
+
 impl Name {
     /// Create a new `Name`.
     /// No `namespace` nor `aliases` will be defined.
@@ -204,31 +292,51 @@ impl Name {
     }
 
     /// Parse a `serde_json::Value` into a `Name`.
-    fn parse(complex: &Map<String, Value>) -> AvroResult<Self> {
+    ///
+    /// A `name` that is neither qualified nor given a `namespace` inherits the namespace of its
+    /// most tightly enclosing named type.
+    fn parse(complex: &Map<String, Value>, enclosing_namespace: Option<&str>) -> AvroResult<Self> {
         let name = complex
             .name()
             .ok_or_else(|| Error::Parse("No `name` field".to_string()))?;
 
-        let namespace = complex.string("namespace");
-
-        let aliases: Option<Vec<String>> = complex
-            .get("aliases")
-            .and_then(|aliases| aliases.as_array())
-            .and_then(|aliases| {
-                aliases
-                    .iter()
-                    .map(|alias| alias.as_str())
-                    .map(|alias| alias.map(|a| a.to_string()))
-                    .collect::<Option<_>>()
-            });
+        let namespace = match complex.string("namespace") {
+            // The empty string explicitly stands for the null namespace.
+            Some(ref namespace) if namespace.is_empty() => None,
+            Some(namespace) => Some(namespace),
+            None if name.contains('.') => None,
+            None => enclosing_namespace.map(|namespace| namespace.to_owned()),
+        };
 
         Ok(Name {
             name,
             namespace,
-            aliases,
+            aliases: parse_aliases(complex),
         })
     }
 
+    /// Return whether data written with the named type called `writers_name` can be read with the
+    /// named type called `self`, because they have the same fullname or because `writers_name`
+    /// is one of the aliases of `self`.
+    ///
+    /// Aliases that are not fully qualified are relative to the namespace of `self`.
+    pub(crate) fn matches(&self, writers_name: &Name) -> bool {
+        let writers_fullname = writers_name.fullname(None);
+        self.fullname(None) == writers_fullname
+            || self.aliases.iter().flatten().any(|alias| {
+                Name::new(alias).fullname(self.effective_namespace()) == writers_fullname
+            })
+    }
+
+    /// Return the namespace of the `fullname` of this `Name`, which is inherited by the named
+    /// types defined within it.
+    pub(crate) fn effective_namespace(&self) -> Option<&str> {
+        match self.name.rfind('.') {
+            Some(index) => Some(&self.name[..index]),
+            None => self.namespace.as_deref(),
+        }
+    }
+
     /// Return the `fullname` of this `Name`
     ///
     /// More information about fullnames can be found in the
@@ -262,6 +370,10 @@ pub struct RecordField {
     /// This value will be used when reading Avro datum if schema resolution
     /// is enabled.
     pub default: Option<Value>,
+    /// Aliases of the field.
+    /// A field of the writer's schema named after one of them is read into this field if schema
+    /// resolution is enabled.
+    pub aliases: Option<Vec<String>>,
     /// Schema of the field.
     pub schema: Schema,
     /// Order of the field.
@@ -270,6 +382,8 @@ pub struct RecordField {
     pub order: RecordFieldOrder,
     /// Position of the field in the list of `field` of its parent `Schema`
     pub position: usize,
+    /// Attributes of the field that are not defined by the Avro specification.
+    pub custom_attributes: CustomAttributes,
 }
 
 /// Represents any valid order for a `field` in a `record` Avro schema.
@@ -283,13 +397,18 @@ pub enum RecordFieldOrder {
 
 impl RecordField {
     /// Parse a `serde_json::Value` into a `RecordField`.
-    fn parse(field: &Map<String, Value>, position: usize) -> AvroResult<Self> {
+    fn parse<'a>(
+        field: &'a Map<String, Value>,
+        position: usize,
+        parser: &mut Parser<'a>,
+        enclosing_namespace: Option<&str>,
+    ) -> AvroResult<Self> {
         let name = field
             .name()
             .ok_or_else(|| Error::Parse("No `name` in record field".to_string()))?;
 
-        // TODO: "type" = "<record name>"
-        let schema = Schema::parse_complex(field)?;
+        // The attributes of the field are its own, rather than the ones of its type.
+        let schema = parser.parse_complex_type(field, enclosing_namespace)?;
 
         let default = field.get("default").cloned();
 
@@ -299,13 +418,31 @@ impl RecordField {
             .and_then(|order| RecordFieldOrder::from_str(order).ok())
             .unwrap_or_else(|| RecordFieldOrder::Ascending);
 
+        // A logical type given on the field itself has been turned into its `schema`, unless it
+        // is unknown to this library.
+        let mut custom_attributes = parse_custom_attributes(field, FIELD_ATTRIBUTES);
+        match field.get("logicalType").and_then(|t| t.as_str()) {
+            Some(logical_type) if LOGICAL_TYPES.contains(&logical_type) => {
+                custom_attributes.retain(|key, _| !LOGICAL_TYPE_ATTRIBUTES.contains(&key.as_str()))
+            }
+            _ => {}
+        }
+        // So has an array or a map defined on the field itself.
+        match field.get("type").and_then(|t| t.as_str()) {
+            Some("array") => custom_attributes.remove("items"),
+            Some("map") => custom_attributes.remove("values"),
+            _ => None,
+        };
+
         Ok(RecordField {
             name,
             doc: field.doc(),
             default,
+            aliases: parse_aliases(field),
             schema,
             order,
             position,
+            custom_attributes,
         })
     }
 }
@@ -324,11 +461,18 @@ impl UnionSchema {
     pub(crate) fn new(schemas: Vec<Schema>) -> AvroResult<Self> {
         let mut vindex = HashMap::new();
         for (i, schema) in schemas.iter().enumerate() {
+            // Annotations do not change the type of a variant.
+            let schema = schema.unannotated();
             if let Schema::Union(_) = schema {
                 return Err(Error::Parse(
                     "Unions may not directly contain a union".to_string(),
                 ));
             }
+            // References are only resolved once a value is looked up, see
+            // `find_schema_with_root`.
+            if let Schema::Ref { .. } = schema {
+                continue;
+            }
             let kind = SchemaKind::from(schema);
             if vindex.insert(kind, i).is_some() {
                 return Err(Error::Parse(
@@ -360,6 +504,92 @@ impl UnionSchema {
             .cloned()
             .map(|i| (i, &self.schemas[i]))
     }
+
+    /// Like [`find_schema`](#method.find_schema), but also considers the variants referring to
+    /// named types, which are resolved against `root`, the top-level `Schema` this union is part
+    /// of.
+    pub(crate) fn find_schema_with_root(
+        &self,
+        value: &types::Value,
+        root: &Root,
+    ) -> Option<(usize, &Schema)> {
+        self.find_schema(value).or_else(|| {
+            self.schemas
+                .iter()
+                .enumerate()
+                .find(|(_, schema)| match schema {
+                    Schema::Ref { name } => root
+                        .lookup_named(&name.fullname(None))
+                        .map(|named| value.validate_internal(named, root))
+                        .unwrap_or(false),
+                    _ => false,
+                })
+        })
+    }
+}
+
+/// The top-level `Schema` that the schemas being encoded, decoded or resolved are part of,
+/// through which a `Schema::Ref` is followed back to the named type it refers to.
+///
+/// The definitions of the named types are collected on the first lookup, so that the whole
+/// `Schema` is walked at most once.
+pub(crate) struct Root<'s> {
+    schema: &'s Schema,
+    definitions: RefCell<Option<HashMap<String, &'s Schema>>>,
+}
+
+impl<'s> Root<'s> {
+    pub(crate) fn new(schema: &'s Schema) -> Self {
+        Root {
+            schema,
+            definitions: RefCell::new(None),
+        }
+    }
+
+    pub(crate) fn schema(&self) -> &'s Schema {
+        self.schema
+    }
+
+    /// Find the definition of the named type (`record`, `enum` or `fixed`) with the given
+    /// `fullname`.
+    pub(crate) fn lookup_named(&self, fullname: &str) -> Option<&'s Schema> {
+        let mut definitions = self.definitions.borrow_mut();
+        definitions
+            .get_or_insert_with(|| {
+                let mut definitions = HashMap::new();
+                collect_named(self.schema, &mut definitions);
+                definitions
+            })
+            .get(fullname)
+            .copied()
+    }
+}
+
+fn collect_named<'s>(schema: &'s Schema, definitions: &mut HashMap<String, &'s Schema>) {
+    match *schema {
+        Schema::Record {
+            ref name,
+            ref fields,
+            ..
+        } => {
+            definitions.insert(name.fullname(None), schema);
+            for field in fields {
+                collect_named(&field.schema, definitions);
+            }
+        }
+        Schema::Enum { ref name, .. } | Schema::Fixed { ref name, .. } => {
+            definitions.insert(name.fullname(None), schema);
+        }
+        Schema::Array(ref inner) | Schema::Map(ref inner) => collect_named(inner, definitions),
+        Schema::Decimal { ref inner, .. } => collect_named(inner, definitions),
+        Schema::Annotated { ref inner, .. } => collect_named(inner, definitions),
+        Schema::Union(ref union) => {
+            for variant in union.variants() {
+                collect_named(variant, definitions);
+            }
+        }
+        _ => {}
+    }
 }
 
 // No need to compare variant_index, it is derivative of schemas.
@@ -413,14 +643,35 @@ impl Schema {
     /// Create a `Schema` from a `serde_json::Value` representing a JSON Avro
     /// schema.
     pub fn parse(value: &Value) -> AvroResult<Self> {
-        match *value {
-            Value::String(ref t) => Schema::parse_primitive(t.as_str()),
-            Value::Object(ref data) => Schema::parse_complex(data),
-            Value::Array(ref data) => Schema::parse_union(data),
-            _ => Err(Error::Parse(
-                "Must be a JSON string, object or array".to_string(),
-            )),
+        Parser::default().parse(value, None)
+    }
+
+    /// Create a list of `Schema`s from strings representing JSON Avro schemas that may refer to
+    /// named types defined in one another, in any order.
+    ///
+    /// Each resulting `Schema` is self-contained: the definitions it borrows from the other
+    /// inputs are embedded where they are first used, and referred to by name afterwards, even
+    /// where another embedded definition encloses them. A reference to a name that is not defined
+    /// in any of the inputs, or a fullname that is defined more than once, is an error.
+    pub fn parse_list(input: &[&str]) -> AvroResult<Vec<Self>> {
+        let values = input
+            .iter()
+            .map(|input| serde_json::from_str(input))
+            .collect::<Result<Vec<Value>, _>>()?;
+
+        let mut parser = Parser::default();
+        for value in &values {
+            parser.collect_definitions(value, None)?;
         }
+
+        values
+            .iter()
+            .map(|value| {
+                // Names defined while parsing another input are not visible from this one.
+                parser.named_types.clear();
+                parser.parse(value, None)
+            })
+            .collect()
     }
 
     /// Converts `self` into its [Parsing Canonical Form].
@@ -446,19 +697,38 @@ impl Schema {
         }
     }
 
-    /// Parse a `serde_json::Value` representing a primitive Avro type into a
-    /// `Schema`.
-    fn parse_primitive(primitive: &str) -> AvroResult<Self> {
-        match primitive {
-            "null" => Ok(Schema::Null),
-            "boolean" => Ok(Schema::Boolean),
-            "int" => Ok(Schema::Int),
-            "long" => Ok(Schema::Long),
-            "double" => Ok(Schema::Double),
-            "float" => Ok(Schema::Float),
-            "bytes" => Ok(Schema::Bytes),
-            "string" => Ok(Schema::String),
-            other => Err(Error::Parse(format!("Unknown type: {}", other))),
+    /// Returns the attributes of a schema that are not defined by the Avro specification, such as
+    /// an unknown `logicalType`, or `None` if it has none.
+    ///
+    /// Named types (`record`, `enum` or `fixed`) always have a possibly empty set of attributes,
+    /// while the schemas of any other type only have some when they are `Schema::Annotated`.
+    pub fn custom_attributes(&self) -> Option<&CustomAttributes> {
+        match *self {
+            Schema::Record {
+                ref custom_attributes,
+                ..
+            }
+            | Schema::Enum {
+                ref custom_attributes,
+                ..
+            }
+            | Schema::Fixed {
+                ref custom_attributes,
+                ..
+            }
+            | Schema::Annotated {
+                ref custom_attributes,
+                ..
+            } => Some(custom_attributes),
+            _ => None,
+        }
+    }
+
+    /// Returns the schema annotated by `self` if it is `Schema::Annotated`, or `self` otherwise.
+    pub fn unannotated(&self) -> &Schema {
+        match *self {
+            Schema::Annotated { ref inner, .. } => inner.unannotated(),
+            _ => self,
         }
     }
 
@@ -480,23 +750,230 @@ impl Schema {
         let scale = get_decimal_integer(complex, "scale")?;
         Ok((precision, scale))
     }
+}
+
+/// Parser for JSON Avro schemas.
+///
+/// Keeps track of the fullnames of the named types defined so far, so that later references to
+/// them, including recursive ones, can be turned into `Schema::Ref`s.
+///
+/// When parsing several schemas at once, it also knows where each named type of the whole set is
+/// defined, so that a reference to a type that has not been defined yet can be parsed in place.
+#[derive(Default)]
+struct Parser<'a> {
+    named_types: HashSet<String>,
+    definitions: HashMap<String, (&'a Value, Option<String>)>,
+}
+
+impl<'a> Parser<'a> {
+    /// Record the JSON definition, along with its enclosing namespace, of every named type found
+    /// in `value`.
+    fn collect_definitions(
+        &mut self,
+        value: &'a Value,
+        enclosing_namespace: Option<&str>,
+    ) -> AvroResult<()> {
+        match *value {
+            Value::Array(ref variants) => variants
+                .iter()
+                .try_for_each(|variant| self.collect_definitions(variant, enclosing_namespace)),
+            Value::Object(ref complex) => match complex.get("type") {
+                Some(Value::String(t)) if t == "record" || t == "enum" || t == "fixed" => {
+                    let name = Name::parse(complex, enclosing_namespace)?;
+                    let fullname = name.fullname(None);
+                    if self.definitions.contains_key(&fullname) {
+                        return Err(Error::Parse(format!(
+                            "Duplicate definition of named type: {}",
+                            fullname
+                        )));
+                    }
+                    self.definitions.insert(
+                        fullname,
+                        (value, enclosing_namespace.map(|ns| ns.to_string())),
+                    );
+
+                    if let Some(fields) = complex.get("fields").and_then(|f| f.as_array()) {
+                        for field in fields {
+                            if let Some(ty) = field.get("type") {
+                                self.collect_definitions(ty, name.effective_namespace())?;
+                            }
+                        }
+                    }
+                    Ok(())
+                }
+                Some(Value::String(t)) if t == "array" || t == "map" => {
+                    match complex.get("items").or_else(|| complex.get("values")) {
+                        Some(inner) => self.collect_definitions(inner, enclosing_namespace),
+                        None => Ok(()),
+                    }
+                }
+                Some(inner) => self.collect_definitions(inner, enclosing_namespace),
+                None => Ok(()),
+            },
+            _ => Ok(()),
+        }
+    }
+
+    /// Parse a `serde_json::Value` representing any Avro type into a `Schema`.
+    fn parse(&mut self, value: &'a Value, enclosing_namespace: Option<&str>) -> AvroResult<Schema> {
+        match *value {
+            Value::String(ref t) => self.parse_known_schema(t.as_str(), enclosing_namespace),
+            Value::Object(ref data) => self.parse_complex(data, enclosing_namespace),
+            Value::Array(ref data) => self.parse_union(data, enclosing_namespace),
+            _ => Err(Error::Parse(
+                "Must be a JSON string, object or array".to_string(),
+            )),
+        }
+    }
+
+    /// Parse a string representing either a primitive Avro type or a reference to a named type
+    /// defined earlier into a `Schema`.
+    fn parse_known_schema(
+        &mut self,
+        name: &str,
+        enclosing_namespace: Option<&str>,
+    ) -> AvroResult<Schema> {
+        match name {
+            "null" => Ok(Schema::Null),
+            "boolean" => Ok(Schema::Boolean),
+            "int" => Ok(Schema::Int),
+            "long" => Ok(Schema::Long),
+            "double" => Ok(Schema::Double),
+            "float" => Ok(Schema::Float),
+            "bytes" => Ok(Schema::Bytes),
+            "string" => Ok(Schema::String),
+            other => self.parse_reference(other, enclosing_namespace),
+        }
+    }
+
+    /// Resolve a reference to a named type into a `Schema::Ref`, or into the full definition of
+    /// the type if it is defined in another schema of the set being parsed.
+    ///
+    /// An unqualified `name` is first looked up in the enclosing namespace, then in the null
+    /// namespace.
+    fn parse_reference(
+        &mut self,
+        name: &str,
+        enclosing_namespace: Option<&str>,
+    ) -> AvroResult<Schema> {
+        let fullname = Name::new(name).fullname(enclosing_namespace);
+        let candidates = [fullname.as_str(), name];
+
+        if let Some(&fullname) = candidates.iter().find(|&&n| self.named_types.contains(n)) {
+            return Ok(Schema::Ref {
+                name: Name::new(fullname),
+            });
+        }
+
+        match candidates.iter().find_map(|&n| self.definitions.get(n)) {
+            Some(&(definition, ref namespace)) => {
+                let namespace = namespace.clone();
+                self.parse(definition, namespace.as_deref())
+            }
+            None => Err(Error::Parse(format!("Unknown type: {}", name))),
+        }
+    }
+
+    /// Turn the definition of a named type into a `Schema::Ref` if it has already been
+    /// embedded into the schema being parsed, because the type was used there before the
+    /// definition that encloses it.
+    ///
+    /// This only happens when parsing several schemas at once, whose definitions were all checked
+    /// to be unique beforehand.
+    fn embedded_reference(
+        &self,
+        complex: &Map<String, Value>,
+        enclosing_namespace: Option<&str>,
+    ) -> AvroResult<Option<Schema>> {
+        match complex.get("type") {
+            Some(Value::String(t)) if t == "record" || t == "enum" || t == "fixed" => {}
+            _ => return Ok(None),
+        }
+        let fullname = Name::parse(complex, enclosing_namespace)?.fullname(None);
+        if self.definitions.contains_key(&fullname) && self.named_types.contains(&fullname) {
+            Ok(Some(Schema::Ref {
+                name: Name::new(&fullname),
+            }))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Register the fullname of a named type, making it available to later references.
+    ///
+    /// Fails if a type of the same fullname has already been defined.
+    fn register_name(&mut self, name: &Name) -> AvroResult<()> {
+        let fullname = name.fullname(None);
+        if self.named_types.contains(&fullname) {
+            return Err(Error::Parse(format!(
+                "Duplicate definition of named type: {}",
+                fullname
+            )));
+        }
+        self.named_types.insert(fullname);
+        Ok(())
+    }
 
     /// Parse a `serde_json::Value` representing a complex Avro type into a
     /// `Schema`.
     ///
     /// Avro supports "recursive" definition of types.
     /// e.g: {"type": {"type": "string"}}
-    fn parse_complex(complex: &Map<String, Value>) -> AvroResult<Self> {
-        fn logical_verify_type(
-            complex: &Map<String, Value>,
+    fn parse_complex(
+        &mut self,
+        complex: &'a Map<String, Value>,
+        enclosing_namespace: Option<&str>,
+    ) -> AvroResult<Schema> {
+        if let Some(reference) = self.embedded_reference(complex, enclosing_namespace)? {
+            return Ok(reference);
+        }
+        let schema = self.parse_complex_type(complex, enclosing_namespace)?;
+        // Named types keep their own custom attributes.
+        let known: &[&str] = match schema {
+            Schema::Record { .. } | Schema::Enum { .. } | Schema::Fixed { .. } => {
+                return Ok(schema)
+            }
+            Schema::Array(_) => &["type", "items"],
+            Schema::Map(_) => &["type", "values"],
+            Schema::Decimal { .. } => &["type", "logicalType", "precision", "scale"],
+            Schema::Uuid
+            | Schema::Date
+            | Schema::TimeMillis
+            | Schema::TimeMicros
+            | Schema::TimestampMillis
+            | Schema::TimestampMicros
+            | Schema::Duration => &["type", "logicalType"],
+            _ => &["type"],
+        };
+        let custom_attributes = parse_custom_attributes(complex, known);
+        if custom_attributes.is_empty() {
+            Ok(schema)
+        } else {
+            Ok(Schema::Annotated {
+                inner: Box::new(schema),
+                custom_attributes,
+            })
+        }
+    }
+
+    /// Parse the type of a complex Avro type, leaving out its custom attributes.
+    fn parse_complex_type(
+        &mut self,
+        complex: &'a Map<String, Value>,
+        enclosing_namespace: Option<&str>,
+    ) -> AvroResult<Schema> {
+        fn logical_verify_type<'a>(
+            parser: &mut Parser<'a>,
+            complex: &'a Map<String, Value>,
             kinds: &[SchemaKind],
+            enclosing_namespace: Option<&str>,
         ) -> AvroResult<Schema> {
             match complex.get("type") {
                 Some(value) => {
-                    let ty = Schema::parse(value)?;
+                    let ty = parser.parse(value, enclosing_namespace)?;
                     if kinds
                         .iter()
-                        .any(|&kind| SchemaKind::from(ty.clone()) == kind)
+                        .any(|&kind| SchemaKind::from(ty.unannotated()) == kind)
                     {
                         Ok(ty)
                     } else {
@@ -515,11 +992,13 @@ impl Schema {
             Some(&Value::String(ref t)) => match t.as_str() {
                 "decimal" => {
                     let inner = Box::new(logical_verify_type(
+                        self,
                         complex,
                         &[SchemaKind::Fixed, SchemaKind::Bytes],
+                        enclosing_namespace,
                     )?);
 
-                    let (precision, scale) = Self::parse_precision_and_scale(complex)?;
+                    let (precision, scale) = Schema::parse_precision_and_scale(complex)?;
 
                     return Ok(Schema::Decimal {
                         precision,
@@ -528,35 +1007,35 @@ impl Schema {
                     });
                 }
                 "uuid" => {
-                    logical_verify_type(complex, &[SchemaKind::String])?;
+                    logical_verify_type(self, complex, &[SchemaKind::String], enclosing_namespace)?;
                     return Ok(Schema::Uuid);
                 }
                 "date" => {
-                    logical_verify_type(complex, &[SchemaKind::Int])?;
+                    logical_verify_type(self, complex, &[SchemaKind::Int], enclosing_namespace)?;
                     return Ok(Schema::Date);
                 }
                 "time-millis" => {
-                    logical_verify_type(complex, &[SchemaKind::Int])?;
+                    logical_verify_type(self, complex, &[SchemaKind::Int], enclosing_namespace)?;
                     return Ok(Schema::TimeMillis);
                 }
                 "time-micros" => {
-                    logical_verify_type(complex, &[SchemaKind::Long])?;
+                    logical_verify_type(self, complex, &[SchemaKind::Long], enclosing_namespace)?;
                     return Ok(Schema::TimeMicros);
                 }
                 "timestamp-millis" => {
-                    logical_verify_type(complex, &[SchemaKind::Long])?;
+                    logical_verify_type(self, complex, &[SchemaKind::Long], enclosing_namespace)?;
                     return Ok(Schema::TimestampMillis);
                 }
                 "timestamp-micros" => {
-                    logical_verify_type(complex, &[SchemaKind::Long])?;
+                    logical_verify_type(self, complex, &[SchemaKind::Long], enclosing_namespace)?;
                     return Ok(Schema::TimestampMicros);
                 }
                 "duration" => {
-                    logical_verify_type(complex, &[SchemaKind::Fixed])?;
+                    logical_verify_type(self, complex, &[SchemaKind::Fixed], enclosing_namespace)?;
                     return Ok(Schema::Duration);
                 }
                 // In this case, of an unknown logical type, we just pass through to the underlying
-                // type.
+                // type, which keeps the `logicalType` among its custom attributes.
                 _ => {}
             },
             // The spec says to ignore invalid logical types and just continue through to the
@@ -567,15 +1046,15 @@ impl Schema {
         }
         match complex.get("type") {
             Some(&Value::String(ref t)) => match t.as_str() {
-                "record" => Schema::parse_record(complex),
-                "enum" => Schema::parse_enum(complex),
-                "array" => Schema::parse_array(complex),
-                "map" => Schema::parse_map(complex),
-                "fixed" => Schema::parse_fixed(complex),
-                other => Schema::parse_primitive(other),
+                "record" => self.parse_record(complex, enclosing_namespace),
+                "enum" => self.parse_enum(complex, enclosing_namespace),
+                "array" => self.parse_array(complex, enclosing_namespace),
+                "map" => self.parse_map(complex, enclosing_namespace),
+                "fixed" => self.parse_fixed(complex, enclosing_namespace),
+                other => self.parse_known_schema(other, enclosing_namespace),
             },
-            Some(&Value::Object(ref data)) => Schema::parse_complex(data),
-            Some(&Value::Array(ref variants)) => Schema::parse_union(variants),
+            Some(&Value::Object(ref data)) => self.parse_complex(data, enclosing_namespace),
+            Some(&Value::Array(ref variants)) => self.parse_union(variants, enclosing_namespace),
             Some(unknown) => Err(Error::Parse(format!(
                 "Unknown complex type: {0:?}",
                 unknown
@@ -586,8 +1065,14 @@ impl Schema {
 
     /// Parse a `serde_json::Value` representing a Avro record type into a
     /// `Schema`.
-    fn parse_record(complex: &Map<String, Value>) -> AvroResult<Self> {
-        let name = Name::parse(complex)?;
+    fn parse_record(
+        &mut self,
+        complex: &'a Map<String, Value>,
+        enclosing_namespace: Option<&str>,
+    ) -> AvroResult<Schema> {
+        let name = Name::parse(complex, enclosing_namespace)?;
+        // The name is registered before parsing the fields, so that they can refer to it.
+        self.register_name(&name)?;
 
         let mut lookup = HashMap::new();
 
@@ -600,7 +1085,9 @@ impl Schema {
                     .iter()
                     .filter_map(|field| field.as_object())
                     .enumerate()
-                    .map(|(position, field)| RecordField::parse(field, position))
+                    .map(|(position, field)| {
+                        RecordField::parse(field, position, self, name.effective_namespace())
+                    })
                     .collect::<Result<_, _>>()
             })?;
 
@@ -613,15 +1100,20 @@ impl Schema {
             doc: complex.doc(),
             fields,
             lookup,
+            custom_attributes: parse_custom_attributes(complex, RECORD_ATTRIBUTES),
         })
     }
 
     /// Parse a `serde_json::Value` representing a Avro enum type into a
     /// `Schema`.
-    fn parse_enum(complex: &Map<String, Value>) -> AvroResult<Self> {
-        let name = Name::parse(complex)?;
-
-        let symbols = complex
+    fn parse_enum(
+        &mut self,
+        complex: &'a Map<String, Value>,
+        enclosing_namespace: Option<&str>,
+    ) -> AvroResult<Schema> {
+        let name = Name::parse(complex, enclosing_namespace)?;
+
+        let symbols: Vec<String> = complex
             .get("symbols")
             .and_then(|v| v.as_array())
             .ok_or_else(|| Error::Parse("No `symbols` field in enum".to_string()))
@@ -633,56 +1125,89 @@ impl Schema {
                     .ok_or_else(|| Error::Parse("Unable to parse `symbols` in enum".to_string()))
             })?;
 
+        let default = complex.string("default");
+        if let Some(ref default) = default {
+            if !symbols.contains(default) {
+                return Err(Error::Parse(format!(
+                    "Enum default {} is not among allowed symbols {:?}",
+                    default, symbols
+                )));
+            }
+        }
+
+        self.register_name(&name)?;
+
         Ok(Schema::Enum {
             name,
             doc: complex.doc(),
             symbols,
+            default,
+            custom_attributes: parse_custom_attributes(complex, ENUM_ATTRIBUTES),
         })
     }
 
     /// Parse a `serde_json::Value` representing a Avro array type into a
     /// `Schema`.
-    fn parse_array(complex: &Map<String, Value>) -> AvroResult<Self> {
+    fn parse_array(
+        &mut self,
+        complex: &'a Map<String, Value>,
+        enclosing_namespace: Option<&str>,
+    ) -> AvroResult<Schema> {
         complex
             .get("items")
             .ok_or_else(|| Error::Parse("No `items` in array".to_string()))
-            .and_then(|items| Schema::parse(items))
+            .and_then(|items| self.parse(items, enclosing_namespace))
             .map(|schema| Schema::Array(Box::new(schema)))
     }
 
     /// Parse a `serde_json::Value` representing a Avro map type into a
     /// `Schema`.
-    fn parse_map(complex: &Map<String, Value>) -> AvroResult<Self> {
+    fn parse_map(
+        &mut self,
+        complex: &'a Map<String, Value>,
+        enclosing_namespace: Option<&str>,
+    ) -> AvroResult<Schema> {
         complex
             .get("values")
             .ok_or_else(|| Error::Parse("No `values` in map".to_string()))
-            .and_then(|items| Schema::parse(items))
+            .and_then(|items| self.parse(items, enclosing_namespace))
             .map(|schema| Schema::Map(Box::new(schema)))
     }
 
     /// Parse a `serde_json::Value` representing a Avro union type into a
     /// `Schema`.
-    fn parse_union(items: &[Value]) -> AvroResult<Self> {
+    fn parse_union(
+        &mut self,
+        items: &'a [Value],
+        enclosing_namespace: Option<&str>,
+    ) -> AvroResult<Schema> {
         items
             .iter()
-            .map(Schema::parse)
+            .map(|item| self.parse(item, enclosing_namespace))
             .collect::<Result<Vec<_>, _>>()
             .and_then(|schemas| Ok(Schema::Union(UnionSchema::new(schemas)?)))
     }
 
     /// Parse a `serde_json::Value` representing a Avro fixed type into a
     /// `Schema`.
-    fn parse_fixed(complex: &Map<String, Value>) -> AvroResult<Self> {
-        let name = Name::parse(complex)?;
+    fn parse_fixed(
+        &mut self,
+        complex: &'a Map<String, Value>,
+        enclosing_namespace: Option<&str>,
+    ) -> AvroResult<Schema> {
+        let name = Name::parse(complex, enclosing_namespace)?;
 
         let size = complex
             .get("size")
             .and_then(|v| v.as_i64())
             .ok_or_else(|| Error::Parse("No `size` in fixed".to_string()))?;
 
+        self.register_name(&name)?;
+
         Ok(Schema::Fixed {
             name,
             size: size as usize,
+            custom_attributes: parse_custom_attributes(complex, FIXED_ATTRIBUTES),
         })
     }
 }
@@ -725,6 +1250,7 @@ impl Serialize for Schema {
                 ref name,
                 ref doc,
                 ref fields,
+                ref custom_attributes,
                 ..
             } => {
                 let mut map = serializer.serialize_map(None)?;
@@ -740,24 +1266,57 @@ impl Serialize for Schema {
                     map.serialize_entry("aliases", aliases)?;
                 }
                 map.serialize_entry("fields", fields)?;
+                for (key, value) in custom_attributes {
+                    map.serialize_entry(key, value)?;
+                }
                 map.end()
             }
             Schema::Enum {
                 ref name,
+                ref doc,
                 ref symbols,
-                ..
+                ref default,
+                ref custom_attributes,
             } => {
                 let mut map = serializer.serialize_map(None)?;
                 map.serialize_entry("type", "enum")?;
+                if let Some(ref n) = name.namespace {
+                    map.serialize_entry("namespace", n)?;
+                }
                 map.serialize_entry("name", &name.name)?;
+                if let Some(ref docstr) = doc {
+                    map.serialize_entry("doc", docstr)?;
+                }
+                if let Some(ref aliases) = name.aliases {
+                    map.serialize_entry("aliases", aliases)?;
+                }
                 map.serialize_entry("symbols", symbols)?;
+                if let Some(ref default) = default {
+                    map.serialize_entry("default", default)?;
+                }
+                for (key, value) in custom_attributes {
+                    map.serialize_entry(key, value)?;
+                }
                 map.end()
             }
-            Schema::Fixed { ref name, ref size } => {
+            Schema::Fixed {
+                ref name,
+                ref size,
+                ref custom_attributes,
+            } => {
                 let mut map = serializer.serialize_map(None)?;
                 map.serialize_entry("type", "fixed")?;
+                if let Some(ref n) = name.namespace {
+                    map.serialize_entry("namespace", n)?;
+                }
                 map.serialize_entry("name", &name.name)?;
+                if let Some(ref aliases) = name.aliases {
+                    map.serialize_entry("aliases", aliases)?;
+                }
                 map.serialize_entry("size", size)?;
+                for (key, value) in custom_attributes {
+                    map.serialize_entry(key, value)?;
+                }
                 map.end()
             }
             Schema::Decimal {
@@ -816,11 +1375,32 @@ impl Serialize for Schema {
                 let inner = Schema::Fixed {
                     name: Name::new("duration"),
                     size: 12,
+                    custom_attributes: CustomAttributes::new(),
                 };
                 map.serialize_entry("type", &inner)?;
                 map.serialize_entry("logicalType", "duration")?;
                 map.end()
             }
+            Schema::Ref { ref name } => serializer.serialize_str(&name.fullname(None)),
+            Schema::Annotated {
+                ref inner,
+                ref custom_attributes,
+            } => {
+                let mut map = serializer.serialize_map(None)?;
+                // The attributes are added to the object form of the annotated schema.
+                match serde_json::to_value(inner).map_err(ser::Error::custom)? {
+                    Value::Object(object) => {
+                        for (key, value) in object {
+                            map.serialize_entry(&key, &value)?;
+                        }
+                    }
+                    inner => map.serialize_entry("type", &inner)?,
+                }
+                for (key, value) in custom_attributes {
+                    map.serialize_entry(key, value)?;
+                }
+                map.end()
+            }
         }
     }
 }
@@ -832,12 +1412,23 @@ impl Serialize for RecordField {
     {
         let mut map = serializer.serialize_map(None)?;
         map.serialize_entry("name", &self.name)?;
+        if let Some(ref doc) = self.doc {
+            map.serialize_entry("doc", doc)?;
+        }
         map.serialize_entry("type", &self.schema)?;
 
         if let Some(ref default) = self.default {
             map.serialize_entry("default", default)?;
         }
 
+        if let Some(ref aliases) = self.aliases {
+            map.serialize_entry("aliases", aliases)?;
+        }
+
+        for (key, value) in &self.custom_attributes {
+            map.serialize_entry(key, value)?;
+        }
+
         map.end()
     }
 }
@@ -857,9 +1448,15 @@ fn pcf_map(schema: &Map<String, serde_json::Value>) -> String {
     // Look for the namespace variant up front.
     let ns = schema.get("namespace").and_then(|v| v.as_str());
     let mut fields = Vec::new();
+    // Attributes stripped out below do not keep a primitive type from being reduced to its simple
+    // form, unlike the logical types this crate knows of, which keep their object form.
+    let primitive = schema
+        .keys()
+        .all(|k| k == "type" || field_ordering_position(k).is_none())
+        && !matches!(schema.get("logicalType"), Some(Value::String(t)) if LOGICAL_TYPES.contains(&t.as_str()));
     for (k, v) in schema {
         // Reduce primitive types to their simple form. ([PRIMITIVE] rule)
-        if schema.len() == 1 && k == "type" {
+        if primitive && k == "type" {
             // Invariant: function is only callable from a valid schema, so this is acceptable.
             if let serde_json::Value::String(s) = v {
                 return pcf_string(s);
@@ -942,6 +1539,102 @@ fn field_ordering_position(field: &str) -> Option<usize> {
     Some(v)
 }
 
+/// Types with a corresponding Avro `Schema`, which can be derived for structs and enums with
+/// `#[derive(AvroSchema)]` when the `derive` feature is enabled.
+pub trait AvroSchema {
+    /// Get the `Schema` of values of this type.
+    fn get_schema() -> Schema;
+}
+
+/// Types which can be part of a `Schema` implementing `AvroSchema`.
+///
+/// `named` holds the fullnames of the named types already defined in the `Schema` being built:
+/// a named type found again is only a `Schema::Ref` to its first definition.
+pub trait AvroSchemaComponent {
+    /// Get the `Schema` of values of this type, within a `Schema` defining the named types of
+    /// `named`.
+    fn get_schema_in_ctxt(named: &mut HashSet<String>) -> Schema;
+}
+
+impl<T: AvroSchemaComponent + ?Sized> AvroSchema for T {
+    fn get_schema() -> Schema {
+        T::get_schema_in_ctxt(&mut HashSet::new())
+    }
+}
+
+macro_rules! impl_schema_component(
+    ($schema:expr, $($t:ty),+) => (
+        $(impl AvroSchemaComponent for $t {
+            fn get_schema_in_ctxt(_: &mut HashSet<String>) -> Schema {
+                $schema
+            }
+        })+
+    );
+);
+
+impl_schema_component!(Schema::Null, ());
+impl_schema_component!(Schema::Boolean, bool);
+impl_schema_component!(Schema::Int, i8, i16, i32, u8, u16);
+impl_schema_component!(Schema::Long, i64, u32, u64);
+impl_schema_component!(Schema::Float, f32);
+impl_schema_component!(Schema::Double, f64);
+impl_schema_component!(Schema::String, char, str, String);
+impl_schema_component!(Schema::Uuid, uuid::Uuid);
+
+impl<T: AvroSchemaComponent + ?Sized> AvroSchemaComponent for &T {
+    fn get_schema_in_ctxt(named: &mut HashSet<String>) -> Schema {
+        T::get_schema_in_ctxt(named)
+    }
+}
+
+impl<T: AvroSchemaComponent + ?Sized> AvroSchemaComponent for Box<T> {
+    fn get_schema_in_ctxt(named: &mut HashSet<String>) -> Schema {
+        T::get_schema_in_ctxt(named)
+    }
+}
+
+impl<T: AvroSchemaComponent> AvroSchemaComponent for Vec<T> {
+    fn get_schema_in_ctxt(named: &mut HashSet<String>) -> Schema {
+        Schema::Array(Box::new(T::get_schema_in_ctxt(named)))
+    }
+}
+
+impl<T: AvroSchemaComponent> AvroSchemaComponent for [T] {
+    fn get_schema_in_ctxt(named: &mut HashSet<String>) -> Schema {
+        Schema::Array(Box::new(T::get_schema_in_ctxt(named)))
+    }
+}
+
+impl<T: AvroSchemaComponent> AvroSchemaComponent for HashMap<String, T> {
+    fn get_schema_in_ctxt(named: &mut HashSet<String>) -> Schema {
+        Schema::Map(Box::new(T::get_schema_in_ctxt(named)))
+    }
+}
+
+impl<T: AvroSchemaComponent> AvroSchemaComponent for BTreeMap<String, T> {
+    fn get_schema_in_ctxt(named: &mut HashSet<String>) -> Schema {
+        Schema::Map(Box::new(T::get_schema_in_ctxt(named)))
+    }
+}
+
+impl<T: AvroSchemaComponent> AvroSchemaComponent for Option<T> {
+    /// `Option<T>` is a union of `null` and of the `Schema` of `T`.
+    fn get_schema_in_ctxt(named: &mut HashSet<String>) -> Schema {
+        let inner = T::get_schema_in_ctxt(named);
+        Schema::Union(
+            UnionSchema::new(vec![Schema::Null, inner])
+                .expect("Option<T> cannot be derived when T is a union or a null"),
+        )
+    }
+}
+
+/// Turn the default value of a field into JSON, for the `Schema` derived by
+/// `#[derive(AvroSchema)]`.
+#[doc(hidden)]
+pub fn derive_default<T: Serialize>(value: T) -> Value {
+    serde_json::to_value(value).expect("the default value of a field must serialize into JSON")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1042,25 +1735,354 @@ mod tests {
                     name: "a".to_string(),
                     doc: None,
                     default: Some(Value::Number(42i64.into())),
+                    aliases: None,
                     schema: Schema::Long,
                     order: RecordFieldOrder::Ascending,
                     position: 0,
+                    custom_attributes: Default::default(),
                 },
                 RecordField {
                     name: "b".to_string(),
                     doc: None,
                     default: None,
+                    aliases: None,
                     schema: Schema::String,
                     order: RecordFieldOrder::Ascending,
                     position: 1,
+                    custom_attributes: Default::default(),
                 },
             ],
             lookup,
+            custom_attributes: Default::default(),
         };
 
         assert_eq!(expected, schema);
     }
 
+    #[test]
+    fn test_recursive_record_schema() {
+        let schema = Schema::parse_str(
+            r#"
+            {
+                "type": "record",
+                "name": "LongList",
+                "namespace": "com.acme",
+                "fields": [
+                    {"name": "value", "type": "long"},
+                    {"name": "next", "type": ["null", "LongList"]}
+                ]
+            }
+        "#,
+        )
+        .unwrap();
+
+        let next = match schema {
+            Schema::Record { ref fields, .. } => &fields[1].schema,
+            _ => panic!("expected a record"),
+        };
+        assert_eq!(
+            next,
+            &Schema::Union(
+                UnionSchema::new(vec![
+                    Schema::Null,
+                    Schema::Ref {
+                        name: Name::new("com.acme.LongList")
+                    }
+                ])
+                .unwrap()
+            )
+        );
+        assert!(Root::new(&schema)
+            .lookup_named("com.acme.LongList")
+            .is_some());
+        assert_eq!(
+            schema.canonical_form(),
+            r#"{"name":"com.acme.LongList","type":"record","fields":[{"name":"value","type":"long"},{"name":"next","type":["null","com.acme.LongList"]}]}"#
+        );
+    }
+
+    #[test]
+    fn test_named_type_reference_inherits_namespace() {
+        let schema = Schema::parse_str(
+            r#"
+            {
+                "type": "record",
+                "name": "Customer",
+                "namespace": "com.acme",
+                "fields": [
+                    {
+                        "name": "home",
+                        "type": {
+                            "type": "record",
+                            "name": "Address",
+                            "fields": [{"name": "city", "type": "string"}]
+                        }
+                    },
+                    {"name": "work", "type": "com.acme.Address"},
+                    {"name": "billing", "type": "Address"}
+                ]
+            }
+        "#,
+        )
+        .unwrap();
+
+        let fields = match schema {
+            Schema::Record { fields, .. } => fields,
+            _ => panic!("expected a record"),
+        };
+        match fields[0].schema {
+            Schema::Record { ref name, .. } => {
+                assert_eq!(name.fullname(None), "com.acme.Address")
+            }
+            _ => panic!("expected a record"),
+        }
+        for field in &fields[1..] {
+            match field.schema {
+                Schema::Ref { ref name } => assert_eq!(name.fullname(None), "com.acme.Address"),
+                _ => panic!("expected a reference"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_custom_attributes() {
+        let raw_schema = r#"
+            {
+                "type": "record",
+                "name": "Customer",
+                "connect.name": "com.acme.Customer",
+                "fields": [
+                    {"name": "email", "type": "string", "pii": true},
+                    {"name": "since", "type": "int", "logicalType": "date"},
+                    {
+                        "name": "id",
+                        "type": {
+                            "type": "fixed",
+                            "name": "id",
+                            "size": 16,
+                            "logicalType": "custom-id"
+                        }
+                    },
+                    {"name": "tags", "type": "array", "items": "string"}
+                ]
+            }
+        "#;
+        let schema = Schema::parse_str(raw_schema).unwrap();
+
+        let attributes = schema.custom_attributes().unwrap();
+        assert_eq!(attributes.len(), 1);
+        assert_eq!(attributes["connect.name"], "com.acme.Customer");
+
+        let fields = match schema {
+            Schema::Record { ref fields, .. } => fields,
+            _ => panic!("expected a record"),
+        };
+        assert_eq!(fields[0].custom_attributes["pii"], true);
+        // A known logical type is not a custom attribute.
+        assert_eq!(fields[1].schema, Schema::Date);
+        assert!(fields[1].custom_attributes.is_empty());
+        assert!(fields[2].custom_attributes.is_empty());
+        assert_eq!(
+            fields[2].schema.custom_attributes().unwrap()["logicalType"],
+            "custom-id"
+        );
+        assert!(fields[3].custom_attributes.is_empty());
+
+        // Custom attributes survive a round-trip through JSON.
+        let json = serde_json::to_value(&schema).unwrap();
+        assert_eq!(json["connect.name"], "com.acme.Customer");
+        assert_eq!(json["fields"][0]["pii"], true);
+        assert_eq!(json["fields"][2]["type"]["logicalType"], "custom-id");
+        let reparsed = Schema::parse(&json).unwrap();
+        assert_eq!(reparsed.custom_attributes(), schema.custom_attributes());
+    }
+
+    #[test]
+    fn test_annotated_schemas() {
+        let raw_schema = r#"
+            {
+                "type": "record",
+                "name": "Event",
+                "fields": [
+                    {"name": "at", "type": {"type": "string", "logicalType": "iso-datetime"}},
+                    {"name": "ids", "type": {"type": "array", "items": "long", "max": 3}},
+                    {"name": "delays", "type": {"type": "map", "values": {"type": "int", "unit": "ms"}}}
+                ]
+            }
+        "#;
+        let schema = Schema::parse_str(raw_schema).unwrap();
+        let fields = match schema {
+            Schema::Record { ref fields, .. } => fields,
+            _ => panic!("expected a record"),
+        };
+        assert_eq!(
+            fields[0].schema.custom_attributes().unwrap()["logicalType"],
+            "iso-datetime"
+        );
+        assert_eq!(fields[0].schema.unannotated(), &Schema::String);
+        assert_eq!(fields[1].schema.custom_attributes().unwrap()["max"], 3);
+        assert_eq!(
+            fields[1].schema.unannotated(),
+            &Schema::Array(Box::new(Schema::Long))
+        );
+        let values = match fields[2].schema {
+            Schema::Map(ref values) => values,
+            _ => panic!("expected a map"),
+        };
+        assert_eq!(values.custom_attributes().unwrap()["unit"], "ms");
+
+        // The attributes are left out of the canonical form, like the ones of named types.
+        assert_eq!(
+            schema.canonical_form(),
+            r#"{"name":"Event","type":"record","fields":[{"name":"at","type":"string"},{"name":"ids","type":{"type":"array","items":"long"}},{"name":"delays","type":{"type":"map","values":"int"}}]}"#
+        );
+
+        let json = serde_json::to_value(&schema).unwrap();
+        assert_eq!(json["fields"][0]["type"]["logicalType"], "iso-datetime");
+        assert_eq!(json["fields"][1]["type"]["max"], 3);
+        assert_eq!(json["fields"][2]["type"]["values"]["unit"], "ms");
+        let reparsed = Schema::parse(&json).unwrap();
+        assert_eq!(serde_json::to_value(&reparsed).unwrap(), json);
+
+        let mut record = types::Record::new(&schema).unwrap();
+        record.put("at", "2020-06-01T00:00:00Z");
+        record.put(
+            "ids",
+            types::Value::Array(vec![types::Value::Long(1), types::Value::Long(2)]),
+        );
+        record.put("delays", HashMap::<String, types::Value>::new());
+        let value = types::Value::from(record);
+        assert!(value.validate(&schema));
+        let datum = crate::to_avro_datum(&schema, value.clone()).unwrap();
+        assert_eq!(
+            crate::from_avro_datum(&schema, &mut &datum[..], None).unwrap(),
+            value
+        );
+    }
+
+    #[test]
+    fn test_named_type_in_annotated_schema() {
+        let schema = Schema::parse_str(
+            r#"
+            {
+                "type": "record",
+                "name": "Holder",
+                "fields": [
+                    {
+                        "name": "xs",
+                        "type": {
+                            "type": "array",
+                            "items": {"type": "record", "name": "X", "fields": []},
+                            "foo": 1
+                        }
+                    },
+                    {"name": "x", "type": "X"}
+                ]
+            }
+        "#,
+        )
+        .unwrap();
+
+        let mut record = types::Record::new(&schema).unwrap();
+        record.put(
+            "xs",
+            types::Value::Array(vec![types::Value::Record(vec![])]),
+        );
+        record.put("x", types::Value::Record(vec![]));
+        let value = types::Value::from(record);
+        assert!(value.validate(&schema));
+        let datum = crate::to_avro_datum(&schema, value.clone()).unwrap();
+        assert_eq!(
+            crate::from_avro_datum(&schema, &mut &datum[..], None).unwrap(),
+            value
+        );
+    }
+
+    #[test]
+    fn test_parse_list() {
+        let customer = r#"
+            {
+                "type": "record",
+                "name": "Customer",
+                "namespace": "com.acme",
+                "fields": [
+                    {"name": "home", "type": "Address"},
+                    {"name": "work", "type": ["null", "com.acme.Address"]}
+                ]
+            }
+        "#;
+        let address = r#"
+            {
+                "type": "record",
+                "name": "Address",
+                "namespace": "com.acme",
+                "fields": [{"name": "city", "type": "string"}]
+            }
+        "#;
+
+        let schemas = Schema::parse_list(&[customer, address]).unwrap();
+        assert_eq!(schemas.len(), 2);
+        assert_eq!(schemas[1], Schema::parse_str(address).unwrap());
+
+        let fields = match schemas[0] {
+            Schema::Record { ref fields, .. } => fields,
+            _ => panic!("expected a record"),
+        };
+        // The first use embeds the definition, the following ones refer to it.
+        assert_eq!(fields[0].schema, schemas[1]);
+        match fields[1].schema {
+            Schema::Union(ref union) => match union.variants()[1] {
+                Schema::Ref { ref name } => assert_eq!(name.fullname(None), "com.acme.Address"),
+                _ => panic!("expected a reference"),
+            },
+            _ => panic!("expected a union"),
+        }
+    }
+
+    #[test]
+    fn test_parse_list_nested_type_used_first() {
+        let a = r#"{"type": "record", "name": "A", "fields": [
+            {"name": "y", "type": "C"},
+            {"name": "x", "type": "B"}
+        ]}"#;
+        let b = r#"{"type": "record", "name": "B", "fields": [
+            {"name": "c", "type": {"type": "record", "name": "C", "fields": []}}
+        ]}"#;
+
+        let schemas = Schema::parse_list(&[a, b]).unwrap();
+        assert_eq!(
+            schemas[0].canonical_form(),
+            r#"{"name":"A","type":"record","fields":[{"name":"y","type":{"name":"C","type":"record","fields":[]}},{"name":"x","type":{"name":"B","type":"record","fields":[{"name":"c","type":"C"}]}}]}"#
+        );
+        assert_eq!(schemas[1], Schema::parse_str(b).unwrap());
+
+        let mut record = types::Record::new(&schemas[0]).unwrap();
+        record.put("y", types::Value::Record(vec![]));
+        record.put(
+            "x",
+            types::Value::Record(vec![("c".to_string(), types::Value::Record(vec![]))]),
+        );
+        assert!(types::Value::from(record).validate(&schemas[0]));
+    }
+
+    #[test]
+    fn test_parse_list_unknown_type() {
+        let schemas = Schema::parse_list(&[
+            r#"{"type": "record", "name": "A", "fields": [{"name": "b", "type": "B"}]}"#,
+            r#"{"type": "record", "name": "C", "fields": []}"#,
+        ]);
+        assert!(schemas.is_err());
+    }
+
+    #[test]
+    fn test_parse_list_duplicate_type() {
+        let schemas = Schema::parse_list(&[
+            r#"{"type": "fixed", "name": "md5", "namespace": "com.acme", "size": 16}"#,
+            r#"{"type": "array", "items": {"type": "fixed", "name": "com.acme.md5", "size": 16}}"#,
+        ]);
+        assert!(schemas.is_err());
+    }
+
     #[test]
     fn test_enum_schema() {
         let schema = Schema::parse_str(
@@ -1076,11 +2098,60 @@ mod tests {
                 "clubs".to_owned(),
                 "hearts".to_owned(),
             ],
+            default: None,
+            custom_attributes: Default::default(),
         };
 
         assert_eq!(expected, schema);
     }
 
+    #[test]
+    fn test_enum_default() {
+        let schema = Schema::parse_str(
+            r#"{"type": "enum", "name": "Suit", "symbols": ["diamonds", "spades"], "default": "spades"}"#,
+        )
+        .unwrap();
+        match schema {
+            Schema::Enum { ref default, .. } => assert_eq!(default.as_deref(), Some("spades")),
+            _ => panic!("expected an enum"),
+        }
+        assert_eq!(
+            Schema::parse(&serde_json::to_value(&schema).unwrap()).unwrap(),
+            schema
+        );
+
+        let invalid = Schema::parse_str(
+            r#"{"type": "enum", "name": "Suit", "symbols": ["diamonds", "spades"], "default": "hearts"}"#,
+        );
+        assert!(invalid.is_err());
+    }
+
+    #[test]
+    fn test_field_aliases() {
+        let schema = Schema::parse_str(
+            r#"
+            {
+                "type": "record",
+                "name": "test",
+                "fields": [{"name": "a", "type": "long", "aliases": ["b", "c"]}]
+            }
+        "#,
+        )
+        .unwrap();
+        let fields = match schema {
+            Schema::Record { ref fields, .. } => fields,
+            _ => panic!("expected a record"),
+        };
+        assert_eq!(
+            fields[0].aliases,
+            Some(vec!["b".to_string(), "c".to_string()])
+        );
+        assert!(fields[0].custom_attributes.is_empty());
+
+        let json = serde_json::to_value(&schema).unwrap();
+        assert_eq!(json["fields"][0]["aliases"], serde_json::json!(["b", "c"]));
+    }
+
     #[test]
     fn test_fixed_schema() {
         let schema = Schema::parse_str(r#"{"type": "fixed", "name": "test", "size": 16}"#).unwrap();
@@ -1088,6 +2159,7 @@ mod tests {
         let expected = Schema::Fixed {
             name: Name::new("test"),
             size: 16usize,
+            custom_attributes: Default::default(),
         };
 
         assert_eq!(expected, schema);
@@ -1,156 +1,405 @@
 //! Logic for checking schema compatibility
 use std::collections::hash_map::DefaultHasher;
 use std::collections::HashSet;
+use std::fmt;
 use std::hash::Hasher;
 use std::ptr;
 
-use crate::schema::{Schema, SchemaKind};
+use strum_macros::{EnumString, IntoStaticStr};
+
+use crate::schema::{Name, Root, Schema, SchemaKind};
 
 pub struct SchemaCompatibility;
 
-struct Checker {
+/// The compatibility modes of schema registries, which tell how a new schema has to relate to the
+/// history of the schemas that preceded it.
+///
+/// A new schema is *backward* compatible with a previous one if it can read data written with
+/// it, and *forward* compatible if data written with it can be read with the previous one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, EnumString, IntoStaticStr)]
+#[strum(serialize_all = "SCREAMING_SNAKE_CASE")]
+pub enum CompatibilityMode {
+    /// The new schema must be backward compatible with the latest previous schema.
+    Backward,
+    /// The new schema must be backward compatible with all the previous schemas.
+    BackwardTransitive,
+    /// The new schema must be forward compatible with the latest previous schema.
+    Forward,
+    /// The new schema must be forward compatible with all the previous schemas.
+    ForwardTransitive,
+    /// The new schema must be both backward and forward compatible with the latest previous
+    /// schema.
+    Full,
+    /// The new schema must be both backward and forward compatible with all the previous
+    /// schemas.
+    FullTransitive,
+    /// Any new schema is accepted.
+    None,
+}
+
+impl CompatibilityMode {
+    /// Check that `new_schema` can follow `previous_schemas`, ordered from the oldest to the
+    /// latest, under this mode.
+    ///
+    /// Incompatibilities are reported in the order of `previous_schemas`. For each of them, those
+    /// of reading with `new_schema` come before those of reading with the previous schema.
+    pub fn check(
+        self,
+        new_schema: &Schema,
+        previous_schemas: &[Schema],
+    ) -> SchemaCompatibilityResult {
+        let (backward, forward, transitive) = match self {
+            CompatibilityMode::Backward => (true, false, false),
+            CompatibilityMode::BackwardTransitive => (true, false, true),
+            CompatibilityMode::Forward => (false, true, false),
+            CompatibilityMode::ForwardTransitive => (false, true, true),
+            CompatibilityMode::Full => (true, true, false),
+            CompatibilityMode::FullTransitive => (true, true, true),
+            CompatibilityMode::None => (false, false, false),
+        };
+
+        let previous_schemas = if transitive || previous_schemas.is_empty() {
+            previous_schemas
+        } else {
+            &previous_schemas[previous_schemas.len() - 1..]
+        };
+
+        let mut result = SchemaCompatibilityResult::default();
+        for previous_schema in previous_schemas {
+            if backward {
+                result.incompatibilities.extend(
+                    SchemaCompatibility::check_can_read(previous_schema, new_schema)
+                        .incompatibilities,
+                );
+            }
+            if forward {
+                result.incompatibilities.extend(
+                    SchemaCompatibility::check_can_read(new_schema, previous_schema)
+                        .incompatibilities,
+                );
+            }
+        }
+        result
+    }
+}
+
+/// The kinds of incompatibility that can be found between a writer's and a reader's schema.
+///
+/// They are the same as those reported by the `SchemaCompatibility` of the Java implementation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SchemaIncompatibilityType {
+    /// The writer's type cannot be read as, nor promoted to, the reader's type.
+    TypeMismatch,
+    /// A field of the reader's record has no default value and no counterpart in the writer's
+    /// record.
+    ReaderFieldMissingDefaultValue,
+    /// Some symbols of the writer's enum are missing from the reader's enum, which has no
+    /// default symbol.
+    MissingEnumSymbols,
+    /// The reader's union has no branch that can read the writer's type.
+    MissingUnionBranch,
+    /// The reader's named type has neither the fullname of the writer's one nor an alias to it.
+    NameMismatch,
+    /// The reader's and the writer's fixed do not have the same size.
+    FixedSizeMismatch,
+}
+
+/// An incompatibility found between a writer's and a reader's schema.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SchemaIncompatibility {
+    /// The kind of incompatibility.
+    pub kind: SchemaIncompatibilityType,
+    /// Where the incompatibility was found, as a path from the top-level schema such as
+    /// `/fields/3/type/items`. Fields are identified by their position in the reader's record,
+    /// and union branches by their position in the writer's union, or in the reader's one when
+    /// the writer's schema is not a union.
+    pub location: String,
+    /// A description of the incompatibility.
+    pub message: String,
+}
+
+impl fmt::Display for SchemaIncompatibility {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?} at {}: {}", self.kind, self.location, self.message)
+    }
+}
+
+/// The result of checking that data written with a schema can be read with another one.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SchemaCompatibilityResult {
+    incompatibilities: Vec<SchemaIncompatibility>,
+}
+
+impl SchemaCompatibilityResult {
+    /// Return whether no incompatibility was found.
+    pub fn is_compatible(&self) -> bool {
+        self.incompatibilities.is_empty()
+    }
+
+    /// Return every incompatibility that was found.
+    pub fn incompatibilities(&self) -> &[SchemaIncompatibility] {
+        &self.incompatibilities
+    }
+}
+
+struct Checker<'a> {
     recursion: HashSet<(u64, u64)>,
+    // The top-level schemas, used to follow references to named types.
+    writers_root: Root<'a>,
+    readers_root: Root<'a>,
+    // The path from the top-level schemas to the ones being checked.
+    location: Vec<String>,
+    // Whether the last segment of the location is the index of the writer's union branch being
+    // checked, which then also stands for the branch of the reader's union it is read as.
+    in_union_branch: bool,
+    incompatibilities: Vec<SchemaIncompatibility>,
 }
 
-impl Checker {
+impl<'a> Checker<'a> {
     /// Create a new checker, with recursion set to an empty set.
-    pub(crate) fn new() -> Self {
+    pub(crate) fn new(writers_root: &'a Schema, readers_root: &'a Schema) -> Self {
         Self {
             recursion: HashSet::new(),
+            writers_root: Root::new(writers_root),
+            readers_root: Root::new(readers_root),
+            location: Vec::new(),
+            in_union_branch: false,
+            incompatibilities: Vec::new(),
         }
     }
 
-    pub(crate) fn can_read(&mut self, writers_schema: &Schema, readers_schema: &Schema) -> bool {
-        self.full_match_schemas(writers_schema, readers_schema)
+    pub(crate) fn check(mut self) -> SchemaCompatibilityResult {
+        self.full_match_schemas(self.writers_root.schema(), self.readers_root.schema());
+        SchemaCompatibilityResult {
+            incompatibilities: self.incompatibilities,
+        }
     }
 
+    /// Check that a datum written with `writers_schema` can be read with `readers_schema`,
+    /// recording every incompatibility found along the way. Returns whether none was found.
     pub(crate) fn full_match_schemas(
         &mut self,
-        writers_schema: &Schema,
-        readers_schema: &Schema,
+        writers_schema: &'a Schema,
+        readers_schema: &'a Schema,
     ) -> bool {
+        let writers_schema = dereference(writers_schema, &self.writers_root);
+        let readers_schema = dereference(readers_schema, &self.readers_root);
+        let in_union_branch = std::mem::replace(&mut self.in_union_branch, false);
+
         if self.recursion_in_progress(writers_schema, readers_schema) {
             return true;
         }
 
-        if !SchemaCompatibility::match_schemas(writers_schema, readers_schema) {
-            return false;
-        }
+        let found = self.incompatibilities.len();
 
-        let w_type = SchemaKind::from(writers_schema);
-        let r_type = SchemaKind::from(readers_schema);
-
-        if w_type != SchemaKind::Union && (r_type.is_primitive() || r_type == SchemaKind::Fixed) {
-            return true;
-        }
-
-        match r_type {
-            SchemaKind::Record => self.match_record_schemas(writers_schema, readers_schema),
-            SchemaKind::Map => {
-                if let Schema::Map(w_m) = writers_schema {
-                    if let Schema::Map(r_m) = readers_schema {
-                        self.full_match_schemas(w_m, r_m)
-                    } else {
-                        unreachable!("readers_schema should have been Schema::Map")
-                    }
-                } else {
-                    unreachable!("writers_schema should have been Schema::Map")
+        match (writers_schema, readers_schema) {
+            (Schema::Union(w_union), _) => {
+                // every branch of the writer's union must be readable
+                for (index, w_branch) in w_union.variants().iter().enumerate() {
+                    self.location.push(index.to_string());
+                    self.in_union_branch = true;
+                    self.full_match_schemas(w_branch, readers_schema);
+                    self.location.pop();
                 }
             }
-            SchemaKind::Array => {
-                if let Schema::Array(w_a) = writers_schema {
-                    if let Schema::Array(r_a) = readers_schema {
-                        self.full_match_schemas(w_a, r_a)
-                    } else {
-                        unreachable!("readers_schema should have been Schema::Array")
+            (_, Schema::Union(_)) => {
+                self.match_readers_union(writers_schema, readers_schema, in_union_branch)
+            }
+            (
+                Schema::Record {
+                    name: w_name,
+                    fields: w_fields,
+                    lookup: w_lookup,
+                    ..
+                },
+                Schema::Record {
+                    name: r_name,
+                    fields: r_fields,
+                    ..
+                },
+            ) => {
+                self.match_names(w_name, r_name);
+                for field in r_fields {
+                    self.location.push("fields".to_string());
+                    self.location.push(field.position.to_string());
+
+                    let w_position = w_lookup.get(&field.name).or_else(|| {
+                        field
+                            .aliases
+                            .iter()
+                            .flatten()
+                            .find_map(|alias| w_lookup.get(alias))
+                    });
+                    match w_position {
+                        Some(&position) => {
+                            self.location.push("type".to_string());
+                            self.full_match_schemas(&w_fields[position].schema, &field.schema);
+                            self.location.pop();
+                        }
+                        None if field.default.is_none() => self.incompatible(
+                            SchemaIncompatibilityType::ReaderFieldMissingDefaultValue,
+                            field.name.clone(),
+                        ),
+                        None => {}
                     }
-                } else {
-                    unreachable!("writers_schema should have been Schema::Array")
+
+                    self.location.pop();
+                    self.location.pop();
                 }
             }
-            SchemaKind::Union => self.match_union_schemas(writers_schema, readers_schema),
-            SchemaKind::Enum => {
-                // reader's symbols must contain all writer's symbols
-                if let Schema::Enum {
-                    symbols: w_symbols, ..
-                } = writers_schema
-                {
-                    if let Schema::Enum {
-                        symbols: r_symbols, ..
-                    } = readers_schema
-                    {
-                        return w_symbols.iter().find(|e| !r_symbols.contains(e)).is_none();
-                    }
+            (
+                Schema::Enum {
+                    name: w_name,
+                    symbols: w_symbols,
+                    ..
+                },
+                Schema::Enum {
+                    name: r_name,
+                    symbols: r_symbols,
+                    default: r_default,
+                    ..
+                },
+            ) => {
+                self.match_names(w_name, r_name);
+                // reader's symbols must contain all writer's symbols, unless the reader has a
+                // default symbol to fall back to
+                let missing = w_symbols
+                    .iter()
+                    .filter(|symbol| !r_symbols.contains(symbol))
+                    .cloned()
+                    .collect::<Vec<_>>();
+                if r_default.is_none() && !missing.is_empty() {
+                    self.location.push("symbols".to_string());
+                    self.incompatible(
+                        SchemaIncompatibilityType::MissingEnumSymbols,
+                        missing.join(", "),
+                    );
+                    self.location.pop();
+                }
+            }
+            (
+                Schema::Fixed {
+                    name: w_name,
+                    size: w_size,
+                    ..
+                },
+                Schema::Fixed {
+                    name: r_name,
+                    size: r_size,
+                    ..
+                },
+            ) => {
+                self.match_names(w_name, r_name);
+                if w_size != r_size {
+                    self.location.push("size".to_string());
+                    self.incompatible(
+                        SchemaIncompatibilityType::FixedSizeMismatch,
+                        format!("expected: {}, found: {}", w_size, r_size),
+                    );
+                    self.location.pop();
                 }
-                false
+            }
+            (Schema::Array(w_items), Schema::Array(r_items)) => {
+                self.location.push("items".to_string());
+                self.full_match_schemas(w_items, r_items);
+                self.location.pop();
+            }
+            (Schema::Map(w_values), Schema::Map(r_values)) => {
+                self.location.push("values".to_string());
+                self.full_match_schemas(w_values, r_values);
+                self.location.pop();
             }
             _ => {
-                if w_type == SchemaKind::Union {
-                    if let Schema::Union(r) = writers_schema {
-                        if r.schemas.len() == 1 {
-                            return self.full_match_schemas(&r.schemas[0], readers_schema);
-                        }
-                    }
+                if !SchemaCompatibility::match_schemas(writers_schema, readers_schema) {
+                    self.incompatible(
+                        SchemaIncompatibilityType::TypeMismatch,
+                        format!(
+                            "reader type: {:?} not compatible with writer type: {:?}",
+                            SchemaKind::from(readers_schema),
+                            SchemaKind::from(writers_schema)
+                        ),
+                    );
                 }
-                false
             }
         }
-    }
 
-    fn match_record_schemas(&mut self, writers_schema: &Schema, readers_schema: &Schema) -> bool {
-        let w_type = SchemaKind::from(writers_schema);
+        self.incompatibilities.len() == found
+    }
 
-        if w_type == SchemaKind::Union {
-            return false;
+    /// Check that one of the branches of the reader's union can read the writer's schema, which
+    /// is not a union.
+    ///
+    /// The index of the reader's branch is added to the location, unless `in_union_branch` tells
+    /// that the location already ends with the index of the writer's branch.
+    fn match_readers_union(
+        &mut self,
+        writers_schema: &'a Schema,
+        readers_schema: &'a Schema,
+        in_union_branch: bool,
+    ) {
+        let r_variants = match readers_schema {
+            Schema::Union(r_union) => r_union.variants(),
+            _ => unreachable!("readers_schema should have been Schema::Union"),
+        };
+
+        for r_branch in r_variants {
+            // Incompatibilities with the branches that do not match are not relevant.
+            let found = self.incompatibilities.len();
+            let recursion = self.recursion.clone();
+            if self.full_match_schemas(writers_schema, r_branch) {
+                return;
+            }
+            self.incompatibilities.truncate(found);
+            self.recursion = recursion;
         }
 
-        if let Schema::Record {
-            fields: w_fields,
-            lookup: w_lookup,
-            ..
-        } = writers_schema
-        {
-            if let Schema::Record {
-                fields: r_fields, ..
-            } = readers_schema
-            {
-                for field in r_fields.iter() {
-                    if let Some(pos) = w_lookup.get(&field.name) {
-                        if !self.full_match_schemas(&w_fields[*pos].schema, &field.schema) {
-                            return false;
-                        }
-                    } else if field.default.is_none() {
-                        return false;
-                    }
-                }
+        // Unless one of the branches is the writer's named type, whose incompatibilities are then
+        // the most helpful ones.
+        let w_name = named_type_name(writers_schema);
+        let same_named_type = r_variants.iter().position(|r_branch| {
+            let r_branch = dereference(r_branch, &self.readers_root);
+            SchemaKind::from(r_branch) == SchemaKind::from(writers_schema)
+                && named_type_name(r_branch).is_some()
+                && named_type_name(r_branch) == w_name
+        });
+        match same_named_type {
+            Some(index) if in_union_branch => {
+                self.full_match_schemas(writers_schema, &r_variants[index]);
+            }
+            Some(index) => {
+                self.location.push(index.to_string());
+                self.full_match_schemas(writers_schema, &r_variants[index]);
+                self.location.pop();
             }
+            None => self.incompatible(
+                SchemaIncompatibilityType::MissingUnionBranch,
+                format!(
+                    "reader union lacking writer type: {:?}",
+                    SchemaKind::from(writers_schema)
+                ),
+            ),
         }
-        true
     }
 
-    fn match_union_schemas(&mut self, writers_schema: &Schema, readers_schema: &Schema) -> bool {
-        let w_type = SchemaKind::from(writers_schema);
-        let r_type = SchemaKind::from(readers_schema);
-
-        assert_eq!(r_type, SchemaKind::Union);
-
-        if w_type == SchemaKind::Union {
-            if let Schema::Union(u) = writers_schema {
-                u.schemas
-                    .iter()
-                    .all(|schema| self.full_match_schemas(schema, readers_schema))
-            } else {
-                unreachable!("writers_schema should have been Schema::Union")
-            }
-        } else if let Schema::Union(u) = readers_schema {
-            u.schemas
-                .iter()
-                .any(|schema| self.full_match_schemas(writers_schema, schema))
-        } else {
-            unreachable!("readers_schema should have been Schema::Union")
+    fn match_names(&mut self, writers_name: &Name, readers_name: &Name) {
+        if !readers_name.matches(writers_name) {
+            self.location.push("name".to_string());
+            self.incompatible(
+                SchemaIncompatibilityType::NameMismatch,
+                format!("expected: {}", writers_name.fullname(None)),
+            );
+            self.location.pop();
         }
     }
 
+    fn incompatible(&mut self, kind: SchemaIncompatibilityType, message: String) {
+        self.incompatibilities.push(SchemaIncompatibility {
+            kind,
+            location: format!("/{}", self.location.join("/")),
+            message,
+        });
+    }
+
     fn recursion_in_progress(&mut self, writers_schema: &Schema, readers_schema: &Schema) -> bool {
         let mut hasher = DefaultHasher::new();
         ptr::hash(writers_schema, &mut hasher);
@@ -167,12 +416,31 @@ impl Checker {
     }
 }
 
+/// Follow a `Schema::Ref` back to the definition of the named type it refers to in `root`, and a
+/// `Schema::Annotated` to the schema it annotates.
+fn dereference<'a>(schema: &'a Schema, root: &Root<'a>) -> &'a Schema {
+    match schema {
+        Schema::Ref { name } => root.lookup_named(&name.fullname(None)).unwrap_or(schema),
+        Schema::Annotated { inner, .. } => dereference(inner, root),
+        _ => schema,
+    }
+}
+
+/// Return the fullname of a named type, or `None` for any other kind of schema.
+fn named_type_name(schema: &Schema) -> Option<String> {
+    match schema {
+        Schema::Record { name, .. } | Schema::Enum { name, .. } | Schema::Fixed { name, .. } => {
+            Some(name.fullname(None))
+        }
+        _ => None,
+    }
+}
+
 impl SchemaCompatibility {
     /// `can_read` performs a full, recursive check that a datum written using the
     /// writers_schema can be read using the readers_schema.
     pub fn can_read(writers_schema: &Schema, readers_schema: &Schema) -> bool {
-        let mut c = Checker::new();
-        c.can_read(writers_schema, readers_schema)
+        SchemaCompatibility::check_can_read(writers_schema, readers_schema).is_compatible()
     }
 
     /// `mutual_read` performs a full, recursive check that a datum written using either
@@ -182,12 +450,37 @@ impl SchemaCompatibility {
             && SchemaCompatibility::can_read(readers_schema, writers_schema)
     }
 
+    /// `check_can_read` performs the same check as `can_read`, but reports every
+    /// incompatibility that makes a datum written using the writers_schema unreadable using the
+    /// readers_schema.
+    pub fn check_can_read(
+        writers_schema: &Schema,
+        readers_schema: &Schema,
+    ) -> SchemaCompatibilityResult {
+        Checker::new(writers_schema, readers_schema).check()
+    }
+
+    /// `check_mutual_read` performs the same check as `mutual_read`, reporting the
+    /// incompatibilities of reading with the readers_schema first, then those of reading with
+    /// the writers_schema.
+    pub fn check_mutual_read(
+        writers_schema: &Schema,
+        readers_schema: &Schema,
+    ) -> SchemaCompatibilityResult {
+        let mut result = SchemaCompatibility::check_can_read(writers_schema, readers_schema);
+        result.incompatibilities.extend(
+            SchemaCompatibility::check_can_read(readers_schema, writers_schema).incompatibilities,
+        );
+        result
+    }
+
     ///  `match_schemas` performs a basic check that a datum written with the
     ///  writers_schema could be read using the readers_schema. This check only includes
     ///  matching the types, including schema promotion, and matching the full name for
-    ///  named types. Aliases for named types are not supported here, and the rust
-    ///  implementation of Avro in general does not include support for aliases (I think).
+    ///  named types, either directly or through one of the reader's aliases.
     pub(crate) fn match_schemas(writers_schema: &Schema, readers_schema: &Schema) -> bool {
+        let writers_schema = writers_schema.unannotated();
+        let readers_schema = readers_schema.unannotated();
         let w_type = SchemaKind::from(writers_schema);
         let r_type = SchemaKind::from(readers_schema);
 
@@ -195,6 +488,12 @@ impl SchemaCompatibility {
             return true;
         }
 
+        // References are followed by the `Checker`, which then matches the named types they
+        // refer to.
+        if w_type == SchemaKind::Ref || r_type == SchemaKind::Ref {
+            return true;
+        }
+
         if w_type == r_type {
             if r_type.is_primitive() {
                 return true;
@@ -204,7 +503,7 @@ impl SchemaCompatibility {
                 SchemaKind::Record => {
                     if let Schema::Record { name: w_name, .. } = writers_schema {
                         if let Schema::Record { name: r_name, .. } = readers_schema {
-                            return w_name.fullname(None) == r_name.fullname(None);
+                            return r_name.matches(w_name);
                         } else {
                             unreachable!("readers_schema should have been Schema::Record")
                         }
@@ -216,15 +515,16 @@ impl SchemaCompatibility {
                     if let Schema::Fixed {
                         name: w_name,
                         size: w_size,
+                        ..
                     } = writers_schema
                     {
                         if let Schema::Fixed {
                             name: r_name,
                             size: r_size,
+                            ..
                         } = readers_schema
                         {
-                            return w_name.fullname(None) == r_name.fullname(None)
-                                && w_size == r_size;
+                            return r_name.matches(w_name) && w_size == r_size;
                         } else {
                             unreachable!("readers_schema should have been Schema::Fixed")
                         }
@@ -235,7 +535,7 @@ impl SchemaCompatibility {
                 SchemaKind::Enum => {
                     if let Schema::Enum { name: w_name, .. } = writers_schema {
                         if let Schema::Enum { name: r_name, .. } = readers_schema {
-                            return w_name.fullname(None) == r_name.fullname(None);
+                            return r_name.matches(w_name);
                         } else {
                             unreachable!("readers_schema should have been Schema::Enum")
                         }
@@ -265,7 +565,29 @@ impl SchemaCompatibility {
                         unreachable!("writers_schema should have been Schema::Array")
                     }
                 }
-                _ => (),
+                SchemaKind::Decimal => {
+                    if let Schema::Decimal {
+                        precision: w_precision,
+                        scale: w_scale,
+                        ..
+                    } = writers_schema
+                    {
+                        if let Schema::Decimal {
+                            precision: r_precision,
+                            scale: r_scale,
+                            ..
+                        } = readers_schema
+                        {
+                            return w_precision == r_precision && w_scale == r_scale;
+                        } else {
+                            unreachable!("readers_schema should have been Schema::Decimal")
+                        }
+                    } else {
+                        unreachable!("writers_schema should have been Schema::Decimal")
+                    }
+                }
+                // the other logical types carry no parameter
+                _ => return true,
             };
         }
 
@@ -697,6 +1019,29 @@ mod tests {
         );
     }
 
+    fn recursive_list_schema(value_type: &str) -> Schema {
+        Schema::parse_str(&format!(
+            r#"
+        {{"type":"record", "name":"List", "fields":[
+          {{"name":"value", "type":"{}"}},
+          {{"name":"next", "type":["null", "List"]}}
+        ]}}
+"#,
+            value_type
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_recursive_schemas() {
+        let int_list = recursive_list_schema("int");
+        let long_list = recursive_list_schema("long");
+
+        assert!(SchemaCompatibility::can_read(&int_list, &int_list));
+        assert!(SchemaCompatibility::can_read(&int_list, &long_list));
+        assert!(!SchemaCompatibility::can_read(&long_list, &int_list));
+    }
+
     #[test]
     fn test_union_reader_writer_subset_incompatiblity() {
         // reader union schema must contain all writer union branches
@@ -754,6 +1099,170 @@ mod tests {
         assert!(SchemaCompatibility::can_read(&enum_schema1, &enum_schema2));
     }
 
+    #[test]
+    fn test_enum_default() {
+        let writer_schema =
+            Schema::parse_str(r#"{"type":"enum", "name":"MyEnum", "symbols":["A","B","C"]}"#)
+                .unwrap();
+        let reader_schema = Schema::parse_str(
+            r#"{"type":"enum", "name":"MyEnum", "symbols":["A","B","UNKNOWN"], "default":"UNKNOWN"}"#,
+        )
+        .unwrap();
+        assert!(SchemaCompatibility::can_read(
+            &writer_schema,
+            &reader_schema
+        ));
+    }
+
+    #[test]
+    fn test_aliases() {
+        let writer_schema = Schema::parse_str(
+            r#"{"type":"record", "name":"com.acme.Old", "fields":[
+                {"name":"kind", "type":{"type":"enum", "name":"OldKind", "symbols":["A"]}},
+                {"name":"id", "type":{"type":"fixed", "name":"OldId", "size":16}}
+            ]}"#,
+        )
+        .unwrap();
+        let reader_schema = Schema::parse_str(
+            r#"{"type":"record", "name":"New", "namespace":"com.acme", "aliases":["Old"], "fields":[
+                {"name":"type", "aliases":["kind"],
+                 "type":{"type":"enum", "name":"Kind", "aliases":["OldKind"], "symbols":["A"]}},
+                {"name":"id", "type":{"type":"fixed", "name":"Id", "aliases":["com.acme.OldId"], "size":16}}
+            ]}"#,
+        )
+        .unwrap();
+        assert!(SchemaCompatibility::can_read(
+            &writer_schema,
+            &reader_schema
+        ));
+        assert!(!SchemaCompatibility::can_read(
+            &reader_schema,
+            &writer_schema
+        ));
+    }
+
+    #[test]
+    fn test_incompatibilities() {
+        let writer_schema = Schema::parse_str(
+            r#"{"type":"record", "name":"Record", "fields":[
+                {"name":"a", "type":{"type":"array", "items":"int"}},
+                {"name":"b", "type":{"type":"enum", "name":"Enum", "symbols":["A","B","C"]}},
+                {"name":"c", "type":{"type":"fixed", "name":"Fixed", "size":16}},
+                {"name":"d", "type":["null", "string", "long"]},
+                {"name":"e", "type":["null", {"type":"record", "name":"Inner", "fields":[]}]},
+                {"name":"g", "type":{"type":"record", "name":"Outer", "fields":[]}}
+            ]}"#,
+        )
+        .unwrap();
+        let reader_schema = Schema::parse_str(
+            r#"{"type":"record", "name":"Record", "fields":[
+                {"name":"a", "type":{"type":"array", "items":"string"}},
+                {"name":"b", "type":{"type":"enum", "name":"Enum", "symbols":["A"]}},
+                {"name":"c", "type":{"type":"fixed", "name":"Other", "size":8}},
+                {"name":"d", "type":["null", "string"]},
+                {"name":"e", "type":["null", {"type":"record", "name":"Inner", "fields":[
+                    {"name":"new", "type":"int"}
+                ]}]},
+                {"name":"f", "type":"int"},
+                {"name":"g", "type":["null", {"type":"record", "name":"Outer", "fields":[
+                    {"name":"new", "type":"int"}
+                ]}]}
+            ]}"#,
+        )
+        .unwrap();
+
+        let result = SchemaCompatibility::check_can_read(&writer_schema, &reader_schema);
+        assert!(!result.is_compatible());
+        let incompatibilities = result
+            .incompatibilities()
+            .iter()
+            .map(|incompatibility| (incompatibility.kind, incompatibility.location.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            incompatibilities,
+            vec![
+                (
+                    SchemaIncompatibilityType::TypeMismatch,
+                    "/fields/0/type/items"
+                ),
+                (
+                    SchemaIncompatibilityType::MissingEnumSymbols,
+                    "/fields/1/type/symbols"
+                ),
+                (
+                    SchemaIncompatibilityType::NameMismatch,
+                    "/fields/2/type/name"
+                ),
+                (
+                    SchemaIncompatibilityType::FixedSizeMismatch,
+                    "/fields/2/type/size"
+                ),
+                (
+                    SchemaIncompatibilityType::MissingUnionBranch,
+                    "/fields/3/type/2"
+                ),
+                (
+                    SchemaIncompatibilityType::ReaderFieldMissingDefaultValue,
+                    "/fields/4/type/1/fields/0"
+                ),
+                (
+                    SchemaIncompatibilityType::ReaderFieldMissingDefaultValue,
+                    "/fields/5"
+                ),
+                (
+                    SchemaIncompatibilityType::ReaderFieldMissingDefaultValue,
+                    "/fields/6/type/1/fields/0"
+                ),
+            ]
+        );
+        assert_eq!(result.incompatibilities()[1].message, "B, C");
+
+        assert!(
+            SchemaCompatibility::check_can_read(&writer_schema, &writer_schema).is_compatible()
+        );
+    }
+
+    #[test]
+    fn test_compatibility_modes() {
+        use std::str::FromStr;
+
+        // each version adds a field: b without a default, c with one
+        let v1 = a_int_record1_schema();
+        let v2 = a_int_b_int_record1_schema();
+        let v3 = Schema::parse_str(r#"{"type":"record", "name":"Record1", "fields":[{"name":"a", "type":"int"}, {"name":"b", "type":"int"}, {"name":"c", "type":"int", "default":0}]}"#).unwrap();
+        // drops b, which v1 and v3 lack and require respectively
+        let v4 = a_dint_record1_schema();
+
+        let history = vec![v1.clone(), v2.clone()];
+        let is_compatible = |mode: &str, new_schema: &Schema, history: &[Schema]| {
+            CompatibilityMode::from_str(mode)
+                .unwrap()
+                .check(new_schema, history)
+                .is_compatible()
+        };
+
+        // v3 can read v2 and v1 can read v3, but v3 cannot read v1 (b has no default)
+        assert!(is_compatible("BACKWARD", &v3, &history));
+        assert!(!is_compatible("BACKWARD_TRANSITIVE", &v3, &history));
+        assert!(is_compatible("FORWARD", &v3, &history));
+        assert!(is_compatible("FORWARD_TRANSITIVE", &v3, &history));
+        assert!(is_compatible("FULL", &v3, &history));
+        assert!(!is_compatible("FULL_TRANSITIVE", &v3, &history));
+
+        // v2 cannot read v4, which lacks b
+        let history = vec![v1, v2, v3];
+        assert!(is_compatible("BACKWARD_TRANSITIVE", &v4, &history));
+        assert!(!is_compatible("FORWARD", &v4, &history));
+        assert!(!is_compatible("FULL", &v4, &history));
+        assert!(is_compatible("NONE", &v4, &history));
+
+        assert!(is_compatible("FULL_TRANSITIVE", &v4, &[]));
+        assert_eq!(
+            <&str>::from(CompatibilityMode::BackwardTransitive),
+            "BACKWARD_TRANSITIVE"
+        );
+    }
+
     // unused
     /*
         fn point_2d_schema() -> Schema {
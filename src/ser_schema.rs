@@ -0,0 +1,1050 @@
+//! Logic for serde-compatible serialization straight into Avro format, guided by a `Schema`.
+use std::convert::TryFrom;
+use std::iter::once;
+use std::rc::Rc;
+
+use serde::{ser, Serialize};
+
+use crate::errors::Error;
+use crate::schema::{RecordField, Root, Schema, SchemaKind, UnionSchema};
+use crate::util::{zig_i32, zig_i64};
+
+/// A `serde::Serializer` encoding values into Avro format as they are visited, checking them
+/// against a `Schema` instead of building an intermediate `Value`.
+///
+/// Integers are widened to `long` and `float`s to `double` when the schema asks for it, and each
+/// branch of a union is picked as the first one able to hold the value being serialized.
+pub struct SchemaAwareSerializer<'s, 'b> {
+    schema: &'s Schema,
+    root: Rc<Root<'s>>,
+    buffer: &'b mut Vec<u8>,
+}
+
+pub struct SeqSerializer<'s, 'b> {
+    items_schema: &'s Schema,
+    root: Rc<Root<'s>>,
+    buffer: &'b mut Vec<u8>,
+    // Number of items announced upfront, in which case they are written straight to `buffer`.
+    len: Option<usize>,
+    count: usize,
+    // Items written so far, when their number is unknown until the end.
+    items: Vec<u8>,
+}
+
+pub struct MapSerializer<'s, 'b> {
+    values_schema: &'s Schema,
+    root: Rc<Root<'s>>,
+    buffer: &'b mut Vec<u8>,
+    len: Option<usize>,
+    count: usize,
+    items: Vec<u8>,
+}
+
+/// Serializer of the keys of a map, which are always Avro `string`s.
+struct MapKeySerializer<'b> {
+    buffer: &'b mut Vec<u8>,
+}
+
+pub struct StructSerializer<'s, 'b> {
+    fields: &'s [RecordField],
+    root: Rc<Root<'s>>,
+    buffer: &'b mut Vec<u8>,
+    position: usize,
+}
+
+fn mismatch(what: &str, schema: &Schema) -> Error {
+    Error::Ser(format!(
+        "{} does not match schema {:?}",
+        what,
+        SchemaKind::from(schema)
+    ))
+}
+
+/// Follow `schema` back to the named type it refers to, if it is a `Schema::Ref`, and to the
+/// schema it annotates, if it is a `Schema::Annotated`.
+fn resolve<'s>(schema: &'s Schema, root: &Root<'s>) -> Result<&'s Schema, Error> {
+    match *schema {
+        Schema::Ref { ref name } => {
+            let fullname = name.fullname(None);
+            root.lookup_named(&fullname)
+                .ok_or_else(|| Error::Ser(format!("unknown reference to named type {}", fullname)))
+        }
+        Schema::Annotated { ref inner, .. } => resolve(inner, root),
+        _ => Ok(schema),
+    }
+}
+
+fn is_int(schema: &Schema) -> bool {
+    matches!(*schema, Schema::Int | Schema::Date | Schema::TimeMillis)
+}
+
+fn is_long(schema: &Schema) -> bool {
+    matches!(
+        *schema,
+        Schema::Long | Schema::TimeMicros | Schema::TimestampMillis | Schema::TimestampMicros
+    )
+}
+
+fn has_symbol(schema: &Schema, symbol: &str) -> bool {
+    match *schema {
+        Schema::Enum { ref symbols, .. } => symbols.iter().any(|item| item == symbol),
+        _ => false,
+    }
+}
+
+fn encode_bytes(bytes: &[u8], buffer: &mut Vec<u8>) {
+    zig_i64(bytes.len() as i64, buffer);
+    buffer.extend_from_slice(bytes);
+}
+
+fn encode_fixed(bytes: &[u8], size: usize, buffer: &mut Vec<u8>) -> Result<(), Error> {
+    if bytes.len() != size {
+        return Err(Error::Ser(format!(
+            "expected {} bytes of fixed, got {}",
+            size,
+            bytes.len()
+        )));
+    }
+    buffer.extend_from_slice(bytes);
+    Ok(())
+}
+
+impl<'s, 'b> SchemaAwareSerializer<'s, 'b> {
+    /// Create a `SchemaAwareSerializer` writing values of `schema` at the end of `buffer`.
+    pub fn new(schema: &'s Schema, buffer: &'b mut Vec<u8>) -> Self {
+        SchemaAwareSerializer {
+            schema,
+            root: Rc::new(Root::new(schema)),
+            buffer,
+        }
+    }
+
+    /// Write the index of the first branch of `union` that `accepts` the value being serialized,
+    /// and return a serializer for that branch.
+    fn branch<F>(self, union: &'s UnionSchema, what: &str, accepts: F) -> Result<Self, Error>
+    where
+        F: Fn(&Schema) -> bool,
+    {
+        let root = self.root;
+        let (index, variant) = union
+            .variants()
+            .iter()
+            .enumerate()
+            .find(|(_, variant)| resolve(variant, &root).map(&accepts).unwrap_or(false))
+            .ok_or_else(|| Error::Ser(format!("no branch of union matches {}", what)))?;
+        zig_i64(index as i64, self.buffer);
+        Ok(SchemaAwareSerializer {
+            schema: variant,
+            root,
+            buffer: self.buffer,
+        })
+    }
+
+    /// Write the symbol of an enum `variant` for the first field of the record that the data of
+    /// an enum variant is serialized into, returning the schema of the second one.
+    fn variant_record(
+        self,
+        variant: &'static str,
+    ) -> Result<(&'s Schema, Rc<Root<'s>>, &'b mut Vec<u8>), Error> {
+        match *resolve(self.schema, &self.root)? {
+            Schema::Record { ref fields, .. } if fields.len() == 2 => {
+                SchemaAwareSerializer {
+                    schema: &fields[0].schema,
+                    root: self.root.clone(),
+                    buffer: &mut *self.buffer,
+                }
+                .serialize_symbol(variant)?;
+                Ok((&fields[1].schema, self.root, self.buffer))
+            }
+            Schema::Union(ref union) => self
+                .branch(union, variant, |schema| match *schema {
+                    Schema::Record { ref fields, .. } => fields.len() == 2,
+                    _ => false,
+                })?
+                .variant_record(variant),
+            ref schema => Err(mismatch(variant, schema)),
+        }
+    }
+
+    /// Write the symbol of an enum, such as the one of a unit variant.
+    fn serialize_symbol(self, symbol: &str) -> Result<(), Error> {
+        match *resolve(self.schema, &self.root)? {
+            Schema::Enum { .. } => ser::Serializer::serialize_str(self, symbol),
+            Schema::Union(ref union) => self
+                .branch(union, symbol, |schema| has_symbol(schema, symbol))?
+                .serialize_symbol(symbol),
+            ref schema => Err(mismatch(symbol, schema)),
+        }
+    }
+
+    /// Start writing the fields of a record, such as the ones of the struct called `name`.
+    fn serialize_record(self, name: &str) -> Result<StructSerializer<'s, 'b>, Error> {
+        match *resolve(self.schema, &self.root)? {
+            Schema::Record { ref fields, .. } => Ok(StructSerializer {
+                fields,
+                root: self.root,
+                buffer: self.buffer,
+                position: 0,
+            }),
+            Schema::Union(ref union) => {
+                // Prefer the record named like the struct, if there is one.
+                let named =
+                    union
+                        .variants()
+                        .iter()
+                        .any(|variant| match resolve(variant, &self.root) {
+                            Ok(Schema::Record { name: record, .. }) => record.name == name,
+                            _ => false,
+                        });
+                self.branch(union, name, |schema| match *schema {
+                    Schema::Record {
+                        name: ref record, ..
+                    } => !named || record.name == name,
+                    _ => false,
+                })?
+                .serialize_record(name)
+            }
+            ref schema => Err(mismatch(name, schema)),
+        }
+    }
+}
+
+impl<'s, 'b> SeqSerializer<'s, 'b> {
+    fn new(
+        items_schema: &'s Schema,
+        root: Rc<Root<'s>>,
+        buffer: &'b mut Vec<u8>,
+        len: Option<usize>,
+    ) -> Self {
+        if let Some(len) = len {
+            if len > 0 {
+                zig_i64(len as i64, buffer);
+            }
+        }
+        SeqSerializer {
+            items_schema,
+            root,
+            buffer,
+            len,
+            count: 0,
+            items: Vec::new(),
+        }
+    }
+}
+
+impl<'s, 'b> MapSerializer<'s, 'b> {
+    fn new(
+        values_schema: &'s Schema,
+        root: Rc<Root<'s>>,
+        buffer: &'b mut Vec<u8>,
+        len: Option<usize>,
+    ) -> Self {
+        if let Some(len) = len {
+            if len > 0 {
+                zig_i64(len as i64, buffer);
+            }
+        }
+        MapSerializer {
+            values_schema,
+            root,
+            buffer,
+            len,
+            count: 0,
+            items: Vec::new(),
+        }
+    }
+}
+
+/// Terminate an array or a map whose `count` items were written either to `buffer` directly, as
+/// announced by `len`, or to `items`.
+fn end_blocks(
+    buffer: &mut Vec<u8>,
+    len: Option<usize>,
+    count: usize,
+    items: &[u8],
+) -> Result<(), Error> {
+    match len {
+        Some(len) if len != count => {
+            return Err(Error::Ser(format!("expected {} items, got {}", len, count)))
+        }
+        Some(_) => {}
+        None => {
+            if count > 0 {
+                zig_i64(count as i64, buffer);
+                buffer.extend_from_slice(items);
+            }
+        }
+    }
+    buffer.push(0u8);
+    Ok(())
+}
+
+impl<'s, 'b> ser::Serializer for SchemaAwareSerializer<'s, 'b> {
+    type Ok = ();
+    type Error = Error;
+    type SerializeSeq = SeqSerializer<'s, 'b>;
+    type SerializeTuple = SeqSerializer<'s, 'b>;
+    type SerializeTupleStruct = SeqSerializer<'s, 'b>;
+    type SerializeTupleVariant = SeqSerializer<'s, 'b>;
+    type SerializeMap = MapSerializer<'s, 'b>;
+    type SerializeStruct = StructSerializer<'s, 'b>;
+    type SerializeStructVariant = StructSerializer<'s, 'b>;
+
+    fn serialize_bool(self, v: bool) -> Result<Self::Ok, Self::Error> {
+        match *resolve(self.schema, &self.root)? {
+            Schema::Boolean => {
+                self.buffer.push(if v { 1u8 } else { 0u8 });
+                Ok(())
+            }
+            Schema::Union(ref union) => self
+                .branch(union, "bool", |schema| matches!(*schema, Schema::Boolean))?
+                .serialize_bool(v),
+            ref schema => Err(mismatch("bool", schema)),
+        }
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Self::Ok, Self::Error> {
+        self.serialize_i32(i32::from(v))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Self::Ok, Self::Error> {
+        self.serialize_i32(i32::from(v))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Self::Ok, Self::Error> {
+        match *resolve(self.schema, &self.root)? {
+            ref schema if is_int(schema) => {
+                zig_i32(v, self.buffer);
+                Ok(())
+            }
+            ref schema if is_long(schema) => {
+                zig_i64(i64::from(v), self.buffer);
+                Ok(())
+            }
+            Schema::Union(ref union) => self
+                .branch(union, "int", |schema| is_int(schema) || is_long(schema))?
+                .serialize_i32(v),
+            ref schema => Err(mismatch("int", schema)),
+        }
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Self::Ok, Self::Error> {
+        match *resolve(self.schema, &self.root)? {
+            ref schema if is_long(schema) => {
+                zig_i64(v, self.buffer);
+                Ok(())
+            }
+            Schema::Union(ref union) => self.branch(union, "long", is_long)?.serialize_i64(v),
+            ref schema => Err(mismatch("long", schema)),
+        }
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Self::Ok, Self::Error> {
+        self.serialize_i32(i32::from(v))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Self::Ok, Self::Error> {
+        self.serialize_i32(i32::from(v))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Self::Ok, Self::Error> {
+        match i32::try_from(v) {
+            Ok(v) => self.serialize_i32(v),
+            Err(_) => self.serialize_i64(i64::from(v)),
+        }
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Self::Ok, Self::Error> {
+        match i64::try_from(v) {
+            Ok(v) => self.serialize_i64(v),
+            Err(_) => Err(ser::Error::custom("u64 is too large")),
+        }
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Self::Ok, Self::Error> {
+        match *resolve(self.schema, &self.root)? {
+            Schema::Float => {
+                self.buffer.extend_from_slice(&v.to_le_bytes());
+                Ok(())
+            }
+            Schema::Double => self.serialize_f64(f64::from(v)),
+            Schema::Union(ref union) => self
+                .branch(union, "float", |schema| {
+                    matches!(*schema, Schema::Float | Schema::Double)
+                })?
+                .serialize_f32(v),
+            ref schema => Err(mismatch("float", schema)),
+        }
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Self::Ok, Self::Error> {
+        match *resolve(self.schema, &self.root)? {
+            Schema::Double => {
+                self.buffer.extend_from_slice(&v.to_le_bytes());
+                Ok(())
+            }
+            Schema::Union(ref union) => self
+                .branch(union, "double", |schema| matches!(*schema, Schema::Double))?
+                .serialize_f64(v),
+            ref schema => Err(mismatch("double", schema)),
+        }
+    }
+
+    fn serialize_char(self, v: char) -> Result<Self::Ok, Self::Error> {
+        self.serialize_str(&once(v).collect::<String>())
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Self::Ok, Self::Error> {
+        match *resolve(self.schema, &self.root)? {
+            Schema::String | Schema::Uuid => {
+                encode_bytes(v.as_bytes(), self.buffer);
+                Ok(())
+            }
+            Schema::Enum { ref symbols, .. } => match symbols.iter().position(|item| item == v) {
+                Some(index) => {
+                    zig_i32(index as i32, self.buffer);
+                    Ok(())
+                }
+                None => Err(Error::Ser(format!(
+                    "{} is not among allowed symbols {:?}",
+                    v, symbols
+                ))),
+            },
+            Schema::Union(ref union) => self
+                .branch(union, "string", |schema| match *schema {
+                    Schema::String | Schema::Uuid => true,
+                    _ => has_symbol(schema, v),
+                })?
+                .serialize_str(v),
+            ref schema => Err(mismatch("string", schema)),
+        }
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok, Self::Error> {
+        match *resolve(self.schema, &self.root)? {
+            Schema::Bytes => {
+                encode_bytes(v, self.buffer);
+                Ok(())
+            }
+            Schema::Fixed { size, .. } => encode_fixed(v, size, self.buffer),
+            Schema::Duration => encode_fixed(v, 12, self.buffer),
+            Schema::Decimal { ref inner, .. } => match *resolve(inner, &self.root)? {
+                Schema::Fixed { size, .. } => encode_fixed(v, size, self.buffer),
+                _ => {
+                    encode_bytes(v, self.buffer);
+                    Ok(())
+                }
+            },
+            Schema::Union(ref union) => self
+                .branch(union, "bytes", |schema| match *schema {
+                    Schema::Bytes | Schema::Decimal { .. } => true,
+                    Schema::Fixed { size, .. } => size == v.len(),
+                    Schema::Duration => v.len() == 12,
+                    _ => false,
+                })?
+                .serialize_bytes(v),
+            ref schema => Err(mismatch("bytes", schema)),
+        }
+    }
+
+    fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
+        self.serialize_unit()
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<Self::Ok, Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
+        match *resolve(self.schema, &self.root)? {
+            Schema::Null => Ok(()),
+            Schema::Union(ref union) => self
+                .branch(union, "null", |schema| matches!(*schema, Schema::Null))?
+                .serialize_unit(),
+            ref schema => Err(mismatch("null", schema)),
+        }
+    }
+
+    fn serialize_unit_struct(self, _: &'static str) -> Result<Self::Ok, Self::Error> {
+        self.serialize_unit()
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+    ) -> Result<Self::Ok, Self::Error> {
+        self.serialize_symbol(variant)
+    }
+
+    fn serialize_newtype_struct<T>(
+        self,
+        _: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        let (schema, root, buffer) = self.variant_record(variant)?;
+        value.serialize(SchemaAwareSerializer {
+            schema,
+            root,
+            buffer,
+        })
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+        match *resolve(self.schema, &self.root)? {
+            Schema::Array(ref inner) => Ok(SeqSerializer::new(inner, self.root, self.buffer, len)),
+            Schema::Union(ref union) => self
+                .branch(union, "array", |schema| matches!(*schema, Schema::Array(_)))?
+                .serialize_seq(len),
+            ref schema => Err(mismatch("array", schema)),
+        }
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, Self::Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct, Self::Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Self::Error> {
+        let (schema, root, buffer) = self.variant_record(variant)?;
+        SchemaAwareSerializer {
+            schema,
+            root,
+            buffer,
+        }
+        .serialize_seq(Some(len))
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+        match *resolve(self.schema, &self.root)? {
+            Schema::Map(ref inner) => Ok(MapSerializer::new(inner, self.root, self.buffer, len)),
+            Schema::Union(ref union) => self
+                .branch(union, "map", |schema| matches!(*schema, Schema::Map(_)))?
+                .serialize_map(len),
+            ref schema => Err(mismatch("map", schema)),
+        }
+    }
+
+    fn serialize_struct(
+        self,
+        name: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeStruct, Self::Error> {
+        self.serialize_record(name)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeStructVariant, Self::Error> {
+        let (schema, root, buffer) = self.variant_record(variant)?;
+        SchemaAwareSerializer {
+            schema,
+            root,
+            buffer,
+        }
+        .serialize_record(variant)
+    }
+}
+
+impl<'s, 'b> ser::SerializeSeq for SeqSerializer<'s, 'b> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        let buffer = match self.len {
+            Some(_) => &mut *self.buffer,
+            None => &mut self.items,
+        };
+        value.serialize(SchemaAwareSerializer {
+            schema: self.items_schema,
+            root: self.root.clone(),
+            buffer,
+        })?;
+        self.count += 1;
+        Ok(())
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        end_blocks(self.buffer, self.len, self.count, &self.items)
+    }
+}
+
+impl<'s, 'b> ser::SerializeTuple for SeqSerializer<'s, 'b> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl<'s, 'b> ser::SerializeTupleStruct for SeqSerializer<'s, 'b> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl<'s, 'b> ser::SerializeTupleVariant for SeqSerializer<'s, 'b> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl<'s, 'b> ser::SerializeMap for MapSerializer<'s, 'b> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_key<T>(&mut self, key: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        let buffer = match self.len {
+            Some(_) => &mut *self.buffer,
+            None => &mut self.items,
+        };
+        key.serialize(MapKeySerializer { buffer })
+    }
+
+    fn serialize_value<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        let buffer = match self.len {
+            Some(_) => &mut *self.buffer,
+            None => &mut self.items,
+        };
+        value.serialize(SchemaAwareSerializer {
+            schema: self.values_schema,
+            root: self.root.clone(),
+            buffer,
+        })?;
+        self.count += 1;
+        Ok(())
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        end_blocks(self.buffer, self.len, self.count, &self.items)
+    }
+}
+
+impl<'s, 'b> ser::SerializeStruct for StructSerializer<'s, 'b> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, name: &'static str, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        // Fields are written in the order they are visited, which must be the one of the schema.
+        match self.fields.get(self.position) {
+            Some(field) if field.name == name => {
+                value.serialize(SchemaAwareSerializer {
+                    schema: &field.schema,
+                    root: self.root.clone(),
+                    buffer: &mut *self.buffer,
+                })?;
+                self.position += 1;
+                Ok(())
+            }
+            Some(field) => Err(Error::Ser(format!(
+                "expected field {}, got {}",
+                field.name, name
+            ))),
+            None => Err(Error::Ser(format!("unexpected field {}", name))),
+        }
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        match self.fields.get(self.position) {
+            Some(field) => Err(Error::Ser(format!("missing field {}", field.name))),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<'s, 'b> ser::SerializeStructVariant for StructSerializer<'s, 'b> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, name: &'static str, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        ser::SerializeStruct::serialize_field(self, name, value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        ser::SerializeStruct::end(self)
+    }
+}
+
+fn key_must_be_a_string() -> Error {
+    ser::Error::custom("map key is not a string")
+}
+
+impl<'b> ser::Serializer for MapKeySerializer<'b> {
+    type Ok = ();
+    type Error = Error;
+    type SerializeSeq = ser::Impossible<(), Error>;
+    type SerializeTuple = ser::Impossible<(), Error>;
+    type SerializeTupleStruct = ser::Impossible<(), Error>;
+    type SerializeTupleVariant = ser::Impossible<(), Error>;
+    type SerializeMap = ser::Impossible<(), Error>;
+    type SerializeStruct = ser::Impossible<(), Error>;
+    type SerializeStructVariant = ser::Impossible<(), Error>;
+
+    fn serialize_str(self, v: &str) -> Result<Self::Ok, Self::Error> {
+        encode_bytes(v.as_bytes(), self.buffer);
+        Ok(())
+    }
+
+    fn serialize_char(self, v: char) -> Result<Self::Ok, Self::Error> {
+        self.serialize_str(v.encode_utf8(&mut [0; 4]))
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<Self::Ok, Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_struct<T>(
+        self,
+        _: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_bool(self, _: bool) -> Result<Self::Ok, Self::Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_i8(self, _: i8) -> Result<Self::Ok, Self::Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_i16(self, _: i16) -> Result<Self::Ok, Self::Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_i32(self, _: i32) -> Result<Self::Ok, Self::Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_i64(self, _: i64) -> Result<Self::Ok, Self::Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_u8(self, _: u8) -> Result<Self::Ok, Self::Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_u16(self, _: u16) -> Result<Self::Ok, Self::Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_u32(self, _: u32) -> Result<Self::Ok, Self::Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_u64(self, _: u64) -> Result<Self::Ok, Self::Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_f32(self, _: f32) -> Result<Self::Ok, Self::Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_f64(self, _: f64) -> Result<Self::Ok, Self::Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_bytes(self, _: &[u8]) -> Result<Self::Ok, Self::Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_unit_struct(self, _: &'static str) -> Result<Self::Ok, Self::Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+    ) -> Result<Self::Ok, Self::Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: &T,
+    ) -> Result<Self::Ok, Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_seq(self, _: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_tuple(self, _: usize) -> Result<Self::SerializeTuple, Self::Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeTupleStruct, Self::Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeTupleVariant, Self::Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_map(self, _: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_struct(
+        self,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeStruct, Self::Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeStructVariant, Self::Error> {
+        Err(key_must_be_a_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{to_avro_datum, to_value};
+    use serde::Serialize;
+    use std::collections::HashMap;
+
+    #[derive(Serialize)]
+    enum Color {
+        Red,
+        Green,
+    }
+
+    #[derive(Serialize)]
+    struct Inner {
+        z: f32,
+    }
+
+    #[derive(Serialize)]
+    struct Test {
+        a: i64,
+        b: String,
+        c: Option<i32>,
+        d: Vec<Inner>,
+        e: HashMap<String, bool>,
+        f: Color,
+        g: Option<Inner>,
+    }
+
+    const SCHEMA: &str = r#"
+    {
+        "type": "record",
+        "name": "Test",
+        "fields": [
+            {"name": "a", "type": "long"},
+            {"name": "b", "type": "string"},
+            {"name": "c", "type": ["null", "int"]},
+            {
+                "name": "d",
+                "type": {
+                    "type": "array",
+                    "items": {
+                        "type": "record",
+                        "name": "Inner",
+                        "fields": [{"name": "z", "type": "float"}]
+                    }
+                }
+            },
+            {"name": "e", "type": {"type": "map", "values": "boolean"}},
+            {"name": "f", "type": {"type": "enum", "name": "Color", "symbols": ["Red", "Green"]}},
+            {"name": "g", "type": ["null", "Inner"]}
+        ]
+    }
+    "#;
+
+    fn serialize<S: Serialize>(schema: &Schema, value: S) -> Result<Vec<u8>, Error> {
+        let mut buffer = Vec::new();
+        value.serialize(SchemaAwareSerializer::new(schema, &mut buffer))?;
+        Ok(buffer)
+    }
+
+    #[test]
+    fn test_same_encoding_as_value() {
+        let schema = Schema::parse_str(SCHEMA).unwrap();
+        let mut e = HashMap::new();
+        e.insert("key".to_string(), true);
+        let values = vec![
+            Test {
+                a: 27,
+                b: "foo".to_string(),
+                c: Some(3),
+                d: vec![Inner { z: 1.5 }, Inner { z: -2.0 }],
+                e,
+                f: Color::Green,
+                g: Some(Inner { z: 0.25 }),
+            },
+            Test {
+                a: -1,
+                b: String::new(),
+                c: None,
+                d: vec![],
+                e: HashMap::new(),
+                f: Color::Red,
+                g: None,
+            },
+        ];
+        for value in values {
+            assert_eq!(
+                serialize(&schema, &value).unwrap(),
+                to_avro_datum(&schema, to_value(&value).unwrap()).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_widening_and_union_branches() {
+        assert_eq!(serialize(&Schema::Long, 1u8).unwrap(), vec![2]);
+        assert_eq!(
+            serialize(&Schema::Double, 0.5f32).unwrap(),
+            0.5f64.to_le_bytes().to_vec()
+        );
+
+        let union = Schema::parse_str(r#"["null", "string", "long"]"#).unwrap();
+        assert_eq!(serialize(&union, ()).unwrap(), vec![0]);
+        assert_eq!(serialize(&union, "a").unwrap(), vec![2, 2, 97]);
+        assert_eq!(serialize(&union, Some(3i32)).unwrap(), vec![4, 6]);
+    }
+
+    #[test]
+    fn test_mismatches() {
+        let schema = Schema::parse_str(SCHEMA).unwrap();
+        let map = Schema::parse_str(r#"{"type": "map", "values": "boolean"}"#).unwrap();
+        let mut int_keys = HashMap::new();
+        int_keys.insert(1i32, true);
+        let cases = vec![
+            serialize(&Schema::Int, 1i64),
+            serialize(&Schema::String, 1i32),
+            serialize(&Schema::parse_str(r#"["null", "int"]"#).unwrap(), "a"),
+            serialize(&schema, Inner { z: 1.0 }),
+            serialize(&schema, HashMap::<String, i32>::new()),
+            serialize(&map, int_keys),
+        ];
+        for result in cases {
+            match result {
+                Err(Error::Ser(_)) => {}
+                other => panic!("expected a serialization error, got {:?}", other),
+            }
+        }
+    }
+}
@@ -0,0 +1,37 @@
+//! Fixtures shared by the tests of the data file readers and writers.
+use crate::schema::Schema;
+use crate::types::{Record, Value};
+use crate::{Codec, Writer};
+
+pub(crate) const SCHEMA: &str = r#"
+{
+  "type": "record",
+  "name": "test",
+  "fields": [
+    {"name": "a", "type": "long", "default": 42},
+    {"name": "b", "type": "string"}
+  ]
+}
+"#;
+
+/// The record of `SCHEMA` numbered `a`.
+pub(crate) fn record(schema: &Schema, a: i64) -> Value {
+    let mut record = Record::new(schema).unwrap();
+    record.put("a", a);
+    record.put("b", format!("foo{}", a));
+    record.into()
+}
+
+/// A data file of `count` records, written in blocks of a few records each.
+pub(crate) fn write_blocks(schema: &Schema, codec: Codec, count: i64) -> Vec<u8> {
+    let mut writer = Writer::builder()
+        .schema(schema)
+        .writer(Vec::new())
+        .codec(codec)
+        .block_size(64)
+        .build();
+    for a in 0..count {
+        writer.append(record(schema, a)).unwrap();
+    }
+    writer.into_inner().unwrap()
+}
@@ -11,7 +11,7 @@ use uuid::Uuid;
 use crate::decimal::Decimal;
 use crate::duration::Duration;
 use crate::errors::{AvroResult, Error};
-use crate::schema::{Precision, RecordField, Scale, Schema, SchemaKind, UnionSchema};
+use crate::schema::{Precision, RecordField, Root, Scale, Schema, SchemaKind, UnionSchema};
 
 /// Compute the maximum decimal value precision of a byte array of length `len` could hold.
 fn max_prec_for_len(len: usize) -> Result<usize, std::num::TryFromIntError> {
@@ -176,6 +176,98 @@ where
     }
 }
 
+/// An Avro value borrowing its strings and bytes from the data it was decoded from, and its enum
+/// symbols and record field names from its `Schema`.
+///
+/// It is produced by [`Reader::next_ref`](../struct.Reader.html#method.next_ref), and can be
+/// turned into an owned [`Value`](enum.Value.html) with `Value::from`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ValueRef<'a> {
+    /// A `null` Avro value.
+    Null,
+    /// A `boolean` Avro value.
+    Boolean(bool),
+    /// A `int` Avro value.
+    Int(i32),
+    /// A `long` Avro value.
+    Long(i64),
+    /// A `float` Avro value.
+    Float(f32),
+    /// A `double` Avro value.
+    Double(f64),
+    /// A `bytes` Avro value.
+    Bytes(&'a [u8]),
+    /// A `string` Avro value.
+    String(&'a str),
+    /// A `fixed` Avro value.
+    Fixed(usize, &'a [u8]),
+    /// An `enum` Avro value, represented by its position in the symbols list and its symbol.
+    Enum(i32, &'a str),
+    /// An `union` Avro value.
+    Union(Box<ValueRef<'a>>),
+    /// An `array` Avro value.
+    Array(Vec<ValueRef<'a>>),
+    /// A `map` Avro value, represented by its entries in the order they were decoded.
+    Map(Vec<(&'a str, ValueRef<'a>)>),
+    /// A `record` Avro value, represented by a vector of (`<field name>`, `value`).
+    Record(Vec<(&'a str, ValueRef<'a>)>),
+    /// A date value.
+    Date(i32),
+    /// An Avro Decimal value, represented by its two's-complement bytes in big-endian order.
+    Decimal(&'a [u8]),
+    /// Time in milliseconds.
+    TimeMillis(i32),
+    /// Time in microseconds.
+    TimeMicros(i64),
+    /// Timestamp in milliseconds.
+    TimestampMillis(i64),
+    /// Timestamp in microseconds.
+    TimestampMicros(i64),
+    /// Avro Duration. An amount of time defined by months, days and milliseconds.
+    Duration(Duration),
+    /// Universally unique identifier.
+    Uuid(Uuid),
+}
+
+impl<'a> From<ValueRef<'a>> for Value {
+    fn from(value: ValueRef<'a>) -> Self {
+        match value {
+            ValueRef::Null => Value::Null,
+            ValueRef::Boolean(b) => Value::Boolean(b),
+            ValueRef::Int(i) => Value::Int(i),
+            ValueRef::Long(i) => Value::Long(i),
+            ValueRef::Float(x) => Value::Float(x),
+            ValueRef::Double(x) => Value::Double(x),
+            ValueRef::Bytes(bytes) => Value::Bytes(bytes.to_vec()),
+            ValueRef::String(s) => Value::String(s.to_string()),
+            ValueRef::Fixed(size, bytes) => Value::Fixed(size, bytes.to_vec()),
+            ValueRef::Enum(index, symbol) => Value::Enum(index, symbol.to_string()),
+            ValueRef::Union(item) => Value::Union(Box::new(Value::from(*item))),
+            ValueRef::Array(items) => Value::Array(items.into_iter().map(Value::from).collect()),
+            ValueRef::Map(items) => Value::Map(
+                items
+                    .into_iter()
+                    .map(|(key, value)| (key.to_string(), Value::from(value)))
+                    .collect(),
+            ),
+            ValueRef::Record(fields) => Value::Record(
+                fields
+                    .into_iter()
+                    .map(|(name, value)| (name.to_string(), Value::from(value)))
+                    .collect(),
+            ),
+            ValueRef::Date(d) => Value::Date(d),
+            ValueRef::Decimal(bytes) => Value::Decimal(Decimal::from(bytes.to_vec())),
+            ValueRef::TimeMillis(t) => Value::TimeMillis(t),
+            ValueRef::TimeMicros(t) => Value::TimeMicros(t),
+            ValueRef::TimestampMillis(t) => Value::TimestampMillis(t),
+            ValueRef::TimestampMicros(t) => Value::TimestampMicros(t),
+            ValueRef::Duration(duration) => Value::Duration(duration),
+            ValueRef::Uuid(uuid) => Value::Uuid(uuid),
+        }
+    }
+}
+
 /// Utility interface to build `Value::Record` objects.
 #[derive(Debug, Clone)]
 pub struct Record<'a> {
@@ -258,7 +350,18 @@ impl Value {
     /// See the [Avro specification](https://avro.apache.org/docs/current/spec.html)
     /// for the full set of rules of schema validation.
     pub fn validate(&self, schema: &Schema) -> bool {
+        self.validate_internal(schema, &Root::new(schema))
+    }
+
+    /// Validate the value against the given `Schema`, following references to named types
+    /// through `root`, the top-level `Schema` it is part of.
+    pub(crate) fn validate_internal(&self, schema: &Schema, root: &Root) -> bool {
         match (self, schema) {
+            (_, Schema::Ref { name }) => root
+                .lookup_named(&name.fullname(None))
+                .map(|schema| self.validate_internal(schema, root))
+                .unwrap_or(false),
+            (_, Schema::Annotated { inner, .. }) => self.validate_internal(inner, root),
             (&Value::Null, &Schema::Null) => true,
             (&Value::Boolean(_), &Schema::Boolean) => true,
             (&Value::Int(_), &Schema::Int) => true,
@@ -293,19 +396,19 @@ impl Value {
                 .unwrap_or(false),
             // (&Value::Union(None), &Schema::Union(_)) => true,
             (&Value::Union(ref value), &Schema::Union(ref inner)) => {
-                inner.find_schema(value).is_some()
+                inner.find_schema_with_root(value, root).is_some()
             }
             (&Value::Array(ref items), &Schema::Array(ref inner)) => {
-                items.iter().all(|item| item.validate(inner))
-            }
-            (&Value::Map(ref items), &Schema::Map(ref inner)) => {
-                items.iter().all(|(_, value)| value.validate(inner))
+                items.iter().all(|item| item.validate_internal(inner, root))
             }
+            (&Value::Map(ref items), &Schema::Map(ref inner)) => items
+                .iter()
+                .all(|(_, value)| value.validate_internal(inner, root)),
             (&Value::Record(ref record_fields), &Schema::Record { ref fields, .. }) => {
                 fields.len() == record_fields.len()
                     && fields.iter().zip(record_fields.iter()).all(
                         |(field, &(ref name, ref value))| {
-                            field.name == *name && value.validate(&field.schema)
+                            field.name == *name && value.validate_internal(&field.schema, root)
                         },
                     )
             }
@@ -319,10 +422,16 @@ impl Value {
     /// See [Schema Resolution](https://avro.apache.org/docs/current/spec.html#Schema+Resolution)
     /// in the Avro specification for the full set of rules of schema
     /// resolution.
-    pub fn resolve(mut self, schema: &Schema) -> AvroResult<Self> {
+    pub fn resolve(self, schema: &Schema) -> AvroResult<Self> {
+        self.resolve_internal(schema, &Root::new(schema))
+    }
+
+    /// Perform schema resolution on the value against the given `Schema`, following references
+    /// to named types through `root`, the top-level reader `Schema` it is part of.
+    fn resolve_internal(mut self, schema: &Schema, root: &Root) -> AvroResult<Self> {
         // Check if this schema is a union, and if the reader schema is not.
         if SchemaKind::from(&self) == SchemaKind::Union
-            && SchemaKind::from(schema) != SchemaKind::Union
+            && SchemaKind::from(schema.unannotated()) != SchemaKind::Union
         {
             // Pull out the Union, and attempt to resolve against it.
             let v = match self {
@@ -341,11 +450,15 @@ impl Value {
             Schema::Bytes => self.resolve_bytes(),
             Schema::String => self.resolve_string(),
             Schema::Fixed { size, .. } => self.resolve_fixed(size),
-            Schema::Union(ref inner) => self.resolve_union(inner),
-            Schema::Enum { ref symbols, .. } => self.resolve_enum(symbols),
-            Schema::Array(ref inner) => self.resolve_array(inner),
-            Schema::Map(ref inner) => self.resolve_map(inner),
-            Schema::Record { ref fields, .. } => self.resolve_record(fields),
+            Schema::Union(ref inner) => self.resolve_union(inner, root),
+            Schema::Enum {
+                ref symbols,
+                ref default,
+                ..
+            } => self.resolve_enum(symbols, default.as_ref()),
+            Schema::Array(ref inner) => self.resolve_array(inner, root),
+            Schema::Map(ref inner) => self.resolve_map(inner, root),
+            Schema::Record { ref fields, .. } => self.resolve_record(fields, root),
             Schema::Decimal {
                 scale,
                 precision,
@@ -358,6 +471,14 @@ impl Value {
             Schema::TimestampMicros => self.resolve_timestamp_micros(),
             Schema::Duration => self.resolve_duration(),
             Schema::Uuid => self.resolve_uuid(),
+            Schema::Ref { ref name } => {
+                let fullname = name.fullname(None);
+                let schema = root.lookup_named(&fullname).ok_or_else(|| {
+                    Error::SchemaResolution(format!("Unknown reference to named type {}", fullname))
+                })?;
+                self.resolve_internal(schema, root)
+            }
+            Schema::Annotated { ref inner, .. } => self.resolve_internal(inner, root),
         }
     }
 
@@ -623,10 +744,14 @@ impl Value {
         }
     }
 
-    fn resolve_enum(self, symbols: &[String]) -> AvroResult<Self> {
+    fn resolve_enum(self, symbols: &[String], default: Option<&String>) -> AvroResult<Self> {
         let validate_symbol = |symbol: String, symbols: &[String]| {
             if let Some(index) = symbols.iter().position(|ref item| item == &&symbol) {
                 Ok(Value::Enum(index as i32, symbol))
+            } else if let Some(default) = default {
+                // The default is always among the symbols, this is checked when parsing.
+                let index = symbols.iter().position(|item| item == default).unwrap_or(0);
+                Ok(Value::Enum(index as i32, default.clone()))
             } else {
                 Err(Error::SchemaResolution(format!(
                     "Enum default {} is not among allowed symbols {:?}",
@@ -636,17 +761,9 @@ impl Value {
         };
 
         match self {
-            Value::Enum(i, s) => {
-                if i >= 0 && i < symbols.len() as i32 {
-                    validate_symbol(s, symbols)
-                } else {
-                    Err(Error::SchemaResolution(format!(
-                        "Enum value {} is out of bound {}",
-                        i,
-                        symbols.len() as i32
-                    )))
-                }
-            }
+            // The index refers to the symbols of the writer's schema, so only the symbol itself
+            // is resolved.
+            Value::Enum(_, s) => validate_symbol(s, symbols),
             Value::String(s) => validate_symbol(s, symbols),
             other => Err(Error::SchemaResolution(format!(
                 "Enum({:?}) expected, got {:?}",
@@ -655,7 +772,7 @@ impl Value {
         }
     }
 
-    fn resolve_union(self, schema: &UnionSchema) -> AvroResult<Self> {
+    fn resolve_union(self, schema: &UnionSchema, root: &Root) -> AvroResult<Self> {
         let v = match self {
             // Both are unions case.
             Value::Union(v) => *v,
@@ -663,18 +780,27 @@ impl Value {
             v => v,
         };
         // Find the first match in the reader schema.
-        let (_, inner) = schema.find_schema(&v).ok_or_else(|| {
-            Error::SchemaResolution("Could not find matching type in union".to_string())
-        })?;
-        Ok(Value::Union(Box::new(v.resolve(inner)?)))
+        if let Some((_, inner)) = schema.find_schema(&v) {
+            return Ok(Value::Union(Box::new(v.resolve_internal(inner, root)?)));
+        }
+        // Otherwise, pick the first named type the value can be resolved against.
+        schema
+            .variants()
+            .iter()
+            .filter(|variant| SchemaKind::from(variant.unannotated()) == SchemaKind::Ref)
+            .find_map(|variant| v.clone().resolve_internal(variant, root).ok())
+            .map(|value| Value::Union(Box::new(value)))
+            .ok_or_else(|| {
+                Error::SchemaResolution("Could not find matching type in union".to_string())
+            })
     }
 
-    fn resolve_array(self, schema: &Schema) -> AvroResult<Self> {
+    fn resolve_array(self, schema: &Schema, root: &Root) -> AvroResult<Self> {
         match self {
             Value::Array(items) => Ok(Value::Array(
                 items
                     .into_iter()
-                    .map(|item| item.resolve(schema))
+                    .map(|item| item.resolve_internal(schema, root))
                     .collect::<Result<_, _>>()?,
             )),
             other => Err(Error::SchemaResolution(format!(
@@ -684,12 +810,16 @@ impl Value {
         }
     }
 
-    fn resolve_map(self, schema: &Schema) -> AvroResult<Self> {
+    fn resolve_map(self, schema: &Schema, root: &Root) -> AvroResult<Self> {
         match self {
             Value::Map(items) => Ok(Value::Map(
                 items
                     .into_iter()
-                    .map(|(key, value)| value.resolve(schema).map(|value| (key, value)))
+                    .map(|(key, value)| {
+                        value
+                            .resolve_internal(schema, root)
+                            .map(|value| (key, value))
+                    })
                     .collect::<Result<_, _>>()?,
             )),
             other => Err(Error::SchemaResolution(format!(
@@ -699,7 +829,7 @@ impl Value {
         }
     }
 
-    fn resolve_record(self, fields: &[RecordField]) -> AvroResult<Self> {
+    fn resolve_record(self, fields: &[RecordField], root: &Root) -> AvroResult<Self> {
         let mut items = match self {
             Value::Map(items) => Ok(items),
             Value::Record(fields) => Ok(fields.into_iter().collect::<HashMap<_, _>>()),
@@ -712,13 +842,23 @@ impl Value {
         let new_fields = fields
             .iter()
             .map(|field| {
-                let value = match items.remove(&field.name) {
+                let value = items.remove(&field.name).or_else(|| {
+                    field
+                        .aliases
+                        .iter()
+                        .flatten()
+                        .find_map(|alias| items.remove(alias))
+                });
+                let value = match value {
                     Some(value) => value,
                     None => match field.default {
                         Some(ref value) => match field.schema {
-                            Schema::Enum { ref symbols, .. } => {
-                                Value::from(value.clone()).resolve_enum(symbols)?
-                            }
+                            Schema::Enum {
+                                ref symbols,
+                                ref default,
+                                ..
+                            } => Value::from(value.clone())
+                                .resolve_enum(symbols, default.as_ref())?,
                             Schema::Union(ref union_schema) => {
                                 let first = &union_schema.variants()[0];
                                 // NOTE: this match exists only to optimize null defaults for large
@@ -726,7 +866,7 @@ impl Value {
                                 match first {
                                     Schema::Null => Value::Union(Box::new(Value::Null)),
                                     _ => Value::Union(Box::new(
-                                        Value::from(value.clone()).resolve(first)?,
+                                        Value::from(value.clone()).resolve_internal(first, root)?,
                                     )),
                                 }
                             }
@@ -741,7 +881,7 @@ impl Value {
                     },
                 };
                 value
-                    .resolve(&field.schema)
+                    .resolve_internal(&field.schema, root)
                     .map(|value| (field.name.clone(), value))
             })
             .collect::<Result<Vec<_>, _>>()?;
@@ -828,6 +968,7 @@ mod tests {
         let schema = Schema::Fixed {
             size: 4,
             name: Name::new("some_fixed"),
+            custom_attributes: Default::default(),
         };
 
         assert!(Value::Fixed(4, vec![0, 0, 0, 0]).validate(&schema));
@@ -845,6 +986,8 @@ mod tests {
                 "diamonds".to_string(),
                 "clubs".to_string(),
             ],
+            default: None,
+            custom_attributes: Default::default(),
         };
 
         assert!(Value::Enum(0, "spades".to_string()).validate(&schema));
@@ -862,6 +1005,8 @@ mod tests {
                 "clubs".to_string(),
                 "spades".to_string(),
             ],
+            default: None,
+            custom_attributes: Default::default(),
         };
 
         assert!(!Value::Enum(0, "spades".to_string()).validate(&other_schema));
@@ -885,20 +1030,25 @@ mod tests {
                     name: "a".to_string(),
                     doc: None,
                     default: None,
+                    aliases: None,
                     schema: Schema::Long,
                     order: RecordFieldOrder::Ascending,
                     position: 0,
+                    custom_attributes: Default::default(),
                 },
                 RecordField {
                     name: "b".to_string(),
                     doc: None,
                     default: None,
+                    aliases: None,
                     schema: Schema::String,
                     order: RecordFieldOrder::Ascending,
                     position: 1,
+                    custom_attributes: Default::default(),
                 },
             ],
             lookup: HashMap::new(),
+            custom_attributes: Default::default(),
         };
 
         assert!(Value::Record(vec![
@@ -933,6 +1083,54 @@ mod tests {
         .validate(&schema));
     }
 
+    #[test]
+    fn resolve_recursive_record() {
+        let writer_schema = Schema::parse_str(
+            r#"{"type": "record", "name": "List", "fields": [
+                {"name": "value", "type": "int"},
+                {"name": "next", "type": ["null", "List"]}
+            ]}"#,
+        )
+        .unwrap();
+        let reader_schema = Schema::parse_str(
+            r#"{"type": "record", "name": "List", "fields": [
+                {"name": "value", "type": "long"},
+                {"name": "next", "type": ["null", "List"]},
+                {"name": "label", "type": "string", "default": "none"}
+            ]}"#,
+        )
+        .unwrap();
+
+        let value = Value::Record(vec![
+            ("value".to_string(), Value::Int(1)),
+            (
+                "next".to_string(),
+                Value::Union(Box::new(Value::Record(vec![
+                    ("value".to_string(), Value::Int(2)),
+                    ("next".to_string(), Value::Union(Box::new(Value::Null))),
+                ]))),
+            ),
+        ]);
+        assert!(value.validate(&writer_schema));
+
+        let resolved = value.resolve(&reader_schema).unwrap();
+        assert_eq!(
+            resolved,
+            Value::Record(vec![
+                ("value".to_string(), Value::Long(1)),
+                (
+                    "next".to_string(),
+                    Value::Union(Box::new(Value::Record(vec![
+                        ("value".to_string(), Value::Long(2)),
+                        ("next".to_string(), Value::Union(Box::new(Value::Null))),
+                        ("label".to_string(), Value::String("none".to_string())),
+                    ]))),
+                ),
+                ("label".to_string(), Value::String("none".to_string())),
+            ])
+        );
+    }
+
     #[test]
     fn resolve_bytes_ok() {
         let value = Value::Array(vec![Value::Int(0), Value::Int(42)]);
@@ -996,7 +1194,8 @@ mod tests {
                 scale: 1,
                 inner: Box::new(Schema::Fixed {
                     name: Name::new("decimal"),
-                    size: 20
+                    size: 20,
+                    custom_attributes: Default::default(),
                 })
             })
             .is_ok());
@@ -1,19 +1,23 @@
 //! Logic handling writing in Avro format at user level.
 use std::collections::HashMap;
-use std::io::Write;
+use std::io::{Read, Seek, SeekFrom, Write};
 
 use rand::random;
 use serde::Serialize;
 
 use crate::encode::{encode, encode_ref, encode_to_vec};
 use crate::errors::{AvroResult, Error};
+use crate::rabin::Rabin;
+use crate::reader::{read_header, BlockReader, RawBlock};
 use crate::schema::Schema;
 use crate::ser::Serializer;
+use crate::ser_schema::SchemaAwareSerializer;
 use crate::types::Value;
-use crate::Codec;
+use crate::{Codec, CodecOptions};
 
 const DEFAULT_BLOCK_SIZE: usize = 16000;
 const AVRO_OBJECT_HEADER: &[u8] = b"Obj\x01";
+pub(crate) const SINGLE_OBJECT_MARKER: [u8; 2] = [0xC3, 0x01];
 
 /// Main interface for writing Avro formatted values.
 #[derive(typed_builder::TypedBuilder)]
@@ -22,6 +26,8 @@ pub struct Writer<'a, W> {
     writer: W,
     #[builder(default = Codec::Null)]
     codec: Codec,
+    #[builder(default)]
+    codec_options: CodecOptions,
     #[builder(default = DEFAULT_BLOCK_SIZE)]
     block_size: usize,
     #[builder(default = Vec::with_capacity(block_size), setter(skip))]
@@ -34,6 +40,8 @@ pub struct Writer<'a, W> {
     marker: Vec<u8>,
     #[builder(default = false, setter(skip))]
     has_header: bool,
+    #[builder(default, setter(skip))]
+    user_metadata: HashMap<String, Value>,
 }
 
 impl<'a, W: Write> Writer<'a, W> {
@@ -59,6 +67,22 @@ impl<'a, W: Write> Writer<'a, W> {
         self.schema
     }
 
+    /// Add user metadata to the header of the file, under `key`, which must not start with the
+    /// `avro.` prefix reserved for the metadata of the specification.
+    ///
+    /// **NOTE** This function fails once the header has been written, upon the first write.
+    pub fn add_user_metadata<T: AsRef<[u8]>>(&mut self, key: String, value: T) -> AvroResult<()> {
+        if self.has_header {
+            return Err(Error::FileHeaderAlreadyWritten);
+        }
+        if key.starts_with("avro.") {
+            return Err(Error::InvalidMetadataKey(key));
+        }
+        self.user_metadata
+            .insert(key, Value::Bytes(value.as_ref().to_vec()));
+        Ok(())
+    }
+
     /// Append a compatible value (implementing the `ToAvro` trait) to a `Writer`, also performing
     /// schema validation.
     ///
@@ -131,6 +155,41 @@ impl<'a, W: Write> Writer<'a, W> {
         self.append(avro_value)
     }
 
+    /// Append anything implementing the `Serialize` trait to a `Writer`, encoding it straight into
+    /// Avro format under the guidance of the `Schema`, instead of going through an intermediate
+    /// `Value` like [`append_ser`](struct.Writer.html#method.append_ser) does.
+    ///
+    /// Return the number of bytes written (it might be 0, see below).
+    ///
+    /// **NOTE** This function is not guaranteed to perform any actual write, since it relies on
+    /// internal buffering for performance reasons. If you want to be sure the value has been
+    /// written, then call [`flush`](struct.Writer.html#method.flush).
+    pub fn append_ser_direct<S: Serialize>(&mut self, value: S) -> AvroResult<usize> {
+        let n = if !self.has_header {
+            let header = self.header()?;
+            let n = self.append_bytes(header.as_ref())?;
+            self.has_header = true;
+            n
+        } else {
+            0
+        };
+
+        let len = self.buffer.len();
+        if let Err(e) = value.serialize(SchemaAwareSerializer::new(self.schema, &mut self.buffer)) {
+            // Do not leave a partially serialized value behind.
+            self.buffer.truncate(len);
+            return Err(e);
+        }
+
+        self.num_values += 1;
+
+        if self.buffer.len() >= self.block_size {
+            return self.flush().map(|b| b + n);
+        }
+
+        Ok(n)
+    }
+
     /// Extend a `Writer` with an `Iterator` of compatible values (implementing the `ToAvro`
     /// trait), also performing schema validation.
     ///
@@ -217,6 +276,63 @@ impl<'a, W: Write> Writer<'a, W> {
         Ok(num_bytes)
     }
 
+    /// Append a data block read as is by a `BlockReader`, re-marked with the sync marker of the
+    /// `Writer`, after flushing the values appended so far.
+    ///
+    /// Return the number of bytes written.
+    ///
+    /// This is the equivalent of `DataFileWriter.appendAllFrom` in Java, one block at a time.
+    ///
+    /// **NOTE** The block is copied verbatim, without any check: the caller must make sure that
+    /// it was read from a data file with the same `Schema` as the `Writer`, and that its values
+    /// are compressed with the `Codec` of the `Writer`, or the resulting file cannot be read.
+    /// [`append_blocks`](struct.Writer.html#method.append_blocks) checks both for every block of a
+    /// `BlockReader`.
+    pub fn append_raw_block(&mut self, block: &RawBlock) -> AvroResult<usize> {
+        let mut n = if !self.has_header {
+            let header = self.header()?;
+            let n = self.append_bytes(header.as_ref())?;
+            self.has_header = true;
+            n
+        } else {
+            0
+        };
+        n += self.flush()?;
+
+        n += self.append_raw(&block.record_count.into(), &Schema::Long)?
+            + self.append_raw(&block.data.len().into(), &Schema::Long)?;
+        self.writer.write_all(&block.data)?;
+        n += block.data.len() + self.append_marker()?;
+
+        Ok(n)
+    }
+
+    /// Append all the data blocks of another data file without decoding their values, which is
+    /// much faster than appending them one by one. The blocks are recompressed if the data file
+    /// uses another `Codec` than the `Writer`.
+    ///
+    /// Return the number of bytes written.
+    ///
+    /// **NOTE** The data file must have the same `Schema` as the `Writer`.
+    pub fn append_blocks<R: Read>(&mut self, blocks: BlockReader<R>) -> AvroResult<usize> {
+        if blocks.writer_schema() != self.schema {
+            return Err(Error::FileSchemaMismatch);
+        }
+        let codec = blocks.codec();
+
+        let mut num_bytes = 0;
+        for block in blocks {
+            let mut block = block?;
+            if codec != self.codec {
+                codec.decompress(&mut block.data)?;
+                self.codec
+                    .compress_with(&mut block.data, &self.codec_options)?;
+            }
+            num_bytes += self.append_raw_block(&block)?;
+        }
+        Ok(num_bytes)
+    }
+
     /// Flush the content appended to a `Writer`. Call this function to make sure all the content
     /// has been written before releasing the `Writer`.
     ///
@@ -226,7 +342,8 @@ impl<'a, W: Write> Writer<'a, W> {
             return Ok(0);
         }
 
-        self.codec.compress(&mut self.buffer)?;
+        self.codec
+            .compress_with(&mut self.buffer, &self.codec_options)?;
 
         let num_values = self.num_values;
         let stream_len = self.buffer.len();
@@ -251,6 +368,12 @@ impl<'a, W: Write> Writer<'a, W> {
         Ok(self.writer)
     }
 
+    /// Get a mutable reference to what the `Writer` is writing to.
+    #[cfg(feature = "async")]
+    pub(crate) fn get_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+
     /// Generate and append synchronization marker to the payload.
     fn append_marker(&mut self) -> AvroResult<usize> {
         // using .writer.write directly to avoid mutable borrow of self
@@ -260,7 +383,7 @@ impl<'a, W: Write> Writer<'a, W> {
 
     /// Append a raw Avro Value to the payload avoiding to encode it again.
     fn append_raw(&mut self, value: &Value, schema: &Schema) -> AvroResult<usize> {
-        self.append_bytes(encode_to_vec(&value, schema).as_ref())
+        self.append_bytes(encode_to_vec(&value, schema)?.as_ref())
     }
 
     /// Append pure bytes to the payload.
@@ -272,9 +395,10 @@ impl<'a, W: Write> Writer<'a, W> {
     fn header(&self) -> AvroResult<Vec<u8>> {
         let schema_bytes = serde_json::to_string(self.schema)?.into_bytes();
 
-        let mut metadata = HashMap::with_capacity(2);
-        metadata.insert("avro.schema", Value::Bytes(schema_bytes));
-        metadata.insert("avro.codec", self.codec.into());
+        let mut metadata = HashMap::with_capacity(2 + self.user_metadata.len());
+        metadata.insert("avro.schema".to_string(), Value::Bytes(schema_bytes));
+        metadata.insert("avro.codec".to_string(), self.codec.into());
+        metadata.extend(self.user_metadata.clone());
 
         let mut header = Vec::new();
         header.extend_from_slice(AVRO_OBJECT_HEADER);
@@ -282,13 +406,59 @@ impl<'a, W: Write> Writer<'a, W> {
             &metadata.into(),
             &Schema::Map(Box::new(Schema::Bytes)),
             &mut header,
-        );
+        )?;
         header.extend_from_slice(&self.marker);
 
         Ok(header)
     }
 }
 
+impl<'a, W: Read + Write + Seek> Writer<'a, W> {
+    /// Creates a `Writer` appending to an existing data file, given something implementing the
+    /// `io::Read`, `io::Write` and `io::Seek` traits positioned at its start.
+    ///
+    /// The header of the file is read to reuse its `Codec` and sync marker, and its `Schema` must
+    /// be equal to `schema`, or `Error::FileSchemaMismatch` is returned. When it is not known, it
+    /// can be read beforehand with [`file_schema`](#method.file_schema). New blocks are then
+    /// written after the end of the file.
+    ///
+    /// ```
+    /// # use avro_rs::{Schema, Writer};
+    /// # use std::io::Cursor;
+    /// # let schema = Schema::parse_str(r#""long""#).unwrap();
+    /// # let mut writer = Writer::new(&schema, Cursor::new(Vec::new()));
+    /// # writer.append(1i64).unwrap();
+    /// # let mut file = writer.into_inner().unwrap();
+    /// # file.set_position(0);
+    /// let schema = Writer::file_schema(&mut file).unwrap();
+    /// let mut writer = Writer::append_to(&schema, file).unwrap();
+    /// writer.append(2i64).unwrap();
+    /// writer.flush().unwrap();
+    /// ```
+    pub fn append_to(schema: &'a Schema, mut writer: W) -> AvroResult<Self> {
+        let header = read_header(&mut writer)?;
+        if header.schema != *schema {
+            return Err(Error::FileSchemaMismatch);
+        }
+        writer.seek(SeekFrom::End(0))?;
+
+        let mut writer = Self::with_codec(schema, writer, header.codec);
+        writer.marker = header.marker.to_vec();
+        writer.has_header = true;
+        Ok(writer)
+    }
+
+    /// Read the `Schema` of an existing data file, given something implementing the `io::Read`,
+    /// `io::Write` and `io::Seek` traits positioned at its start, where it is left to be given to
+    /// [`append_to`](#method.append_to).
+    pub fn file_schema(writer: &mut W) -> AvroResult<Schema> {
+        let start = writer.stream_position()?;
+        let header = read_header(writer)?;
+        writer.seek(SeekFrom::Start(start))?;
+        Ok(header.schema)
+    }
+}
+
 /// Encode a compatible value (implementing the `ToAvro` trait) into Avro format, also performing
 /// schema validation.
 ///
@@ -303,16 +473,14 @@ fn write_avro_datum<T: Into<Value>>(
     if !avro.validate(schema) {
         return Err(Error::Validation("value does not match schema".to_string()));
     }
-    encode(&avro, schema, buffer);
-    Ok(())
+    encode(&avro, schema, buffer)
 }
 
 fn write_value_ref(schema: &Schema, value: &Value, buffer: &mut Vec<u8>) -> AvroResult<()> {
     if !value.validate(schema) {
         return Err(Error::Validation("value does not match schema".to_string()));
     }
-    encode_ref(value, schema, buffer);
-    Ok(())
+    encode_ref(value, schema, buffer)
 }
 
 /// Encode a compatible value (implementing the `ToAvro` trait) into Avro format, also
@@ -327,12 +495,90 @@ pub fn to_avro_datum<T: Into<Value>>(schema: &Schema, value: T) -> AvroResult<Ve
     Ok(buffer)
 }
 
+/// Encode anything implementing the `Serialize` trait into Avro format, straight under the
+/// guidance of `schema` instead of going through an intermediate `Value`.
+///
+/// Values not matching the schema are reported as `Error::Ser`.
+pub fn to_avro_datum_ser<S: Serialize>(schema: &Schema, value: S) -> AvroResult<Vec<u8>> {
+    let mut buffer = Vec::new();
+    value.serialize(SchemaAwareSerializer::new(schema, &mut buffer))?;
+    Ok(buffer)
+}
+
+/// Writer for the [single-object encoding] of Avro data, where each datum is preceded by a marker
+/// and the fingerprint of its schema.
+///
+/// It is meant for standalone messages, such as those sent over a message bus, and is compatible
+/// with the `BinaryMessageEncoder` of the Java implementation.
+///
+/// [single-object encoding]: https://avro.apache.org/docs/current/spec.html#single_object_encoding
+pub struct SingleObjectWriter<'a> {
+    schema: &'a Schema,
+    header: Vec<u8>,
+    serializer: Serializer,
+}
+
+impl<'a> SingleObjectWriter<'a> {
+    /// Create a `SingleObjectWriter` for data of the given `Schema`.
+    pub fn new(schema: &'a Schema) -> Self {
+        let mut header = SINGLE_OBJECT_MARKER.to_vec();
+        header.extend_from_slice(&Rabin::fingerprint64(schema).to_le_bytes());
+        SingleObjectWriter {
+            schema,
+            header,
+            serializer: Serializer::default(),
+        }
+    }
+
+    /// Get a reference to the `Schema` associated to a `SingleObjectWriter`.
+    pub fn schema(&self) -> &'a Schema {
+        self.schema
+    }
+
+    /// Write a compatible value (implementing the `ToAvro` trait) in the single-object encoding,
+    /// also performing schema validation.
+    ///
+    /// Return the number of bytes written.
+    pub fn write_value<T: Into<Value>, W: Write>(
+        &self,
+        value: T,
+        writer: &mut W,
+    ) -> AvroResult<usize> {
+        let mut buffer = self.header.clone();
+        write_avro_datum(self.schema, value, &mut buffer)?;
+        writer.write_all(&buffer)?;
+        Ok(buffer.len())
+    }
+
+    /// Write a value implementing `Serialize` in the single-object encoding, also performing
+    /// schema validation.
+    ///
+    /// Return the number of bytes written.
+    pub fn write_ser<S: Serialize, W: Write>(
+        &mut self,
+        value: S,
+        writer: &mut W,
+    ) -> AvroResult<usize> {
+        let avro_value = value.serialize(&mut self.serializer)?;
+        self.write_value(avro_value, writer)
+    }
+
+    /// Encode a compatible value (implementing the `ToAvro` trait) in the single-object encoding,
+    /// also performing schema validation.
+    pub fn to_vec<T: Into<Value>>(&self, value: T) -> AvroResult<Vec<u8>> {
+        let mut buffer = Vec::new();
+        self.write_value(value, &mut buffer)?;
+        Ok(buffer)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::decimal::Decimal;
     use crate::duration::{Days, Duration, Millis, Months};
     use crate::schema::Name;
+    use crate::test_util;
     use crate::types::Record;
     use crate::util::zig_i64;
     use serde::{Deserialize, Serialize};
@@ -396,6 +642,24 @@ mod tests {
         assert_eq!(to_avro_datum(&schema, union).unwrap(), expected);
     }
 
+    #[test]
+    fn test_single_object_writer() {
+        let writer = SingleObjectWriter::new(&Schema::Long);
+
+        let mut expected = vec![0xC3, 0x01];
+        // CRC-64-AVRO fingerprint of "long"
+        expected.extend_from_slice(&(-3_434_872_931_120_570_953i64).to_le_bytes());
+        zig_i64(27, &mut expected);
+
+        let mut buffer = Vec::new();
+        assert_eq!(
+            writer.write_value(27i64, &mut buffer).unwrap(),
+            expected.len()
+        );
+        assert_eq!(buffer, expected);
+        assert!(writer.to_vec("foo").is_err());
+    }
+
     type TestResult<T> = Result<T, Box<dyn std::error::Error>>;
 
     fn logical_type_test<T: Into<Value> + Clone>(
@@ -482,6 +746,7 @@ mod tests {
         let inner = Schema::Fixed {
             name: Name::new("decimal"),
             size,
+            custom_attributes: Default::default(),
         };
         let value = vec![0u8; size];
         logical_type_test(
@@ -519,6 +784,7 @@ mod tests {
         let inner = Schema::Fixed {
             name: Name::new("duration"),
             size: 12,
+            custom_attributes: Default::default(),
         };
         let value = Value::Duration(Duration::new(
             Months::new(256),
@@ -636,6 +902,40 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_writer_append_ser_direct() {
+        let schema = Schema::parse_str(SCHEMA).unwrap();
+        let mut writer = Writer::new(&schema, Vec::new());
+
+        let record = TestSerdeSerialize {
+            a: 27,
+            b: "foo".to_owned(),
+        };
+
+        let n1 = writer.append_ser_direct(record).unwrap();
+        // A mismatching value leaves nothing behind.
+        match writer.append_ser_direct(("foo", 27)) {
+            Err(Error::Ser(_)) => {}
+            other => panic!("expected a serialization error, got {:?}", other),
+        }
+        let n2 = writer.flush().unwrap();
+        let result = writer.into_inner().unwrap();
+
+        assert_eq!(n1 + n2, result.len());
+
+        let mut data = Vec::new();
+        zig_i64(27, &mut data);
+        zig_i64(3, &mut data);
+        data.extend(b"foo");
+
+        // ends with the only block, made of the data and the sync marker
+        let last_data_byte = result.len() - 16;
+        assert_eq!(
+            &result[last_data_byte - data.len() - 2..last_data_byte],
+            [&[2, data.len() as u8 * 2][..], &data].concat().as_slice()
+        );
+    }
+
     #[test]
     fn test_writer_extend_ser() {
         let schema = Schema::parse_str(SCHEMA).unwrap();
@@ -726,6 +1026,146 @@ mod tests {
         check_writer(writer, &schema);
     }
 
+    #[test]
+    fn test_writer_with_codec_options() {
+        let schema = Schema::parse_str(SCHEMA).unwrap();
+        let write = |level| {
+            let mut writer = Writer::builder()
+                .writer(Vec::new())
+                .schema(&schema)
+                .codec(Codec::Deflate)
+                .codec_options(CodecOptions::builder().deflate_level(level).build())
+                .build();
+            for a in 0..100 {
+                writer.append(test_util::record(&schema, a % 3)).unwrap();
+            }
+            writer.into_inner().unwrap()
+        };
+
+        let stored = write(0);
+        let compressed = write(9);
+        assert!(compressed.len() < stored.len());
+        let read = |input: &[u8]| {
+            crate::Reader::new(input)
+                .unwrap()
+                .collect::<AvroResult<Vec<_>>>()
+                .unwrap()
+        };
+        assert_eq!(read(&stored).len(), 100);
+        assert_eq!(read(&stored), read(&compressed));
+    }
+
+    #[test]
+    fn test_writer_user_metadata() {
+        let schema = Schema::parse_str(SCHEMA).unwrap();
+        let mut writer = Writer::new(&schema, Vec::new());
+        writer
+            .add_user_metadata("producer".to_string(), "1.2.3")
+            .unwrap();
+        writer
+            .add_user_metadata("lineage".to_string(), [0u8, 1, 2])
+            .unwrap();
+        match writer.add_user_metadata("avro.codec".to_string(), "deflate") {
+            Err(Error::InvalidMetadataKey(key)) => assert_eq!(key, "avro.codec"),
+            other => panic!("expected an invalid key error, got {:?}", other),
+        }
+
+        let mut record = Record::new(&schema).unwrap();
+        record.put("a", 27i64);
+        record.put("b", "foo");
+        writer.append(record).unwrap();
+        match writer.add_user_metadata("partition".to_string(), "3") {
+            Err(Error::FileHeaderAlreadyWritten) => {}
+            other => panic!("expected a header error, got {:?}", other),
+        }
+
+        let input = writer.into_inner().unwrap();
+        let reader = crate::Reader::new(&input[..]).unwrap();
+        let mut expected = HashMap::new();
+        expected.insert("producer".to_string(), b"1.2.3".to_vec());
+        expected.insert("lineage".to_string(), vec![0, 1, 2]);
+        assert_eq!(reader.user_metadata(), &expected);
+        assert_eq!(reader.count(), 1);
+    }
+
+    #[test]
+    fn test_writer_append_to() {
+        let schema = Schema::parse_str(SCHEMA).unwrap();
+        let record = |a| test_util::record(&schema, a);
+
+        let mut writer =
+            Writer::with_codec(&schema, std::io::Cursor::new(Vec::new()), Codec::Deflate);
+        writer.append(record(1)).unwrap();
+        writer.append(record(2)).unwrap();
+        let mut file = writer.into_inner().unwrap();
+
+        file.set_position(0);
+        let other = Schema::parse_str(r#"{"type": "array", "items": "long"}"#).unwrap();
+        match Writer::append_to(&other, &mut file) {
+            Err(Error::FileSchemaMismatch) => {}
+            other => panic!("expected a schema mismatch, got {:?}", other.err()),
+        }
+
+        file.set_position(0);
+        let file_schema = Writer::file_schema(&mut file).unwrap();
+        assert_eq!(file_schema, schema);
+        assert_eq!(file.position(), 0);
+        let mut writer = Writer::append_to(&file_schema, file).unwrap();
+        writer.append(record(3)).unwrap();
+        let input = writer.into_inner().unwrap().into_inner();
+
+        let reader = crate::Reader::new(&input[..]).unwrap();
+        assert_eq!(reader.codec(), Codec::Deflate);
+        let values = reader.collect::<AvroResult<Vec<_>>>().unwrap();
+        let expected = (1..=3).map(record).collect::<Vec<_>>();
+        assert_eq!(values, expected);
+    }
+
+    #[test]
+    fn test_writer_append_blocks() {
+        let schema = Schema::parse_str(SCHEMA).unwrap();
+        let record = |a| test_util::record(&schema, a);
+        let write = |codec, values: std::ops::Range<i64>| {
+            let mut writer = Writer::builder()
+                .schema(&schema)
+                .writer(Vec::new())
+                .codec(codec)
+                .block_size(32)
+                .build();
+            for a in values {
+                writer.append(record(a)).unwrap();
+            }
+            writer.into_inner().unwrap()
+        };
+        let deflate = write(Codec::Deflate, 1..10);
+        let null = write(Codec::Null, 10..20);
+
+        let mut writer = Writer::with_codec(&schema, Vec::new(), Codec::Deflate);
+        writer.append(record(0)).unwrap();
+        let mut blocks = BlockReader::new(&deflate[..]).unwrap();
+        let first = blocks.next().unwrap().unwrap();
+        writer.append_raw_block(&first).unwrap();
+        writer.append_blocks(blocks).unwrap();
+        writer
+            .append_blocks(BlockReader::new(&null[..]).unwrap())
+            .unwrap();
+        writer.append(record(20)).unwrap();
+
+        let other = Schema::parse_str(r#"{"type": "array", "items": "long"}"#).unwrap();
+        let mut other_writer = Writer::new(&other, Vec::new());
+        match other_writer.append_blocks(BlockReader::new(&null[..]).unwrap()) {
+            Err(Error::FileSchemaMismatch) => {}
+            other => panic!("expected a schema mismatch, got {:?}", other),
+        }
+
+        let input = writer.into_inner().unwrap();
+        let values = crate::Reader::new(&input[..])
+            .unwrap()
+            .collect::<AvroResult<Vec<_>>>()
+            .unwrap();
+        assert_eq!(values, (0..=20).map(record).collect::<Vec<_>>());
+    }
+
     #[test]
     fn test_logical_writer() {
         const LOGICAL_TYPE_SCHEMA: &str = r#"
@@ -788,4 +1228,40 @@ mod tests {
             data.as_slice()
         );
     }
+
+    #[test]
+    fn test_writer_header_keeps_custom_attributes() {
+        let raw_schema = r#"
+        {
+          "type": "record",
+          "name": "test",
+          "connect.name": "com.acme.Test",
+          "fields": [
+            {"name": "a", "type": "long", "pii": true},
+            {"name": "b", "type": "long", "logicalType": "custom-timestamp"}
+          ]
+        }
+        "#;
+        let schema = Schema::parse_str(raw_schema).unwrap();
+        let mut writer = Writer::new(&schema, Vec::new());
+        let mut record = Record::new(&schema).unwrap();
+        record.put("a", 27i64);
+        record.put("b", 42i64);
+        writer.append(record).unwrap();
+        let result = writer.into_inner().unwrap();
+
+        let reader = crate::Reader::new(&result[..]).unwrap();
+        let read_schema = reader.writer_schema();
+        assert_eq!(read_schema.custom_attributes(), schema.custom_attributes());
+        match *read_schema {
+            Schema::Record { ref fields, .. } => {
+                assert_eq!(fields[0].custom_attributes["pii"], true);
+                assert_eq!(
+                    fields[1].custom_attributes["logicalType"],
+                    "custom-timestamp"
+                );
+            }
+            _ => panic!("expected a record"),
+        }
+    }
 }
@@ -19,7 +19,17 @@ lazy_static! {
         (r#"{"type": "array", "items": "long"}"#, Value::Array(vec![Value::Long(1), Value::Long(3), Value::Long(2)])),
         (r#"{"type": "map", "values": "long"}"#, Value::Map([("a".to_string(), Value::Long(1i64)), ("b".to_string(), Value::Long(3i64)), ("c".to_string(), Value::Long(2i64))].iter().cloned().collect())),
         (r#"["string", "null", "long"]"#, Value::Union(Box::new(Value::Null))),
-        (r#"{"type": "record", "name": "Test", "fields": [{"name": "f", "type": "long"}]}"#, Value::Record(vec![("f".to_string(), Value::Long(1))]))
+        (r#"{"type": "record", "name": "Test", "fields": [{"name": "f", "type": "long"}]}"#, Value::Record(vec![("f".to_string(), Value::Long(1))])),
+        (
+            r#"{"type": "record", "name": "LongList", "fields": [{"name": "value", "type": "long"}, {"name": "next", "type": ["null", "LongList"]}]}"#,
+            Value::Record(vec![
+                ("value".to_string(), Value::Long(1)),
+                ("next".to_string(), Value::Union(Box::new(Value::Record(vec![
+                    ("value".to_string(), Value::Long(2)),
+                    ("next".to_string(), Value::Union(Box::new(Value::Null))),
+                ])))),
+            ])
+        )
     ];
 
     static ref BINARY_ENCODINGS: Vec<(i64, Vec<u8>)> = vec![
@@ -168,6 +178,26 @@ fn test_unknown_symbol() {
     assert!(decoded.is_err());
 }
 
+#[test]
+fn test_unknown_symbol_with_default() {
+    let writer_schema =
+        Schema::parse_str(r#"{"type": "enum", "name": "Test", "symbols": ["FOO", "BAR"]}"#)
+            .unwrap();
+    let reader_schema = Schema::parse_str(
+        r#"{"type": "enum", "name": "Test", "symbols": ["BAR", "BAZ"], "default": "BAZ"}"#,
+    )
+    .unwrap();
+    let original_value = Value::Enum(0, "FOO".to_string());
+    let encoded = to_avro_datum(&writer_schema, original_value).unwrap();
+    let decoded = from_avro_datum(
+        &writer_schema,
+        &mut Cursor::new(encoded),
+        Some(&reader_schema),
+    )
+    .unwrap();
+    assert_eq!(decoded, Value::Enum(1, "BAZ".to_string()));
+}
+
 #[test]
 fn test_default_value() {
     for (field_type, default_json, default_datum) in DEFAULT_VALUE_EXAMPLES.iter() {
@@ -252,6 +282,36 @@ fn test_projection() {
     assert_eq!(datum_to_read, datum_read);
 }
 
+#[test]
+fn test_projection_with_aliases() {
+    let reader_schema = Schema::parse_str(
+        r#"
+        {
+            "type": "record",
+            "name": "Renamed",
+            "aliases": ["Test"],
+            "fields": [
+                {"name": "e", "type": "int", "aliases": ["E"]},
+                {"name": "F", "type": "int", "aliases": ["G"]}
+            ]
+        }
+    "#,
+    )
+    .unwrap();
+    let datum_to_read = Value::Record(vec![
+        ("e".to_string(), Value::Int(5)),
+        ("F".to_string(), Value::Int(6)),
+    ]);
+    let encoded = to_avro_datum(&LONG_RECORD_SCHEMA, LONG_RECORD_DATUM.clone()).unwrap();
+    let datum_read = from_avro_datum(
+        &LONG_RECORD_SCHEMA,
+        &mut Cursor::new(encoded),
+        Some(&reader_schema),
+    )
+    .unwrap();
+    assert_eq!(datum_to_read, datum_read);
+}
+
 #[test]
 fn test_field_order() {
     let reader_schema = Schema::parse_str(
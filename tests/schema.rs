@@ -127,8 +127,6 @@ lazy_static! {
             true
         ),
         */
-        /*
-        // TODO: (#92) properly support recursive types and uncomment
         (
             r#"{
                 "type": "record",
@@ -176,7 +174,6 @@ lazy_static! {
                 ]
             }"#, true
         ),
-        */
         (
             r#"{
                 "type":"record",
@@ -507,12 +504,6 @@ lazy_static! {
         EXAMPLES.iter().cloned().filter(|s| s.1).collect();
 }
 
-/*
-// TODO: (#92) properly support recursive types and uncomment
-
-This test is failing unwrapping the outer schema with ParseSchemaError("Unknown type: X"). It seems
-that recursive types are not properly supported.
-
 #[test]
 fn test_correct_recursive_extraction() {
     let raw_outer_schema = r#"{
@@ -535,21 +526,98 @@ fn test_correct_recursive_extraction() {
         ]
     }"#;
     let outer_schema = Schema::parse_str(raw_outer_schema).unwrap();
-    if let Schema::Record { fields: outer_fields, .. } = outer_schema {
+    if let Schema::Record {
+        fields: outer_fields,
+        ..
+    } = outer_schema
+    {
         let raw_inner_schema = outer_fields[0].schema.canonical_form();
-        let inner_schema = Schema::parse_str(raw_inner_schema.as_str()).unwrap();
-        if let Schema::Record { fields: inner_fields, .. } = inner_schema {
-            if let Schema::Record {name: recursive_type, .. } = &inner_fields[0].schema {
+        assert_eq!(
+            raw_inner_schema,
+            r#"{"name":"Y","type":"record","fields":[{"name":"Z","type":"X"}]}"#
+        );
+        // Y refers to X, which is defined outside of it, so Y is not self-contained.
+        assert!(Schema::parse_str(raw_inner_schema.as_str()).is_err());
+        if let Schema::Record {
+            fields: inner_fields,
+            ..
+        } = &outer_fields[0].schema
+        {
+            if let Schema::Ref {
+                name: recursive_type,
+            } = &inner_fields[0].schema
+            {
                 assert_eq!("X", recursive_type.name.as_str());
+            } else {
+                panic!("inner field should have been a reference to X")
             }
         } else {
-            panic!("inner schema {} should have been a record", raw_inner_schema)
+            panic!(
+                "inner schema {} should have been a record",
+                raw_inner_schema
+            )
         }
     } else {
-        panic!("outer schema {} should have been a record", raw_outer_schema)
+        panic!(
+            "outer schema {} should have been a record",
+            raw_outer_schema
+        )
     }
 }
-*/
+
+#[test]
+fn test_duplicate_named_type() {
+    let raw_schema = r#"{
+        "type": "record",
+        "name": "test",
+        "fields": [
+            {"name": "a", "type": {"type": "fixed", "name": "md5", "size": 16}},
+            {"name": "b", "type": {"type": "enum", "name": "md5", "symbols": ["A"]}}
+        ]
+    }"#;
+    assert!(Schema::parse_str(raw_schema).is_err());
+
+    let raw_schema = r#"{
+        "type": "record",
+        "name": "test",
+        "fields": [{"name": "a", "type": {"type": "record", "name": "test", "fields": []}}]
+    }"#;
+    assert!(Schema::parse_str(raw_schema).is_err());
+}
+
+#[test]
+fn test_unknown_named_type_reference() {
+    let raw_schema = r#"{
+        "type": "record",
+        "name": "test",
+        "fields": [{"name": "a", "type": "Unknown"}]
+    }"#;
+    assert!(Schema::parse_str(raw_schema).is_err());
+}
+
+#[test]
+fn test_parse_list_mutually_recursive_schemas() {
+    let schema_a = r#"{
+        "type": "record",
+        "name": "A",
+        "fields": [{"name": "b", "type": ["null", "B"]}]
+    }"#;
+    let schema_b = r#"{
+        "type": "record",
+        "name": "B",
+        "fields": [{"name": "a", "type": ["null", "A"]}]
+    }"#;
+
+    let schemas = Schema::parse_list(&[schema_a, schema_b]).unwrap();
+    assert_eq!(
+        schemas[0].canonical_form(),
+        r#"{"name":"A","type":"record","fields":[{"name":"b","type":["null",{"name":"B","type":"record","fields":[{"name":"a","type":["null","A"]}]}]}]}"#
+    );
+    assert_eq!(
+        schemas[1].canonical_form(),
+        r#"{"name":"B","type":"record","fields":[{"name":"a","type":["null",{"name":"A","type":"record","fields":[{"name":"b","type":["null","B"]}]}]}]}"#
+    );
+}
 
 #[test]
 fn test_parse() {
@@ -691,39 +759,40 @@ fn test_doc_attributes() {
     }
 }
 
-/*
-TODO: (#94) add support for user-defined attributes and uncomment (may need some tweaks to compile)
 #[test]
 fn test_other_attributes() {
-    fn assert_attribute_type(attribute: (String, serde_json::Value)) {
-        match attribute.1.as_ref() {
-            "cp_boolean" => assert!(attribute.2.is_bool()),
-            "cp_int" => assert!(attribute.2.is_i64()),
-            "cp_object" => assert!(attribute.2.is_object()),
-            "cp_float" => assert!(attribute.2.is_f64()),
-            "cp_array" => assert!(attribute.2.is_array()),
+    fn assert_attribute_type(attribute: (&String, &serde_json::Value)) {
+        match attribute.0.as_ref() {
+            "cp_string" => assert!(attribute.1.is_string()),
+            "cp_boolean" => assert!(attribute.1.is_boolean()),
+            "cp_int" => assert!(attribute.1.is_i64()),
+            "cp_object" => assert!(attribute.1.is_object()),
+            "cp_float" => assert!(attribute.1.is_f64()),
+            "cp_array" => assert!(attribute.1.is_array()),
+            "cp_null" => assert!(attribute.1.is_null()),
+            "date" => assert!(attribute.1.is_string()),
+            other => panic!("unexpected attribute {}", other),
         }
     }
 
     for (raw_schema, _) in OTHER_ATTRIBUTES_EXAMPLES.iter() {
         let schema = Schema::parse_str(raw_schema).unwrap();
-        // all inputs have at least some user-defined attributes
-        assert!(schema.other_attributes.is_some());
-        for prop in schema.other_attributes.unwrap().iter() {
+        let attributes = schema.custom_attributes().unwrap();
+        assert!(!attributes.is_empty());
+        for prop in attributes.iter() {
             assert_attribute_type(prop);
         }
         if let Schema::Record { fields, .. } = schema {
-           for f in fields {
-               // all fields in the record have at least some user-defined attributes
-               assert!(f.schema.other_attributes.is_some());
-               for prop in f.schema.other_attributes.unwrap().iter() {
-                   assert_attribute_type(prop);
-               }
-           }
+            for f in fields {
+                // all fields in the record have at least some user-defined attributes
+                assert!(!f.custom_attributes.is_empty());
+                for prop in f.custom_attributes.iter() {
+                    assert_attribute_type(prop);
+                }
+            }
         }
     }
 }
-*/
 
 #[test]
 fn test_root_error_is_not_swallowed_on_parse_error() -> Result<(), String> {